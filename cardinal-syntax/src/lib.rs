@@ -17,6 +17,9 @@
 //! }
 //! ```
 
+mod macros;
+
+pub use macros::{MACRO_PARAM, MacroError, MacroRegistry};
 use std::fmt;
 
 /// Parses an Everything-like query string into a structured expression tree.
@@ -390,6 +393,27 @@ pub enum FilterKind {
     /// assert!(matches!(filter.kind, FilterKind::CaseSensitive));
     /// ```
     CaseSensitive,
+    /// Case-insensitive search toggle (`nocase:`).
+    /// ```
+    /// use cardinal_syntax::{parse_query, Expr, Term, FilterKind};
+    /// let Expr::Term(Term::Filter(filter)) = parse_query("nocase:readme").unwrap().expr else { panic!() };
+    /// assert!(matches!(filter.kind, FilterKind::NoCase));
+    /// ```
+    NoCase,
+    /// Match at word boundaries inside names (`wholeword:`, `ww:`).
+    /// ```
+    /// use cardinal_syntax::{parse_query, Expr, Term, FilterKind};
+    /// let Expr::Term(Term::Filter(filter)) = parse_query("ww:report").unwrap().expr else { panic!() };
+    /// assert!(matches!(filter.kind, FilterKind::WholeWord));
+    /// ```
+    WholeWord,
+    /// Match the term as initials or camel-case humps (`acronym:`, `ac:`).
+    /// ```
+    /// use cardinal_syntax::{parse_query, Expr, Term, FilterKind};
+    /// let Expr::Term(Term::Filter(filter)) = parse_query("ac:fbs").unwrap().expr else { panic!() };
+    /// assert!(matches!(filter.kind, FilterKind::Acronym));
+    /// ```
+    Acronym,
     /// Content search (`content:`).
     /// ```
     /// use cardinal_syntax::{parse_query, Expr, Term, FilterKind};
@@ -453,6 +477,9 @@ impl FilterKind {
             "orientation" => FilterKind::Orientation,
             "bitdepth" => FilterKind::BitDepth,
             "case" => FilterKind::CaseSensitive,
+            "nocase" => FilterKind::NoCase,
+            "wholeword" | "ww" => FilterKind::WholeWord,
+            "acronym" | "ac" => FilterKind::Acronym,
             "content" => FilterKind::Content,
             "nowholefilename" => FilterKind::NoWholeFilename,
             _ => FilterKind::Custom(name.to_string()),
@@ -898,7 +925,34 @@ impl<'a> Parser<'a> {
             return Ok(None);
         }
 
-        if self.starts_with_filter_token() {
+        // Modifiers such as `case:` wrap the term that follows, which may be
+        // another modifier (`case:ww:readme`).
+        let is_modifier = matches!(
+            kind,
+            FilterKind::CaseSensitive
+                | FilterKind::NoCase
+                | FilterKind::WholeWord
+                | FilterKind::Acronym
+                | FilterKind::NoWholeFilename
+        );
+
+        // `child:regex:<pattern>`, `content:regex:<pattern>` and modifiers
+        // match by regex rather than starting a separate regex term.
+        if (is_modifier || matches!(kind, FilterKind::Child | FilterKind::Content))
+            && self
+                .remaining()
+                .get(..REGEX_PREFIX.len())
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case(REGEX_PREFIX))
+        {
+            self.pos += REGEX_PREFIX.len();
+            let pattern = self.parse_regex_pattern()?;
+            return Ok(Some(FilterArgument {
+                raw: format!("{REGEX_PREFIX}{pattern}"),
+                kind: ArgumentKind::Bare,
+            }));
+        }
+
+        if !is_modifier && self.starts_with_filter_token() {
             return Ok(None);
         }
 
@@ -1058,6 +1112,8 @@ impl<'a> Parser<'a> {
     }
 }
 
+const REGEX_PREFIX: &str = "regex:";
+
 fn is_term_breaker(ch: char) -> bool {
     ch.is_whitespace() || matches!(ch, '|' | '<' | '>' | '(' | ')' | '!')
 }
@@ -1072,8 +1128,10 @@ fn is_keyword_boundary_char(ch: char) -> bool {
         || ch == '\0'
 }
 
+// Names must start with a letter so date-time literals such as
+// `dm:2024-05-01T10:30` aren't mistaken for a `2024-05-01T10:` filter.
 fn is_valid_filter_name(name: &str) -> bool {
-    !name.is_empty()
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
         && name
             .chars()
             .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
@@ -0,0 +1,266 @@
+//! User-defined filter macros (Everything calls them "filters" or
+//! "bookmarks"): `proj:` can stand for `infolder:~/work ext:rs;toml !target`.
+//!
+//! Macros are expanded on the parsed AST, so an expansion always behaves like
+//! a parenthesized group no matter where it is used. An expansion may refer to
+//! its argument with `$param:`; `proj:foo` then substitutes `foo` there. When
+//! there is no placeholder, the argument is ANDed with the expansion as a plain
+//! word, so `pics:beach` finds pictures named like "beach".
+
+use crate::{ArgumentKind, Expr, Filter, FilterKind, ParseError, Query, Term, parse_query};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+use toml::Spanned;
+
+/// Placeholder replaced by the macro argument.
+pub const MACRO_PARAM: &str = "$param:";
+
+/// The part of a config file [`MacroRegistry::from_toml_str`] reads.
+#[derive(Deserialize)]
+struct MacroFile {
+    #[serde(default)]
+    macros: BTreeMap<String, Spanned<String>>,
+}
+
+#[derive(Serialize)]
+struct MacroTable<'a> {
+    macros: &'a BTreeMap<String, String>,
+}
+
+/// Named query fragments that [`MacroRegistry::expand`] splices into a query.
+///
+/// Names are case-insensitive and can't shadow built-in filters.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MacroRegistry {
+    macros: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroError {
+    /// The name isn't a valid filter name or collides with a built-in filter.
+    InvalidName(String),
+    /// The expansion (after substituting the argument) doesn't parse.
+    Parse { name: String, error: ParseError },
+    /// Expanding the macro would expand it again. Holds the chain of macro
+    /// names, ending with the one that repeats.
+    Recursive(Vec<String>),
+    /// The macro file isn't valid.
+    Config { line: usize, message: String },
+}
+
+impl fmt::Display for MacroError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MacroError::InvalidName(name) => write!(f, "invalid macro name {name:?}"),
+            MacroError::Parse { name, error } => write!(f, "macro {name}: {error}"),
+            MacroError::Recursive(chain) => {
+                write!(f, "recursive macro expansion: {}", chain.join(": -> "))?;
+                f.write_str(":")
+            }
+            MacroError::Config { line, message } => write!(f, "{message} (at line {line})"),
+        }
+    }
+}
+
+impl std::error::Error for MacroError {}
+
+impl MacroRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the `[macros]` table of a TOML document:
+    ///
+    /// ```
+    /// use cardinal_syntax::MacroRegistry;
+    ///
+    /// let registry = MacroRegistry::from_toml_str(r#"
+    /// [macros]
+    /// proj = "infolder:~/work ext:rs;toml !target"
+    /// pics = 'ext:jpg;png;gif'
+    /// "#).unwrap();
+    /// assert_eq!(registry.get("PICS"), Some("ext:jpg;png;gif"));
+    /// ```
+    ///
+    /// Other tables are ignored, so the macros can live in a larger config file.
+    pub fn from_toml_str(input: &str) -> Result<Self, MacroError> {
+        let line_of = |offset: usize| input[..offset].matches('\n').count() + 1;
+        let file: MacroFile = toml::from_str(input).map_err(|err| MacroError::Config {
+            line: err.span().map_or(1, |span| line_of(span.start)),
+            message: err.message().to_string(),
+        })?;
+        // In file order, so a repeated name is reported where it repeats.
+        let mut macros: Vec<_> = file.macros.into_iter().collect();
+        macros.sort_by_key(|(_, expansion)| expansion.span().start);
+
+        let mut registry = Self::new();
+        for (name, expansion) in macros {
+            let config_error = |message: String| MacroError::Config {
+                line: line_of(expansion.span().start),
+                message,
+            };
+            if registry.get(&name).is_some() {
+                return Err(config_error(format!("macro {name:?} is defined twice")));
+            }
+            registry
+                .insert(&name, expansion.get_ref().as_str())
+                .map_err(|err| config_error(err.to_string()))?;
+        }
+        Ok(registry)
+    }
+
+    /// Serializes the registry as a `[macros]` table that
+    /// [`Self::from_toml_str`] reads back.
+    pub fn to_toml_string(&self) -> String {
+        toml::to_string(&MacroTable {
+            macros: &self.macros,
+        })
+        .expect("macros serialize as a table of strings")
+    }
+
+    /// Adds or replaces a macro, returning the previous expansion.
+    pub fn insert(
+        &mut self,
+        name: &str,
+        expansion: impl Into<String>,
+    ) -> Result<Option<String>, MacroError> {
+        if !is_valid_macro_name(name) {
+            return Err(MacroError::InvalidName(name.to_string()));
+        }
+        let expansion = expansion.into();
+        // Catch typos up front; the argument can't change whether it parses.
+        parse_query(&expansion.replace(MACRO_PARAM, "x")).map_err(|error| MacroError::Parse {
+            name: name.to_string(),
+            error,
+        })?;
+        Ok(self.macros.insert(name.to_ascii_lowercase(), expansion))
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.macros.remove(&name.to_ascii_lowercase())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.macros
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Macros ordered by their lowercase name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.macros
+            .iter()
+            .map(|(name, expansion)| (name.as_str(), expansion.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.macros.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.macros.is_empty()
+    }
+
+    /// Replaces every known macro in `query` with its expansion, recursively.
+    /// Unknown custom filters are left alone.
+    ///
+    /// ```
+    /// use cardinal_syntax::{MacroRegistry, parse_query};
+    ///
+    /// let mut registry = MacroRegistry::new();
+    /// registry.insert("src", "infolder:$param: ext:rs").unwrap();
+    /// let expanded = registry.expand(parse_query("src:core main").unwrap()).unwrap();
+    /// assert_eq!(expanded, parse_query("<infolder:core ext:rs> main").unwrap());
+    /// ```
+    pub fn expand(&self, query: Query) -> Result<Query, MacroError> {
+        if self.is_empty() {
+            return Ok(query);
+        }
+        let mut active = Vec::new();
+        Ok(Query {
+            expr: self.expand_expr(query.expr, &mut active)?,
+        })
+    }
+
+    fn expand_expr(&self, expr: Expr, active: &mut Vec<String>) -> Result<Expr, MacroError> {
+        Ok(match expr {
+            Expr::Term(Term::Filter(filter)) => self.expand_filter(filter, active)?,
+            Expr::Not(inner) => Expr::Not(Box::new(self.expand_expr(*inner, active)?)),
+            Expr::And(parts) => Expr::And(self.expand_all(parts, active)?),
+            Expr::Or(parts) => Expr::Or(self.expand_all(parts, active)?),
+            expr => expr,
+        })
+    }
+
+    fn expand_all(
+        &self,
+        parts: Vec<Expr>,
+        active: &mut Vec<String>,
+    ) -> Result<Vec<Expr>, MacroError> {
+        parts
+            .into_iter()
+            .map(|part| self.expand_expr(part, active))
+            .collect()
+    }
+
+    fn expand_filter(&self, filter: Filter, active: &mut Vec<String>) -> Result<Expr, MacroError> {
+        let FilterKind::Custom(name) = &filter.kind else {
+            return Ok(Expr::Term(Term::Filter(filter)));
+        };
+        let key = name.to_ascii_lowercase();
+        let Some(expansion) = self.macros.get(&key) else {
+            return Ok(Expr::Term(Term::Filter(filter)));
+        };
+        if active.contains(&key) {
+            let mut chain = active.clone();
+            chain.push(key);
+            return Err(MacroError::Recursive(chain));
+        }
+
+        let argument = filter.argument.as_ref();
+        let text = match argument {
+            Some(argument) if expansion.contains(MACRO_PARAM) => {
+                let value = match argument.kind {
+                    ArgumentKind::Phrase => format!("\"{}\"", argument.raw),
+                    _ => argument.raw.clone(),
+                };
+                expansion.replace(MACRO_PARAM, &value)
+            }
+            _ => expansion.replace(MACRO_PARAM, ""),
+        };
+        let parsed = parse_query(&text).map_err(|error| MacroError::Parse {
+            name: name.clone(),
+            error,
+        })?;
+
+        active.push(key);
+        let expanded = self.expand_expr(parsed.expr, active);
+        active.pop();
+        let expanded = expanded?;
+
+        Ok(match argument {
+            Some(argument) if !expansion.contains(MACRO_PARAM) => {
+                let term = match argument.kind {
+                    ArgumentKind::Phrase => Term::Phrase(argument.raw.clone()),
+                    _ => Term::Word(argument.raw.clone()),
+                };
+                match expanded {
+                    Expr::Empty => Expr::Term(term),
+                    Expr::And(mut parts) => {
+                        parts.push(Expr::Term(term));
+                        Expr::And(parts)
+                    }
+                    expanded => Expr::And(vec![expanded, Expr::Term(term)]),
+                }
+            }
+            _ => expanded,
+        })
+    }
+}
+
+/// Macro names follow filter names and must not collide with built-ins.
+fn is_valid_macro_name(name: &str) -> bool {
+    crate::is_valid_filter_name(name)
+        && !name.eq_ignore_ascii_case("regex")
+        && matches!(FilterKind::from_name(name), FilterKind::Custom(_))
+}
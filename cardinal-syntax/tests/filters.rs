@@ -17,6 +17,11 @@ fn recognized_filter_kinds_without_arguments() {
         ("namepartdupe:", FilterKind::NamePartDuplicate),
         ("sizedupe:", FilterKind::SizeDuplicate),
         ("nowholefilename:", FilterKind::NoWholeFilename),
+        ("nocase:", FilterKind::NoCase),
+        ("wholeword:", FilterKind::WholeWord),
+        ("ww:", FilterKind::WholeWord),
+        ("acronym:", FilterKind::Acronym),
+        ("ac:", FilterKind::Acronym),
     ];
 
     for (q, kind) in cases {
@@ -0,0 +1,219 @@
+mod common;
+use cardinal_syntax::*;
+use common::*;
+
+fn registry(macros: &[(&str, &str)]) -> MacroRegistry {
+    let mut registry = MacroRegistry::new();
+    for (name, expansion) in macros {
+        registry.insert(name, *expansion).unwrap();
+    }
+    registry
+}
+
+fn expand(registry: &MacroRegistry, input: &str) -> Result<Expr, MacroError> {
+    registry
+        .expand(parse_query(input).unwrap())
+        .map(|query| query.expr)
+}
+
+#[test]
+fn macro_without_argument_expands_in_place() {
+    let registry = registry(&[("pics", "ext:jpg;png;gif")]);
+    let expr = expand(&registry, "beach pics:").unwrap();
+    let parts = as_and(&expr);
+    word_is(&parts[0], "beach");
+    filter_is_kind(&parts[1], &FilterKind::Ext);
+    filter_arg_is_list(&parts[1], &["jpg", "png", "gif"]);
+}
+
+#[test]
+fn macro_expansion_keeps_its_own_grouping() {
+    let registry = registry(&[("docs", "ext:pdf | ext:docx")]);
+    let expr = expand(&registry, "report !docs:").unwrap();
+    let parts = as_and(&expr);
+    word_is(&parts[0], "report");
+    let alternatives = as_or(as_not(&parts[1]));
+    assert_eq!(alternatives.len(), 2);
+}
+
+#[test]
+fn macro_names_are_case_insensitive() {
+    let registry = registry(&[("Proj", "infolder:~/work")]);
+    assert_eq!(registry.get("proj"), Some("infolder:~/work"));
+    let expr = expand(&registry, "PROJ:").unwrap();
+    filter_is_kind(&expr, &FilterKind::InFolder);
+    filter_arg_raw(&expr, "~/work");
+}
+
+#[test]
+fn parameter_is_substituted() {
+    let registry = registry(&[("proj", "infolder:~/work/$param: ext:rs;toml !target")]);
+    let expr = expand(&registry, "proj:cardinal").unwrap();
+    let parts = as_and(&expr);
+    filter_arg_raw(&parts[0], "~/work/cardinal");
+    filter_arg_is_list(&parts[1], &["rs", "toml"]);
+    word_is(as_not(&parts[2]), "target");
+
+    // Quoted arguments stay quoted.
+    let registry = self::registry(&[("in", "parent:$param:")]);
+    let expr = expand(&registry, "in:\"My Documents\"").unwrap();
+    filter_arg_raw(&expr, "My Documents");
+    assert!(matches!(
+        filter_kind(&expr).1.as_ref().unwrap().kind,
+        ArgumentKind::Phrase
+    ));
+}
+
+#[test]
+fn argument_without_placeholder_is_anded() {
+    let registry = registry(&[("pics", "ext:jpg;png")]);
+    let expr = expand(&registry, "pics:beach").unwrap();
+    let parts = as_and(&expr);
+    filter_is_kind(&parts[0], &FilterKind::Ext);
+    word_is(&parts[1], "beach");
+
+    let registry = self::registry(&[("recent", "dm:thisweek file:")]);
+    let expr = expand(&registry, "recent:\"tax return\"").unwrap();
+    let parts = as_and(&expr);
+    assert_eq!(parts.len(), 3);
+    phrase_is(&parts[2], "tax return");
+}
+
+#[test]
+fn missing_argument_leaves_placeholder_empty() {
+    let registry = registry(&[("in", "parent:$param:")]);
+    let expr = expand(&registry, "in:").unwrap();
+    filter_is_kind(&expr, &FilterKind::Parent);
+    filter_arg_none(&expr);
+}
+
+#[test]
+fn nested_macros_expand() {
+    let registry = registry(&[
+        ("pics", "ext:jpg;png"),
+        ("wallpapers", "pics: width:>=1920"),
+    ]);
+    let expr = expand(&registry, "wallpapers:").unwrap();
+    let parts = as_and(&expr);
+    filter_is_kind(&parts[0], &FilterKind::Ext);
+    filter_is_kind(&parts[1], &FilterKind::Width);
+
+    // The same macro can appear twice as long as it doesn't contain itself.
+    let expr = expand(&registry, "pics: | wallpapers:").unwrap();
+    assert_eq!(as_or(&expr).len(), 2);
+}
+
+#[test]
+fn recursive_macros_are_rejected() {
+    let registry = registry(&[("loop", "loop: foo")]);
+    assert_eq!(
+        expand(&registry, "loop:").unwrap_err(),
+        MacroError::Recursive(vec!["loop".into(), "loop".into()])
+    );
+
+    let registry = self::registry(&[("a", "b: | x"), ("b", "<c:>"), ("c", "!a:")]);
+    let err = expand(&registry, "y a:").unwrap_err();
+    assert_eq!(
+        err,
+        MacroError::Recursive(vec!["a".into(), "b".into(), "c".into(), "a".into()])
+    );
+    assert_eq!(
+        err.to_string(),
+        "recursive macro expansion: a: -> b: -> c: -> a:"
+    );
+}
+
+#[test]
+fn unknown_custom_filters_are_kept() {
+    let registry = registry(&[("pics", "ext:jpg")]);
+    let expr = expand(&registry, "tag:red").unwrap();
+    filter_is_custom(&expr, "tag");
+    filter_arg_raw(&expr, "red");
+}
+
+#[test]
+fn invalid_macros_are_rejected() {
+    let mut registry = MacroRegistry::new();
+    for name in ["ext", "EXT", "dm", "regex", "9lives", "", "a b"] {
+        assert!(
+            matches!(
+                registry.insert(name, "foo"),
+                Err(MacroError::InvalidName(_))
+            ),
+            "{name:?} should be rejected"
+        );
+    }
+    assert!(matches!(
+        registry.insert("broken", "(foo"),
+        Err(MacroError::Parse { .. })
+    ));
+    assert_eq!(registry.insert("docs", "ext:pdf"), Ok(None));
+    assert_eq!(
+        registry.insert("docs", "ext:docx"),
+        Ok(Some("ext:pdf".to_string()))
+    );
+    assert_eq!(registry.remove("DOCS"), Some("ext:docx".to_string()));
+    assert!(registry.is_empty());
+}
+
+#[test]
+fn loads_macros_from_toml() {
+    let registry = MacroRegistry::from_toml_str(
+        r#"
+# Cardinal settings
+[ui]
+theme = "dark"
+
+[macros]
+proj = "infolder:~/work ext:rs;toml !target" # trailing comment
+pics = 'ext:jpg;png;gif'
+"tagged-doc" = "ext:pdf \"to read\""
+notes = "ext:md readme"
+
+[other]
+proj = "ignored"
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        registry.iter().collect::<Vec<_>>(),
+        [
+            ("notes", "ext:md readme"),
+            ("pics", "ext:jpg;png;gif"),
+            ("proj", "infolder:~/work ext:rs;toml !target"),
+            ("tagged-doc", "ext:pdf \"to read\""),
+        ]
+    );
+}
+
+#[test]
+fn invalid_toml_reports_the_line() {
+    let cases = [
+        ("[macros]\nproj infolder:~", 2),
+        ("[macros]\n\nproj = \"unterminated", 3),
+        ("[macros]\nproj = \"a\" b", 2),
+        ("[macros]\next = \"foo\"", 2),
+        ("[macros]\nproj = \"(a\"", 2),
+        ("[macros]\nproj = \"a\"\nPROJ = \"b\"", 3),
+        ("[macros\nproj = \"a\"", 1),
+    ];
+    for (input, expected_line) in cases {
+        match MacroRegistry::from_toml_str(input) {
+            Err(MacroError::Config { line, .. }) => assert_eq!(line, expected_line, "{input:?}"),
+            other => panic!("expected a config error for {input:?}, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn toml_round_trips() {
+    let registry = registry(&[
+        ("proj", "infolder:~/work ext:rs;toml !target"),
+        ("quoted", r#"parent:"C:\Users\demo" 'notes'"#),
+        ("tagged-doc", "ext:pdf\t\"to read\""),
+    ]);
+    let toml = registry.to_toml_string();
+    assert!(toml.starts_with("[macros]\n"));
+    assert_eq!(MacroRegistry::from_toml_str(&toml).unwrap(), registry);
+    assert_eq!(MacroRegistry::new().to_toml_string(), "[macros]\n");
+}
@@ -33,6 +33,17 @@ fn hyphenated_ranges_for_dates() {
     filter_arg_is_range_dots(&expr, Some("2023-01-01"), Some("2023-12-31"));
 }
 
+#[test]
+fn date_time_literals_are_not_filter_names() {
+    let expr = parse_ok("dm:2024-05-01T10:30");
+    filter_is_kind(&expr, &FilterKind::DateModified);
+    filter_arg_raw(&expr, "2024-05-01T10:30");
+
+    let expr = parse_ok("dc:>2024-05-01T10:30:15");
+    filter_is_kind(&expr, &FilterKind::DateCreated);
+    filter_arg_is_comparison(&expr, ComparisonOp::Gt, "2024-05-01T10:30:15");
+}
+
 #[test]
 fn comparisons_are_detected() {
     let expr = parse_ok("size:>1GB");
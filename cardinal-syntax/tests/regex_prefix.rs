@@ -50,3 +50,42 @@ fn regex_term_supports_quoted_patterns() {
     let expr = parse_ok(r#"regex:"foo bar|baz""#);
     regex_is(&expr, "foo bar|baz");
 }
+
+#[test]
+fn regex_prefix_becomes_child_filter_argument() {
+    let expr = parse_ok(r"child:regex:^Cargo\.toml$ foo");
+    let parts = as_and(&expr);
+    assert_eq!(parts.len(), 2);
+    filter_is_kind(&parts[0], &FilterKind::Child);
+    filter_arg_raw(&parts[0], r"regex:^Cargo\.toml$");
+    word_is(&parts[1], "foo");
+
+    let expr = parse_ok("ext:regex:foo");
+    let parts = as_and(&expr);
+    filter_arg_none(&parts[0]);
+    regex_is(&parts[1], "foo");
+}
+
+#[test]
+fn regex_prefix_becomes_content_filter_argument() {
+    let expr = parse_ok(r"ext:rs content:regex:unsafe\s*\{");
+    let parts = as_and(&expr);
+    assert_eq!(parts.len(), 2);
+    filter_is_kind(&parts[1], &FilterKind::Content);
+    filter_arg_raw(&parts[1], r"regex:unsafe\s*\{");
+}
+
+#[test]
+fn regex_prefix_becomes_modifier_argument() {
+    let expr = parse_ok("case:regex:^README nocase:todo");
+    let parts = as_and(&expr);
+    assert_eq!(parts.len(), 2);
+    filter_is_kind(&parts[0], &FilterKind::CaseSensitive);
+    filter_arg_raw(&parts[0], "regex:^README");
+    filter_is_kind(&parts[1], &FilterKind::NoCase);
+    filter_arg_raw(&parts[1], "todo");
+
+    let expr = parse_ok("case:ww:readme");
+    filter_is_kind(&expr, &FilterKind::CaseSensitive);
+    filter_arg_raw(&expr, "ww:readme");
+}
@@ -1,5 +1,5 @@
 use crate::{
-    commands::SearchJob,
+    commands::{NodeInfoRequest, SearchJob},
     lifecycle::{AppLifecycleState, load_app_state, update_app_state},
 };
 use anyhow::Result;
@@ -7,7 +7,10 @@ use base64::{Engine as _, engine::general_purpose};
 use cardinal_sdk::{EventFlag, EventWatcher};
 use crossbeam_channel::{Receiver, Sender};
 use rayon::spawn;
-use search_cache::{HandleFSEError, SearchCache, SearchOptions, SearchResultNode, SlabIndex};
+use search_cache::{
+    HandleFSEError, MacroRegistry, ResultDetails, SearchCache, SearchOptions, SearchPage,
+    SearchResultNode, SlabIndex,
+};
 use serde::Serialize;
 use std::{
     path::PathBuf,
@@ -34,11 +37,12 @@ pub struct IconPayload {
 pub struct BackgroundLoopChannels {
     pub finish_rx: Receiver<Sender<Option<SearchCache>>>,
     pub search_rx: Receiver<SearchJob>,
-    pub result_tx: Sender<Result<Option<Vec<SlabIndex>>>>,
-    pub node_info_rx: Receiver<Vec<SlabIndex>>,
+    pub result_tx: Sender<Result<Option<SearchPage>>>,
+    pub node_info_rx: Receiver<NodeInfoRequest>,
     pub node_info_results_tx: Sender<Vec<SearchResultNode>>,
     pub icon_viewport_rx: Receiver<(u64, Vec<SlabIndex>)>,
     pub rescan_rx: Receiver<()>,
+    pub macros_rx: Receiver<MacroRegistry>,
     pub icon_update_tx: Sender<IconPayload>,
 }
 
@@ -90,6 +94,7 @@ pub fn run_background_event_loop(
         node_info_results_tx,
         icon_viewport_rx,
         rescan_rx,
+        macros_rx,
         icon_update_tx,
     } = channels;
     let mut processed_events = 0usize;
@@ -108,26 +113,29 @@ pub fn run_background_event_loop(
                     cancellation_token,
                 } = job.expect("Search channel closed");
                 let opts = SearchOptions::from(options);
-                let result = if query.is_empty() {
-                    Ok(cache.search_empty(cancellation_token))
-                } else {
-                    cache.search_with_options(
-                        &query,
-                        opts,
-                        cancellation_token,
-                    )
-                };
+                let result = cache.search_page(
+                    &query,
+                    opts,
+                    cancellation_token,
+                );
                 result_tx.send(result).expect("Failed to send result");
             }
-            recv(node_info_rx) -> results => {
-                let results = results.expect("Node info channel closed");
-                let node_info_results = cache.expand_file_nodes(&results);
+            recv(node_info_rx) -> request => {
+                let NodeInfoRequest { results, search } = request.expect("Node info channel closed");
+                // A query that no longer parses, e.g. after its macros
+                // changed, only loses the details.
+                let details = search
+                    .and_then(|(query, options)| {
+                        cache.result_details(&query, SearchOptions::from(options)).ok()
+                    })
+                    .unwrap_or_default();
+                let node_info_results = cache.expand_file_nodes(&results, &details);
                 node_info_results_tx.send(node_info_results).expect("Failed to send node info results");
             }
             recv(icon_viewport_rx) -> update => {
                 let (_request_id, viewport) = update.expect("Icon viewport channel closed");
 
-                let nodes = cache.expand_file_nodes(&viewport);
+                let nodes = cache.expand_file_nodes(&viewport, &ResultDetails::default());
                 let icon_jobs: Vec<_> = viewport
                     .into_iter()
                     .zip(nodes.into_iter())
@@ -166,6 +174,10 @@ pub fn run_background_event_loop(
                     &mut history_ready,
                 );
             }
+            recv(macros_rx) -> macros => {
+                *cache.macros_mut() = macros.expect("Macros channel closed");
+                info!("Search macros updated: {} defined", cache.macros().len());
+            }
             recv(event_watcher) -> events => {
                 let events = events.expect("Event stream closed");
                 processed_events += events.len();
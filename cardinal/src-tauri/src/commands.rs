@@ -1,14 +1,18 @@
 use crate::{
-    LOGIC_START,
+    LOGIC_START, MACROS_PATH,
     lifecycle::{EXIT_REQUESTED, load_app_state},
 };
 use anyhow::Result;
 use base64::{Engine as _, engine::general_purpose};
 use crossbeam_channel::{Receiver, Sender};
-use search_cache::{SearchOptions, SearchResultNode, SlabIndex, SlabNodeMetadata};
+use parking_lot::Mutex;
+use search_cache::{
+    MacroRegistry, MatchRange, SearchOptions, SearchPage, SearchResultNode, SlabIndex,
+    SlabNodeMetadata, SortSpec,
+};
 use search_cancel::CancellationToken;
 use serde::{Deserialize, Serialize};
-use std::{process::Command, sync::atomic::Ordering};
+use std::{collections::BTreeMap, fs, process::Command, sync::atomic::Ordering};
 use tauri::{AppHandle, State};
 use tracing::info;
 
@@ -19,6 +23,25 @@ pub struct SearchOptionsPayload {
     pub use_regex: bool,
     #[serde(default)]
     pub case_insensitive: bool,
+    #[serde(default)]
+    pub fuzzy: bool,
+    #[serde(default)]
+    pub acronym: bool,
+    #[serde(default)]
+    pub ignore_diacritics: bool,
+    /// Compute [`NodeInfo::highlights`] for the results.
+    #[serde(default)]
+    pub highlight: bool,
+    /// e.g. `{ "key": "dateModified", "direction": "descending" }`.
+    #[serde(default)]
+    pub sort: Option<SortSpec>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: usize,
+    /// Only count the matches, for the status bar.
+    #[serde(default)]
+    pub count_only: bool,
 }
 
 impl From<SearchOptionsPayload> for SearchOptions {
@@ -26,15 +49,60 @@ impl From<SearchOptionsPayload> for SearchOptions {
         SearchOptionsPayload {
             use_regex,
             case_insensitive,
+            fuzzy,
+            acronym,
+            ignore_diacritics,
+            highlight,
+            sort,
+            limit,
+            offset,
+            count_only,
         }: SearchOptionsPayload,
     ) -> Self {
         SearchOptions {
             use_regex,
             case_insensitive,
+            fuzzy,
+            acronym,
+            ignore_diacritics,
+            highlight,
+            sort,
+            limit,
+            offset,
+            count_only,
         }
     }
 }
 
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResponse {
+    pub results: Vec<SlabIndex>,
+    /// Matches outside of the requested window included.
+    pub total: usize,
+    /// `false` when the search stopped after filling the window, in which case
+    /// `total` is a lower bound.
+    pub total_is_exact: bool,
+}
+
+impl From<SearchPage> for SearchResponse {
+    fn from(SearchPage { nodes, total }: SearchPage) -> Self {
+        Self {
+            results: nodes,
+            total: total.count(),
+            total_is_exact: total.is_exact(),
+        }
+    }
+}
+
+/// Nodes to describe, with the search they were found by when the caller
+/// wants their [`NodeInfo::score`], distance and highlights.
+#[derive(Debug, Clone)]
+pub struct NodeInfoRequest {
+    pub results: Vec<SlabIndex>,
+    pub search: Option<(String, SearchOptionsPayload)>,
+}
+
 #[derive(Debug, Clone)]
 pub struct SearchJob {
     pub query: String,
@@ -44,24 +112,30 @@ pub struct SearchJob {
 
 pub struct SearchState {
     search_tx: Sender<SearchJob>,
-    result_rx: Receiver<Result<Option<Vec<SlabIndex>>>>,
+    result_rx: Receiver<Result<Option<SearchPage>>>,
 
-    node_info_tx: Sender<Vec<SlabIndex>>,
+    node_info_tx: Sender<NodeInfoRequest>,
     node_info_results_rx: Receiver<Vec<SearchResultNode>>,
 
     icon_viewport_tx: Sender<(u64, Vec<SlabIndex>)>,
     rescan_tx: Sender<()>,
+
+    macros_tx: Sender<MacroRegistry>,
+    /// Copy of the macros the background thread searches with.
+    macros: Mutex<MacroRegistry>,
 }
 
 impl SearchState {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         search_tx: Sender<SearchJob>,
-        result_rx: Receiver<Result<Option<Vec<SlabIndex>>>>,
-        node_info_tx: Sender<Vec<SlabIndex>>,
+        result_rx: Receiver<Result<Option<SearchPage>>>,
+        node_info_tx: Sender<NodeInfoRequest>,
         node_info_results_rx: Receiver<Vec<SearchResultNode>>,
         icon_viewport_tx: Sender<(u64, Vec<SlabIndex>)>,
         rescan_tx: Sender<()>,
+        macros_tx: Sender<MacroRegistry>,
+        macros: MacroRegistry,
     ) -> Self {
         Self {
             search_tx,
@@ -70,15 +144,24 @@ impl SearchState {
             node_info_results_rx,
             icon_viewport_tx,
             rescan_tx,
+            macros_tx,
+            macros: Mutex::new(macros),
         }
     }
 }
 
 #[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct NodeInfo {
     pub path: String,
     pub metadata: Option<NodeInfoMetadata>,
     pub icon: Option<String>,
+    pub score: Option<u32>,
+    pub distance: Option<u32>,
+    pub acronym_positions: Option<Vec<usize>>,
+    /// Byte ranges of the path components that matched, when the search
+    /// asked for them.
+    pub highlights: Option<Vec<MatchRange>>,
 }
 
 #[derive(Serialize)]
@@ -87,6 +170,8 @@ pub struct NodeInfoMetadata {
     pub size: u64,
     pub ctime: u32,
     pub mtime: u32,
+    pub atime: u32,
+    pub mode: u32,
 }
 
 impl NodeInfoMetadata {
@@ -96,6 +181,8 @@ impl NodeInfoMetadata {
             size: metadata.size(),
             ctime: metadata.ctime().map(|x| x.get()).unwrap_or_default(),
             mtime: metadata.mtime().map(|x| x.get()).unwrap_or_default(),
+            atime: metadata.atime().map(|x| x.get()).unwrap_or_default(),
+            mode: metadata.mode(),
         }
     }
 }
@@ -106,7 +193,7 @@ pub async fn search(
     options: Option<SearchOptionsPayload>,
     version: u64,
     state: State<'_, SearchState>,
-) -> Result<Vec<SlabIndex>, String> {
+) -> Result<SearchResponse, String> {
     let options = options.unwrap_or_default();
     let cancellation_token = CancellationToken::new(version);
     state
@@ -126,7 +213,7 @@ pub async fn search(
             if res.is_none() {
                 info!("Search {version} was cancelled");
             }
-            res.unwrap_or_default()
+            res.map(SearchResponse::from).unwrap_or_default()
         });
 
     search_result.map_err(|e| format!("Failed to process search result: {e:?}"))
@@ -135,6 +222,8 @@ pub async fn search(
 #[tauri::command]
 pub async fn get_nodes_info(
     results: Vec<SlabIndex>,
+    query: Option<String>,
+    options: Option<SearchOptionsPayload>,
     state: State<'_, SearchState>,
 ) -> Result<Vec<NodeInfo>, String> {
     if results.is_empty() {
@@ -143,7 +232,10 @@ pub async fn get_nodes_info(
 
     state
         .node_info_tx
-        .send(results.clone())
+        .send(NodeInfoRequest {
+            results,
+            search: query.map(|query| (query, options.unwrap_or_default())),
+        })
         .map_err(|e| format!("Failed to send node info request: {e:?}"))?;
 
     let nodes = state
@@ -153,20 +245,33 @@ pub async fn get_nodes_info(
 
     let node_infos = nodes
         .into_iter()
-        .map(|SearchResultNode { path, metadata }| {
-            let path = path.to_string_lossy().into_owned();
-            let icon = fs_icon::icon_of_path_ns(&path).map(|data| {
-                format!(
-                    "data:image/png;base64,{}",
-                    general_purpose::STANDARD.encode(data)
-                )
-            });
-            NodeInfo {
-                path,
-                icon,
-                metadata: metadata.as_ref().map(NodeInfoMetadata::from_metadata),
-            }
-        })
+        .map(
+            |SearchResultNode {
+                 path,
+                 metadata,
+                 score,
+                 distance,
+                 acronym_positions,
+                 highlights,
+             }| {
+                let path = path.to_string_lossy().into_owned();
+                let icon = fs_icon::icon_of_path_ns(&path).map(|data| {
+                    format!(
+                        "data:image/png;base64,{}",
+                        general_purpose::STANDARD.encode(data)
+                    )
+                });
+                NodeInfo {
+                    path,
+                    icon,
+                    metadata: metadata.as_ref().map(NodeInfoMetadata::from_metadata),
+                    score,
+                    distance,
+                    acronym_positions,
+                    highlights,
+                }
+            },
+        )
         .collect();
 
     Ok(node_infos)
@@ -198,6 +303,45 @@ pub async fn trigger_rescan(state: State<'_, SearchState>) -> Result<(), String>
     Ok(())
 }
 
+#[tauri::command]
+pub async fn get_search_macros(
+    state: State<'_, SearchState>,
+) -> Result<BTreeMap<String, String>, String> {
+    Ok(state
+        .macros
+        .lock()
+        .iter()
+        .map(|(name, expansion)| (name.to_string(), expansion.to_string()))
+        .collect())
+}
+
+/// Replaces the filter macros and saves them for the next launch.
+#[tauri::command]
+pub async fn set_search_macros(
+    macros: BTreeMap<String, String>,
+    state: State<'_, SearchState>,
+) -> Result<(), String> {
+    let mut registry = MacroRegistry::new();
+    for (name, expansion) in macros {
+        registry
+            .insert(&name, expansion)
+            .map_err(|e| format!("Invalid macro: {e}"))?;
+    }
+
+    if let Some(parent) = MACROS_PATH.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to save macros: {e}"))?;
+    }
+    fs::write(&*MACROS_PATH, registry.to_toml_string())
+        .map_err(|e| format!("Failed to save macros: {e}"))?;
+
+    state
+        .macros_tx
+        .send(registry.clone())
+        .map_err(|e| format!("Failed to update macros: {e:?}"))?;
+    *state.macros.lock() = registry;
+    Ok(())
+}
+
 #[tauri::command]
 pub fn open_in_finder(path: String) -> Result<(), String> {
     Command::new("open")
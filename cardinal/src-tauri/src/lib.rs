@@ -9,15 +9,16 @@ use background::{
 };
 use cardinal_sdk::EventWatcher;
 use commands::{
-    SearchJob, SearchState, get_app_status, get_nodes_info, open_in_finder, preview_with_quicklook,
-    request_app_exit, search, start_logic, trigger_rescan, update_icon_viewport,
+    NodeInfoRequest, SearchJob, SearchState, get_app_status, get_nodes_info, get_search_macros,
+    open_in_finder, preview_with_quicklook, request_app_exit, search, set_search_macros,
+    start_logic, trigger_rescan, update_icon_viewport,
 };
 use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, bounded, unbounded};
 use lifecycle::{
     APP_QUIT, AppLifecycleState, EXIT_REQUESTED, emit_app_state, load_app_state, update_app_state,
 };
 use once_cell::sync::OnceCell;
-use search_cache::{SearchCache, SearchResultNode, SlabIndex, WalkData};
+use search_cache::{MacroRegistry, SearchCache, SearchPage, SearchResultNode, SlabIndex, WalkData};
 use std::{
     path::PathBuf,
     sync::{
@@ -41,6 +42,9 @@ static CACHE_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
         .config_dir()
         .join("cardinal.db")
 });
+/// Filter macros, in the `[macros]` table of a TOML file next to the cache.
+pub(crate) static MACROS_PATH: LazyLock<PathBuf> =
+    LazyLock::new(|| CACHE_PATH.with_file_name("macros.toml"));
 const QUICK_LAUNCH_SHORTCUT: &str = "CmdOrCtrl+Shift+Space";
 pub(crate) static LOGIC_START: OnceCell<Sender<()>> = OnceCell::new();
 
@@ -55,11 +59,12 @@ pub fn run() -> Result<()> {
 
     let (finish_tx, finish_rx) = bounded::<Sender<Option<SearchCache>>>(1);
     let (search_tx, search_rx) = unbounded::<SearchJob>();
-    let (result_tx, result_rx) = unbounded::<Result<Option<Vec<SlabIndex>>>>();
-    let (node_info_tx, node_info_rx) = unbounded::<Vec<SlabIndex>>();
+    let (result_tx, result_rx) = unbounded::<Result<Option<SearchPage>>>();
+    let (node_info_tx, node_info_rx) = unbounded::<NodeInfoRequest>();
     let (node_info_results_tx, node_info_results_rx) = unbounded::<Vec<SearchResultNode>>();
     let (icon_viewport_tx, icon_viewport_rx) = unbounded::<(u64, Vec<SlabIndex>)>();
     let (rescan_tx, rescan_rx) = unbounded::<()>();
+    let (macros_tx, macros_rx) = unbounded::<MacroRegistry>();
     let (icon_update_tx, icon_update_rx) = unbounded::<IconPayload>();
     let (logic_start_tx, logic_start_rx) = bounded(1);
     LOGIC_START
@@ -118,6 +123,12 @@ pub fn run() -> Result<()> {
             }
         });
 
+    // Queued so the background thread starts searching with the saved macros.
+    let macros = load_search_macros();
+    macros_tx
+        .send(macros.clone())
+        .expect("macros channel closed");
+
     let app = builder
         .manage(SearchState::new(
             search_tx,
@@ -126,6 +137,8 @@ pub fn run() -> Result<()> {
             node_info_results_rx,
             icon_viewport_tx.clone(),
             rescan_tx.clone(),
+            macros_tx,
+            macros,
         ))
         .invoke_handler(tauri::generate_handler![
             search,
@@ -133,6 +146,8 @@ pub fn run() -> Result<()> {
             update_icon_viewport,
             get_app_status,
             trigger_rescan,
+            get_search_macros,
+            set_search_macros,
             open_in_finder,
             preview_with_quicklook,
             request_app_exit,
@@ -150,6 +165,7 @@ pub fn run() -> Result<()> {
         node_info_results_tx,
         icon_viewport_rx,
         rescan_rx,
+        macros_rx,
         icon_update_tx,
     };
     emit_app_state(app_handle);
@@ -298,6 +314,21 @@ fn run_logic_thread(app_handle: &tauri::AppHandle, channels: BackgroundLoopChann
     info!("Background thread exited");
 }
 
+fn load_search_macros() -> MacroRegistry {
+    let content = match std::fs::read_to_string(&*MACROS_PATH) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return MacroRegistry::new(),
+        Err(e) => {
+            warn!("Failed to read search macros from {:?}: {e}", &*MACROS_PATH);
+            return MacroRegistry::new();
+        }
+    };
+    MacroRegistry::from_toml_str(&content).unwrap_or_else(|e| {
+        warn!("Ignoring invalid search macros in {:?}: {e}", &*MACROS_PATH);
+        MacroRegistry::new()
+    })
+}
+
 fn flush_cache_to_file_once(finish_tx: &Sender<Sender<Option<SearchCache>>>) {
     static FLUSH_ONCE: Once = Once::new();
     if load_app_state() != AppLifecycleState::Ready {
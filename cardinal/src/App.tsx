@@ -24,6 +24,7 @@ import { listen } from '@tauri-apps/api/event';
 import type { UnlistenFn } from '@tauri-apps/api/event';
 import { useTranslation } from 'react-i18next';
 import type { SlabIndex } from './types/slab';
+import { extendsSlabIndices } from './types/slab';
 import { useFullDiskAccessPermission } from './hooks/useFullDiskAccessPermission';
 
 type ActiveTab = StatusTabKey;
@@ -34,6 +35,7 @@ function App() {
     searchParams,
     updateSearchParams,
     queueSearch,
+    loadMoreResults,
     resetSearchQuery,
     cancelPendingSearches,
     handleStatusUpdate,
@@ -49,6 +51,7 @@ function App() {
     initialFetchCompleted,
     durationMs,
     resultCount,
+    resultCountIsExact,
     searchError,
     lifecycleState,
   } = state;
@@ -463,8 +466,14 @@ function App() {
     [updateSearchParams],
   );
 
+  const shownResultsRef = useRef<SlabIndex[]>([]);
   useEffect(() => {
     // Reset vertical scroll and prefetch initial rows to keep first render responsive
+    const previousResults = shownResultsRef.current;
+    shownResultsRef.current = results;
+    // A further page of the same search keeps the scroll position.
+    if (extendsSlabIndices(previousResults, results)) return;
+
     const list = virtualListRef.current;
     if (!list) return;
 
@@ -618,6 +627,7 @@ function App() {
               overscan={OVERSCAN_ROW_COUNT}
               renderRow={renderRow}
               onScrollSync={handleHorizontalSync}
+              onEndReached={loadMoreResults}
             />
           )}
         </div>
@@ -630,6 +640,7 @@ function App() {
           lifecycleState={lifecycleState}
           searchDurationMs={durationMs}
           resultCount={resultCount}
+          resultCountIsExact={resultCountIsExact}
           activeTab={activeTab}
           onTabChange={handleTabChange}
           onRequestRescan={requestRescan}
@@ -26,6 +26,7 @@ type FilesTabContentProps = {
     rowStyle: CSSProperties,
   ) => ReactNode;
   onScrollSync: (scrollLeft: number) => void;
+  onEndReached?: () => void;
 };
 
 export function FilesTabContent({
@@ -41,6 +42,7 @@ export function FilesTabContent({
   overscan,
   renderRow,
   onScrollSync,
+  onEndReached,
 }: FilesTabContentProps): React.JSX.Element {
   return (
     <div className="scroll-area">
@@ -60,6 +62,7 @@ export function FilesTabContent({
             overscan={overscan}
             renderRow={renderRow}
             onScrollSync={onScrollSync}
+            onEndReached={onEndReached}
             className="virtual-list"
           />
         )}
@@ -13,6 +13,8 @@ type StatusBarProps = {
   lifecycleState: AppLifecycleStatus;
   searchDurationMs?: number | null;
   resultCount?: number | null;
+  // When false, resultCount is a lower bound and shows as e.g. "1,001+".
+  resultCountIsExact?: boolean;
   activeTab?: StatusTabKey;
   onTabChange?: (tab: StatusTabKey) => void;
   onRequestRescan?: () => void;
@@ -32,6 +34,7 @@ const StatusBar = ({
   lifecycleState,
   searchDurationMs,
   resultCount,
+  resultCountIsExact = true,
   activeTab = 'files',
   onTabChange,
   onRequestRescan,
@@ -72,7 +75,9 @@ const StatusBar = ({
   );
 
   const formattedResultCount =
-    typeof resultCount === 'number' ? resultCount.toLocaleString() : null;
+    typeof resultCount === 'number'
+      ? `${resultCount.toLocaleString()}${resultCountIsExact ? '' : '+'}`
+      : null;
   const resultsText =
     typeof resultCount === 'number'
       ? t('statusBar.resultsCount', {
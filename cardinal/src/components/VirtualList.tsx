@@ -33,12 +33,22 @@ type VirtualListProps = {
     rowStyle: CSSProperties,
   ) => React.ReactNode;
   onScrollSync?: (scrollLeft: number) => void;
+  // Called when the last row comes into the rendered window, to fetch more results.
+  onEndReached?: () => void;
   className?: string;
 };
 
 // Virtualized list with lazy row hydration and synchronized column scrolling
 export const VirtualList = forwardRef<VirtualListHandle, VirtualListProps>(function VirtualList(
-  { results = [], rowHeight = 24, overscan = 5, renderRow, onScrollSync, className = '' },
+  {
+    results = [],
+    rowHeight = 24,
+    overscan = 5,
+    renderRow,
+    onScrollSync,
+    onEndReached,
+    className = '',
+  },
   ref,
 ) {
   // ----- refs -----
@@ -123,6 +133,11 @@ export const VirtualList = forwardRef<VirtualListHandle, VirtualListProps>(funct
     if (end >= start) ensureRangeLoaded(start, end);
   }, [start, end, ensureRangeLoaded]);
 
+  // Ask for the next page once the overscanned window reaches the last loaded row
+  useEffect(() => {
+    if (onEndReached && rowCount > 0 && end === rowCount - 1) onEndReached();
+  }, [end, rowCount, onEndReached]);
+
   useEffect(() => {
     if (resultsList.length === 0 || end < start) {
       updateIconViewport([]);
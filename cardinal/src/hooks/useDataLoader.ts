@@ -4,7 +4,7 @@ import { listen } from '@tauri-apps/api/event';
 import type { UnlistenFn } from '@tauri-apps/api/event';
 import type { NodeInfoResponse, SearchResultItem } from '../types/search';
 import type { SlabIndex } from '../types/slab';
-import { toSlabIndex } from '../types/slab';
+import { extendsSlabIndices, toSlabIndex } from '../types/slab';
 import type { IconUpdatePayload, IconUpdateWirePayload } from '../types/ipc';
 
 type IconUpdateEventPayload = readonly IconUpdateWirePayload[] | null | undefined;
@@ -22,6 +22,7 @@ const fromNodeInfo = (node: NodeInfoResponse): SearchResultItem => {
     size: node.size ?? metadata?.size,
     mtime: node.mtime ?? metadata?.mtime,
     ctime: node.ctime ?? metadata?.ctime,
+    atime: node.atime ?? metadata?.atime,
     icon: normalizeIcon(node.icon),
   };
   return base;
@@ -42,6 +43,16 @@ export function useDataLoader(results: SlabIndex[]) {
 
   // Reset loading state whenever the result source changes.
   useEffect(() => {
+    const previous = resultsRef.current;
+    if (extendsSlabIndices(previous, results)) {
+      // A further page of the same search: keep the rows already loaded.
+      resultsRef.current = results;
+      for (let index = previous.length; index < results.length; index++) {
+        indexMapRef.current.set(results[index], index);
+      }
+      return;
+    }
+
     versionRef.current += 1;
     loadingRef.current.clear();
     iconOverridesRef.current.clear();
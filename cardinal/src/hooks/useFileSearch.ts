@@ -1,8 +1,9 @@
 import { useReducer, useRef, useCallback, useEffect } from 'react';
 import type { MutableRefObject } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { SEARCH_DEBOUNCE_MS } from '../constants';
+import { SEARCH_DEBOUNCE_MS, SEARCH_PAGE_SIZE } from '../constants';
 import type { AppLifecycleStatus } from '../types/ipc';
+import type { SearchResponse } from '../types/search';
 import type { SlabIndex } from '../types/slab';
 import { toSlabIndexArray } from '../types/slab';
 
@@ -17,6 +18,8 @@ type SearchState = {
   initialFetchCompleted: boolean;
   durationMs: number | null;
   resultCount: number;
+  // False when the backend stopped after filling the page, making resultCount a lower bound.
+  resultCountIsExact: boolean;
   searchError: SearchError;
   lifecycleState: AppLifecycleStatus;
 };
@@ -27,6 +30,12 @@ type SearchParams = {
   caseSensitive: boolean;
 };
 
+// The search whose results are shown, for fetching its further pages.
+type ActiveSearch = SearchParams & {
+  loaded: number;
+  hasMore: boolean;
+};
+
 type SearchAction =
   | { type: 'STATUS_UPDATE'; payload: { scannedFiles: number; processedEvents: number } }
   | { type: 'SEARCH_REQUEST'; payload: { immediate: boolean } }
@@ -38,6 +47,15 @@ type SearchAction =
         query: string;
         duration: number;
         count: number;
+        countIsExact: boolean;
+      };
+    }
+  | {
+      type: 'RESULTS_PAGE_LOADED';
+      payload: {
+        results: SlabIndex[];
+        count: number;
+        countIsExact: boolean;
       };
     }
   | {
@@ -58,6 +76,7 @@ const initialSearchState: SearchState = {
   initialFetchCompleted: false,
   durationMs: null,
   resultCount: 0,
+  resultCountIsExact: true,
   searchError: null,
   lifecycleState: 'Initializing',
 };
@@ -68,6 +87,20 @@ const initialSearchParams: SearchParams = {
   caseSensitive: false,
 };
 
+const searchOptions = ({ useRegex, caseSensitive }: SearchParams, offset: number) => ({
+  useRegex,
+  caseInsensitive: !caseSensitive,
+  limit: SEARCH_PAGE_SIZE,
+  offset,
+});
+
+const responseResults = (response: SearchResponse | undefined): SlabIndex[] =>
+  Array.isArray(response?.results) ? toSlabIndexArray(response.results) : [];
+
+// Without an exact total the backend stopped early, so more results follow.
+const hasMorePages = (response: SearchResponse | undefined, loaded: number): boolean =>
+  response != null && (!response.totalIsExact || loaded < response.total);
+
 const cancelTimer = (timerRef: MutableRefObject<ReturnType<typeof setTimeout> | null>) => {
   if (timerRef.current) {
     clearTimeout(timerRef.current);
@@ -104,8 +137,16 @@ function reducer(state: SearchState, action: SearchAction): SearchState {
         initialFetchCompleted: true,
         durationMs: action.payload.duration,
         resultCount: action.payload.count,
+        resultCountIsExact: action.payload.countIsExact,
         searchError: null,
       };
+    case 'RESULTS_PAGE_LOADED':
+      return {
+        ...state,
+        results: [...state.results, ...action.payload.results],
+        resultCount: action.payload.count,
+        resultCountIsExact: action.payload.countIsExact,
+      };
     case 'SEARCH_FAILURE':
       return {
         ...state,
@@ -114,6 +155,7 @@ function reducer(state: SearchState, action: SearchAction): SearchState {
         initialFetchCompleted: true,
         durationMs: action.payload.duration,
         resultCount: 0,
+        resultCountIsExact: true,
       };
     case 'SET_LIFECYCLE_STATE':
       return {
@@ -136,6 +178,7 @@ type UseFileSearchResult = {
   updateSearchParams: (patch: Partial<SearchParams>) => void;
   queueSearch: (query: string) => void;
   handleSearch: (overrides?: Partial<SearchParams>) => Promise<void>;
+  loadMoreResults: () => Promise<void>;
   resetSearchQuery: () => void;
   cancelPendingSearches: () => void;
   handleStatusUpdate: (scannedFiles: number, processedEvents: number) => void;
@@ -147,6 +190,10 @@ export function useFileSearch(): UseFileSearchResult {
   const [state, dispatch] = useReducer(reducer, initialSearchState);
   const latestSearchRef = useRef<SearchParams>(initialSearchParams);
   const searchVersionRef = useRef(0);
+  const activeSearchRef = useRef<ActiveSearch | null>(null);
+  // Version of the search being waited on, so a page load can't cancel it.
+  const pendingSearchVersionRef = useRef<number | null>(null);
+  const pageLoadingRef = useRef(false);
   const hasInitialSearchRunRef = useRef(false);
   const debounceTimerRef = useRef<ReturnType<typeof setTimeout> | null>(null);
   const loadingDelayTimerRef = useRef<ReturnType<typeof setTimeout> | null>(null);
@@ -196,8 +243,9 @@ export function useFileSearch(): UseFileSearchResult {
     latestSearchRef.current = nextSearch;
     const requestVersion = searchVersionRef.current + 1;
     searchVersionRef.current = requestVersion;
+    pendingSearchVersionRef.current = requestVersion;
 
-    const { query, useRegex, caseSensitive } = nextSearch;
+    const { query } = nextSearch;
     const startTs = performance.now();
     const isInitial = !hasInitialSearchRunRef.current;
     const trimmedQuery = query.trim();
@@ -213,21 +261,24 @@ export function useFileSearch(): UseFileSearchResult {
     }
 
     try {
-      const rawResults = await invoke<number[]>('search', {
+      const response = await invoke<SearchResponse>('search', {
         query,
-        options: {
-          useRegex,
-          caseInsensitive: !caseSensitive,
-        },
+        options: searchOptions(nextSearch, 0),
         version: requestVersion,
       });
 
-      const searchResults = Array.isArray(rawResults) ? toSlabIndexArray(rawResults) : [];
+      const searchResults = responseResults(response);
 
       if (searchVersionRef.current !== requestVersion) {
         return;
       }
 
+      activeSearchRef.current = {
+        ...nextSearch,
+        loaded: searchResults.length,
+        hasMore: hasMorePages(response, searchResults.length),
+      };
+
       cancelTimer(loadingDelayTimerRef);
 
       const endTs = performance.now();
@@ -239,7 +290,8 @@ export function useFileSearch(): UseFileSearchResult {
           results: searchResults,
           query: trimmedQuery,
           duration,
-          count: searchResults.length,
+          count: response?.total ?? searchResults.length,
+          countIsExact: response?.totalIsExact ?? true,
         },
       });
     } catch (error) {
@@ -249,6 +301,7 @@ export function useFileSearch(): UseFileSearchResult {
         return;
       }
 
+      activeSearchRef.current = null;
       cancelTimer(loadingDelayTimerRef);
 
       const endTs = performance.now();
@@ -266,6 +319,59 @@ export function useFileSearch(): UseFileSearchResult {
       });
     } finally {
       hasInitialSearchRunRef.current = true;
+      if (pendingSearchVersionRef.current === requestVersion) {
+        pendingSearchVersionRef.current = null;
+      }
+    }
+  }, []);
+
+  // Fetch the next page of the shown search, e.g. when the list scrolls to its end.
+  const loadMoreResults = useCallback(async () => {
+    const activeSearch = activeSearchRef.current;
+    if (
+      !activeSearch?.hasMore ||
+      pageLoadingRef.current ||
+      pendingSearchVersionRef.current !== null
+    ) {
+      return;
+    }
+
+    pageLoadingRef.current = true;
+    // A new version cancels nothing in flight; a later search cancels this page instead.
+    const requestVersion = searchVersionRef.current + 1;
+    searchVersionRef.current = requestVersion;
+
+    try {
+      const response = await invoke<SearchResponse>('search', {
+        query: activeSearch.query,
+        options: searchOptions(activeSearch, activeSearch.loaded),
+        version: requestVersion,
+      });
+
+      if (searchVersionRef.current !== requestVersion) {
+        return;
+      }
+
+      const pageResults = responseResults(response);
+      const loaded = activeSearch.loaded + pageResults.length;
+      activeSearchRef.current = {
+        ...activeSearch,
+        loaded,
+        hasMore: pageResults.length > 0 && hasMorePages(response, loaded),
+      };
+
+      dispatch({
+        type: 'RESULTS_PAGE_LOADED',
+        payload: {
+          results: pageResults,
+          count: response?.total ?? loaded,
+          countIsExact: response?.totalIsExact ?? true,
+        },
+      });
+    } catch (error) {
+      console.error('Failed to load more results:', error);
+    } finally {
+      pageLoadingRef.current = false;
     }
   }, []);
 
@@ -314,6 +420,7 @@ export function useFileSearch(): UseFileSearchResult {
     updateSearchParams,
     queueSearch,
     handleSearch,
+    loadMoreResults,
     resetSearchQuery,
     cancelPendingSearches,
     handleStatusUpdate,
@@ -0,0 +1,15 @@
+import { describe, expect, it } from 'vitest';
+import { extendsSlabIndices, toSlabIndexArray } from '../slab';
+
+describe('extendsSlabIndices', () => {
+  it('accepts results with a further page appended', () => {
+    expect(extendsSlabIndices(toSlabIndexArray([3, 1]), toSlabIndexArray([3, 1, 7]))).toBe(true);
+  });
+
+  it('rejects results from a different search', () => {
+    expect(extendsSlabIndices(toSlabIndexArray([3, 1]), toSlabIndexArray([1, 3, 7]))).toBe(false);
+    expect(extendsSlabIndices(toSlabIndexArray([3, 1]), toSlabIndexArray([3, 1]))).toBe(false);
+    expect(extendsSlabIndices(toSlabIndexArray([3, 1]), toSlabIndexArray([3]))).toBe(false);
+    expect(extendsSlabIndices([], toSlabIndexArray([1]))).toBe(false);
+  });
+});
@@ -3,6 +3,9 @@ export type SearchResultMetadata = Readonly<{
   size: number;
   mtime: number;
   ctime: number;
+  atime: number;
+  // Unix st_mode: file type and permission bits.
+  mode: number;
 }>;
 
 export type SearchResultItem = Readonly<{
@@ -11,9 +14,16 @@ export type SearchResultItem = Readonly<{
   size?: number;
   mtime?: number;
   ctime?: number;
+  atime?: number;
   icon?: string;
 }>;
 
+export type SearchResponse = Readonly<{
+  results: number[];
+  total: number;
+  totalIsExact: boolean;
+}>;
+
 export type NodeInfoResponse = Readonly<{
   path: string;
   icon?: string | null;
@@ -21,4 +31,17 @@ export type NodeInfoResponse = Readonly<{
   size?: number | null;
   mtime?: number | null;
   ctime?: number | null;
+  atime?: number | null;
+  score?: number | null;
+  distance?: number | null;
+  acronymPositions?: number[] | null;
+  highlights?: MatchRange[] | null;
+}>;
+
+// Byte offsets into one component of the path: level 0 is the file name,
+// level 1 its parent folder's name, and so on.
+export type MatchRange = Readonly<{
+  level: number;
+  start: number;
+  end: number;
 }>;
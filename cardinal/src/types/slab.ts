@@ -4,3 +4,10 @@ export const toSlabIndex = (value: number): SlabIndex => value as SlabIndex;
 
 export const toSlabIndexArray = (values: number[]): SlabIndex[] =>
   values.map((value) => value as SlabIndex);
+
+// Whether `next` is the non-empty `previous` with more results appended, as
+// when a further page of the same search arrives.
+export const extendsSlabIndices = (previous: SlabIndex[], next: SlabIndex[]): boolean =>
+  previous.length > 0 &&
+  next.length > previous.length &&
+  previous.every((value, index) => next[index] === value);
@@ -25,6 +25,9 @@ pub struct NodeMetadata {
     pub size: u64,
     pub ctime: Option<NonZeroU64>,
     pub mtime: Option<NonZeroU64>,
+    pub atime: Option<NonZeroU64>,
+    /// Raw `st_mode`: file type, permission and setuid/setgid/sticky bits.
+    pub mode: u32,
 }
 
 impl From<Metadata> for NodeMetadata {
@@ -47,11 +50,19 @@ impl NodeMetadata {
             .ok()
             .and_then(|x| x.duration_since(UNIX_EPOCH).ok())
             .and_then(|x| NonZeroU64::new(x.as_secs()));
+        let atime = metadata
+            .accessed()
+            .ok()
+            .and_then(|x| x.duration_since(UNIX_EPOCH).ok())
+            .and_then(|x| NonZeroU64::new(x.as_secs()));
+        let mode = metadata.mode();
         Self {
             r#type,
             size,
             ctime,
             mtime,
+            atime,
+            mode,
         }
     }
 }
@@ -1,4 +1,5 @@
 use clap::Parser;
+use search_cache::SortKey;
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -8,4 +9,34 @@ pub struct Cli {
     pub refresh: bool,
     #[clap(long, default_value = "/")]
     pub path: PathBuf,
+    #[clap(long)]
+    /// TOML file whose `[macros]` table defines filter macros such as `proj:`.
+    pub macros: Option<PathBuf>,
+    #[clap(long)]
+    /// Sort results by name, path, size, dm, dc, da, ext, type, relevance or distance.
+    pub sort: Option<SortKey>,
+    #[clap(long, default_value = "false")]
+    /// Allow a few typos in plain words.
+    pub fuzzy: bool,
+    #[clap(long, default_value = "false")]
+    /// Read plain words as initials or camel-case humps.
+    pub acronym: bool,
+    #[clap(long, default_value = "false")]
+    /// Ignore accents, so `resume` finds `résumé`.
+    pub ignore_diacritics: bool,
+    #[clap(long, default_value = "false")]
+    /// Sort in descending order.
+    pub desc: bool,
+    #[clap(long)]
+    /// Print at most this many results.
+    pub limit: Option<usize>,
+    #[clap(long, default_value = "0")]
+    /// Skip this many results first.
+    pub offset: usize,
+    #[clap(long, default_value = "false")]
+    /// Only print how many results match.
+    pub count: bool,
+    #[clap(long, default_value = "false")]
+    /// Index names by trigrams for faster substring searches, using more memory.
+    pub trigram_index: bool,
 }
@@ -5,7 +5,10 @@ use cardinal_sdk::EventWatcher;
 use clap::Parser;
 use cli::Cli;
 use crossbeam_channel::{Sender, bounded, unbounded};
-use search_cache::{HandleFSEError, SearchCache, SearchResultNode};
+use search_cache::{
+    HandleFSEError, NAME_POOL, SearchCache, SearchOptions, SearchPage, SearchResultNode,
+    SearchTotal, SortDirection, SortSpec,
+};
 use search_cancel::CancellationToken;
 use std::{
     io::Write,
@@ -25,6 +28,9 @@ fn main() -> Result<()> {
     }
 
     let cli = Cli::parse();
+    if cli.trigram_index {
+        NAME_POOL.set_trigram_index(true);
+    }
     let path = cli.path;
     let mut cache = if cli.refresh {
         println!("Walking filesystem...");
@@ -42,12 +48,34 @@ fn main() -> Result<()> {
             SearchCache::walk_fs_with_ignore(path, vec![PathBuf::from(IGNORE_PATH)])
         })
     };
+    if let Some(macros) = &cli.macros {
+        cache.load_macros(macros)?;
+        println!("Loaded {} macros", cache.macros().len());
+    }
 
     println!("Cache is: {cache:?}");
 
+    let options = SearchOptions {
+        sort: cli.sort.map(|key| SortSpec {
+            key,
+            direction: if cli.desc {
+                SortDirection::Descending
+            } else {
+                SortDirection::Ascending
+            },
+        }),
+        limit: cli.limit,
+        offset: cli.offset,
+        count_only: cli.count,
+        fuzzy: cli.fuzzy,
+        acronym: cli.acronym,
+        ignore_diacritics: cli.ignore_diacritics,
+        ..SearchOptions::default()
+    };
+
     let (finish_tx, finish_rx) = bounded::<Sender<SearchCache>>(1);
     let (search_tx, search_rx) = unbounded::<String>();
-    let (search_result_tx, search_result_rx) = unbounded::<Result<Vec<SearchResultNode>>>();
+    let (search_result_tx, search_result_rx) = unbounded::<Result<SearchPage<SearchResultNode>>>();
 
     std::thread::spawn(move || {
         let (dev, mut event_watcher) =
@@ -62,7 +90,9 @@ fn main() -> Result<()> {
                 }
                 recv(search_rx) -> query => {
                     let query = query.expect("search_tx is closed");
-                    let files = cache.query_files(query, CancellationToken::noop()).map(|x| x.unwrap());
+                    let files = cache
+                        .query_files_page(query, options, CancellationToken::noop())
+                        .map(|x| x.unwrap());
                     search_result_tx
                         .send(files)
                         .expect("search_result_tx is closed");
@@ -106,9 +136,18 @@ fn main() -> Result<()> {
             .recv()
             .context("search_result_rx is closed")?;
         match search_result {
-            Ok(path_set) => {
-                for (i, path) in path_set.into_iter().enumerate() {
-                    println!("[{i}] {:?} {:?}", path.path, path.metadata);
+            Ok(SearchPage { nodes, total }) => {
+                for (i, path) in nodes.into_iter().enumerate() {
+                    println!(
+                        "[{}] {:?} {:?}",
+                        i + options.offset,
+                        path.path,
+                        path.metadata
+                    );
+                }
+                match total {
+                    SearchTotal::Exact(total) => println!("{total} matches"),
+                    SearchTotal::AtLeast(total) => println!("{total}+ matches"),
                 }
             }
             Err(e) => {
@@ -1,20 +1,48 @@
 #![feature(str_from_raw_parts)]
+mod trigram;
+
 use core::str;
 use parking_lot::Mutex;
 use regex::Regex;
 use search_cancel::CancellationToken;
 use std::collections::BTreeSet;
+use trigram::{TrigramIndex, regex_literals};
 
 const CANCEL_CHECK_INTERVAL: usize = 0x10000;
 
+/// What every name a [`NamePool::search_with`] predicate accepts contains, so
+/// the trigram index can skip the other names.
+///
+/// Only ASCII names are held to it, and in any case: names with other
+/// characters are always checked, since folding or normalizing them can
+/// produce the hint from different bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum SearchHint<'a> {
+    /// Nothing is known, every name is checked.
+    #[default]
+    None,
+    /// Text accepted names contain, like the needle of a folded search.
+    Literal(&'a str),
+    /// A regex accepted names match.
+    Regex(&'a Regex),
+}
+
 pub struct NamePool {
-    inner: Mutex<BTreeSet<Box<str>>>,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    names: BTreeSet<Box<str>>,
+    /// See [`NamePool::set_trigram_index`].
+    index: Option<TrigramIndex>,
 }
 
 impl std::fmt::Debug for NamePool {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("NamePool")
             .field("len", &self.len())
+            .field("trigram_index", &self.has_trigram_index())
             .finish()
     }
 }
@@ -28,16 +56,52 @@ impl Default for NamePool {
 impl NamePool {
     pub fn new() -> Self {
         Self {
-            inner: Mutex::new(BTreeSet::new()),
+            inner: Mutex::new(Inner::default()),
         }
     }
 
+    /// A pool that keeps a trigram index from the start, see
+    /// [`Self::set_trigram_index`].
+    pub fn with_trigram_index() -> Self {
+        let pool = Self::new();
+        pool.set_trigram_index(true);
+        pool
+    }
+
     pub fn len(&self) -> usize {
-        self.inner.lock().len()
+        self.inner.lock().names.len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.inner.lock().is_empty()
+        self.inner.lock().names.is_empty()
+    }
+
+    /// Turns the trigram index on or off.
+    ///
+    /// The index maps every three byte window of the names to the names
+    /// containing it, so searches only check the names sharing all the
+    /// trigrams of the needle (or of the literals a regex requires, or of the
+    /// [`SearchHint`] of [`Self::search_with`]) instead of scanning the whole
+    /// pool. It costs a few bytes per character of every name, which is
+    /// why it's off by default. Turning it on indexes the names already in the
+    /// pool, and [`Self::push`] keeps it up to date from then on.
+    pub fn set_trigram_index(&self, enabled: bool) {
+        let mut inner = self.inner.lock();
+        match (enabled, inner.index.is_some()) {
+            (true, false) => {
+                let mut index = TrigramIndex::default();
+                for name in &inner.names {
+                    index.insert(name);
+                }
+                inner.index = Some(index);
+            }
+            (false, true) => inner.index = None,
+            _ => {}
+        }
+    }
+
+    pub fn has_trigram_index(&self) -> bool {
+        self.inner.lock().index.is_some()
     }
 
     /// This function add a name into last cache line, if the last cache line is
@@ -53,10 +117,15 @@ impl NamePool {
     /// and won't be overwritten.
     pub fn push<'c>(&'c self, name: &str) -> &'c str {
         let mut inner = self.inner.lock();
-        if !inner.contains(name) {
-            inner.insert(name.into());
+        let inner = &mut *inner;
+        if !inner.names.contains(name) {
+            inner.names.insert(name.into());
+            let existing = inner.names.get(name).unwrap();
+            if let Some(index) = &mut inner.index {
+                index.insert(existing);
+            }
         }
-        let existing = inner.get(name).unwrap();
+        let existing = inner.names.get(name).unwrap();
         unsafe { str::from_raw_parts(existing.as_ptr(), existing.len()) }
     }
 
@@ -65,16 +134,12 @@ impl NamePool {
         substr: &'search str,
         cancellation_token: CancellationToken,
     ) -> Option<BTreeSet<&'pool str>> {
-        let mut result = BTreeSet::new();
-        for (i, x) in self.inner.lock().iter().enumerate() {
-            if i % CANCEL_CHECK_INTERVAL == 0 && cancellation_token.is_cancelled() {
-                return None;
-            }
-            if x.contains(substr) {
-                result.insert(unsafe { str::from_raw_parts(x.as_ptr(), x.len()) });
-            }
-        }
-        Some(result)
+        self.collect(
+            &[substr.as_bytes()],
+            false,
+            |x| x.contains(substr),
+            cancellation_token,
+        )
     }
 
     pub fn search_suffix<'search, 'pool: 'search>(
@@ -82,16 +147,12 @@ impl NamePool {
         suffix: &'search str,
         cancellation_token: CancellationToken,
     ) -> Option<BTreeSet<&'pool str>> {
-        let mut result = BTreeSet::new();
-        for (i, x) in self.inner.lock().iter().enumerate() {
-            if i % CANCEL_CHECK_INTERVAL == 0 && cancellation_token.is_cancelled() {
-                return None;
-            }
-            if x.ends_with(suffix) {
-                result.insert(unsafe { str::from_raw_parts(x.as_ptr(), x.len()) });
-            }
-        }
-        Some(result)
+        self.collect(
+            &[suffix.as_bytes()],
+            false,
+            |x| x.ends_with(suffix),
+            cancellation_token,
+        )
     }
 
     pub fn search_prefix<'search, 'pool: 'search>(
@@ -99,17 +160,12 @@ impl NamePool {
         prefix: &'search str,
         cancellation_token: CancellationToken,
     ) -> Option<BTreeSet<&'pool str>> {
-        let mut result = BTreeSet::new();
-        for (i, x) in self.inner.lock().iter().enumerate() {
-            if i % CANCEL_CHECK_INTERVAL == 0 && cancellation_token.is_cancelled() {
-                return None;
-            }
-            if x.starts_with(prefix) {
-                result.insert(unsafe { str::from_raw_parts(x.as_ptr(), x.len()) });
-            }
-        }
-
-        Some(result)
+        self.collect(
+            &[prefix.as_bytes()],
+            false,
+            |x| x.starts_with(prefix),
+            cancellation_token,
+        )
     }
 
     pub fn search_regex<'search, 'pool: 'search>(
@@ -117,17 +173,46 @@ impl NamePool {
         pattern: &Regex,
         cancellation_token: CancellationToken,
     ) -> Option<BTreeSet<&'pool str>> {
-        let mut result = BTreeSet::new();
-        for (i, x) in self.inner.lock().iter().enumerate() {
-            if i % CANCEL_CHECK_INTERVAL == 0 && cancellation_token.is_cancelled() {
-                return None;
-            }
-            let existing = unsafe { str::from_raw_parts(x.as_ptr(), x.len()) };
-            if pattern.is_match(existing) {
-                result.insert(existing);
-            }
+        let literals = self.regex_literals(pattern);
+        let literals: Vec<&[u8]> = literals.iter().map(Vec::as_slice).collect();
+        self.collect(
+            &literals,
+            false,
+            |x| pattern.is_match(x),
+            cancellation_token,
+        )
+    }
+
+    /// Collects every name accepted by `predicate`, for matchers that can't be
+    /// expressed as a plain substring, prefix, suffix or regex search, like
+    /// ones folding case. With a `hint`, the trigram index only hands it the
+    /// names that may be accepted.
+    pub fn search_with(
+        &self,
+        hint: SearchHint<'_>,
+        predicate: impl FnMut(&str) -> bool,
+        cancellation_token: CancellationToken,
+    ) -> Option<BTreeSet<&str>> {
+        let regex_literals = match hint {
+            SearchHint::Regex(pattern) => self.regex_literals(pattern),
+            _ => Vec::new(),
+        };
+        let literals: Vec<&[u8]> = match hint {
+            SearchHint::None => Vec::new(),
+            SearchHint::Literal(literal) => vec![literal.as_bytes()],
+            SearchHint::Regex(_) => regex_literals.iter().map(Vec::as_slice).collect(),
+        };
+        self.collect(&literals, true, predicate, cancellation_token)
+    }
+
+    /// Literals for narrowing down a search for `pattern`, only worked out
+    /// when there's an index to use them.
+    fn regex_literals(&self, pattern: &Regex) -> Vec<Vec<u8>> {
+        if self.has_trigram_index() {
+            regex_literals(pattern.as_str())
+        } else {
+            Vec::new()
         }
-        Some(result)
     }
 
     // `exact` should starts with a '\0', and ends with a '\0',
@@ -137,12 +222,52 @@ impl NamePool {
         exact: &'search str,
         cancellation_token: CancellationToken,
     ) -> Option<BTreeSet<&'pool str>> {
+        self.collect(
+            &[exact.as_bytes()],
+            false,
+            |x| x == exact,
+            cancellation_token,
+        )
+    }
+
+    /// Collects the names accepted by `predicate`. When every name it accepts
+    /// contains one of `literals`, the trigram index (if any) narrows down the
+    /// names to check; with no literals every name is checked. With
+    /// `ascii_only`, only ASCII names have to contain one, in any case.
+    fn collect(
+        &self,
+        literals: &[&[u8]],
+        ascii_only: bool,
+        mut predicate: impl FnMut(&str) -> bool,
+        cancellation_token: CancellationToken,
+    ) -> Option<BTreeSet<&str>> {
         let mut result = BTreeSet::new();
-        for (i, x) in self.inner.lock().iter().enumerate() {
+        let inner = self.inner.lock();
+        let candidates = inner.index.as_ref().and_then(|index| {
+            let ids = if ascii_only {
+                index.ascii_candidates(literals)
+            } else {
+                index.candidates(literals)
+            };
+            ids.map(|ids| (index, ids))
+        });
+        if let Some((index, ids)) = candidates {
+            for (i, id) in ids.into_iter().enumerate() {
+                if i % CANCEL_CHECK_INTERVAL == 0 && cancellation_token.is_cancelled() {
+                    return None;
+                }
+                let name = index.name(id);
+                if predicate(name) {
+                    result.insert(unsafe { str::from_raw_parts(name.as_ptr(), name.len()) });
+                }
+            }
+            return Some(result);
+        }
+        for (i, x) in inner.names.iter().enumerate() {
             if i % CANCEL_CHECK_INTERVAL == 0 && cancellation_token.is_cancelled() {
                 return None;
             }
-            if &**x == exact {
+            if predicate(x) {
                 result.insert(unsafe { str::from_raw_parts(x.as_ptr(), x.len()) });
             }
         }
@@ -325,6 +450,23 @@ mod tests {
         assert!(result.contains("world"));
     }
 
+    #[test]
+    fn test_search_with_predicate() {
+        let pool = NamePool::new();
+        pool.push("hello");
+        pool.push("world");
+        pool.push("hello world");
+
+        let result = guard(pool.search_with(
+            SearchHint::None,
+            |name| name.len() == 5,
+            CancellationToken::noop(),
+        ));
+        assert_eq!(result.len(), 2);
+        assert!(result.contains("hello"));
+        assert!(result.contains("world"));
+    }
+
     #[test]
     fn test_search_regex_basic() {
         use regex::Regex;
@@ -903,4 +1045,151 @@ mod tests {
         let result = substr(&pool, "1");
         assert_eq!(result.len(), 271);
     }
+
+    #[test]
+    fn test_trigram_index_matches_scan() {
+        let names = [
+            "main.rs",
+            "lib.rs",
+            "README.md",
+            "readme.txt",
+            "Cargo.toml",
+            "cargo-foo-1.2.3",
+            "abcxbcab",
+            "abcab",
+            "こんにちは世界",
+            "ab",
+            "",
+        ];
+        let scanned = NamePool::new();
+        let indexed = NamePool::with_trigram_index();
+        for name in names {
+            scanned.push(name);
+            indexed.push(name);
+        }
+        assert!(indexed.has_trigram_index());
+        for needle in [
+            "", "a", "rs", ".rs", "main", "abcab", "argo", "世界", "missing",
+        ] {
+            assert_eq!(
+                substr(&indexed, needle),
+                substr(&scanned, needle),
+                "{needle:?}"
+            );
+            assert_eq!(
+                suffix_search(&indexed, needle),
+                suffix_search(&scanned, needle),
+                "{needle:?}"
+            );
+        }
+        let regexes = [
+            r"\.rs$",
+            r"(?i)readme",
+            r"^cargo-\d",
+            r"foo|main",
+            r"\d+",
+            "世界$",
+        ];
+        for pattern in regexes {
+            let regex = Regex::new(pattern).unwrap();
+            assert_eq!(
+                regex_search(&indexed, &regex),
+                regex_search(&scanned, &regex),
+                "{pattern:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_trigram_index_case_insensitive_regex() {
+        use regex::RegexBuilder;
+
+        let pool = NamePool::with_trigram_index();
+        pool.push("README.md");
+        pool.push("Kelvin \u{212A}");
+        pool.push("other");
+
+        // The case folding comes from the builder, not the pattern.
+        let regex = RegexBuilder::new("readme")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        assert_eq!(regex_search(&pool, &regex), BTreeSet::from(["README.md"]));
+        let regex = RegexBuilder::new("in k")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            regex_search(&pool, &regex),
+            BTreeSet::from(["Kelvin \u{212A}"])
+        );
+    }
+
+    #[test]
+    fn test_trigram_index_search_hint() {
+        let pool = NamePool::with_trigram_index();
+        for name in [
+            "Report.PDF",
+            "report-old.pdf",
+            "notes.txt",
+            "RÉPORT.pdf",
+            "todo.md",
+        ] {
+            pool.push(name);
+        }
+        let search = |hint: SearchHint<'_>, predicate: fn(&str) -> bool| {
+            let mut checked = Vec::new();
+            let found = guard(pool.search_with(
+                hint,
+                |name| {
+                    checked.push(name.to_string());
+                    predicate(name)
+                },
+                CancellationToken::noop(),
+            ));
+            checked.sort();
+            (found, checked)
+        };
+
+        let (found, checked) = search(SearchHint::Literal("report"), |name| {
+            name.to_lowercase().contains("report")
+        });
+        assert_eq!(found, BTreeSet::from(["Report.PDF", "report-old.pdf"]));
+        // Besides the ASCII names with the hint in any case, only the names
+        // outside of ASCII are checked.
+        assert_eq!(checked, ["Report.PDF", "RÉPORT.pdf", "report-old.pdf"]);
+
+        let regex = Regex::new(r"(?i)\.pdf$").unwrap();
+        let (found, checked) = search(SearchHint::Regex(&regex), |name| {
+            name.to_lowercase().ends_with(".pdf")
+        });
+        assert_eq!(found.len(), 3);
+        assert_eq!(checked.len(), 3);
+
+        let (_, checked) = search(SearchHint::Literal("re"), |_| false);
+        assert_eq!(checked.len(), 5);
+        let (_, checked) = search(SearchHint::None, |_| false);
+        assert_eq!(checked.len(), 5);
+    }
+
+    #[test]
+    fn test_trigram_index_follows_push() {
+        let pool = NamePool::new();
+        pool.push("before.rs");
+        pool.set_trigram_index(true);
+        pool.push("after.rs");
+        pool.push("after.rs");
+        assert_eq!(
+            substr(&pool, ".rs"),
+            BTreeSet::from(["after.rs", "before.rs"])
+        );
+        assert_eq!(suffix_search(&pool, "ter.rs"), BTreeSet::from(["after.rs"]));
+
+        pool.set_trigram_index(false);
+        assert!(!pool.has_trigram_index());
+        pool.push("later.rs");
+        assert_eq!(substr(&pool, ".rs").len(), 3);
+        pool.set_trigram_index(true);
+        assert_eq!(substr(&pool, "later").len(), 1);
+    }
 }
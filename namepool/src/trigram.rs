@@ -0,0 +1,222 @@
+use core::str;
+use regex_syntax::{
+    ParserBuilder,
+    hir::literal::{ExtractKind, Extractor},
+};
+use rustc_hash::FxHashMap;
+
+/// Posting lists from the three byte windows of the names to the names
+/// containing them.
+///
+/// Windows are lowercased in ASCII, so the spellings of a literal that only
+/// differ in case share their lists; lookups are a superset either way.
+#[derive(Default)]
+pub(crate) struct TrigramIndex {
+    /// Every indexed name; posting lists refer to names by their position.
+    names: Vec<NameRef>,
+    /// Ids in each list are ascending, since names only get appended.
+    postings: FxHashMap<[u8; 3], Vec<u32>>,
+    /// Ids of the names with characters outside of ASCII, ascending.
+    non_ascii: Vec<u32>,
+}
+
+/// A name of the pool, which is never moved or freed once interned.
+#[derive(Clone, Copy)]
+struct NameRef {
+    ptr: *const u8,
+    len: usize,
+}
+
+// SAFETY: the pointers are only read, under the pool's lock, and the names
+// they point to live as long as the pool.
+unsafe impl Send for NameRef {}
+
+impl TrigramIndex {
+    pub(crate) fn insert(&mut self, name: &str) {
+        let id = u32::try_from(self.names.len()).expect("too many names for the trigram index");
+        self.names.push(NameRef {
+            ptr: name.as_ptr(),
+            len: name.len(),
+        });
+        if !name.is_ascii() {
+            self.non_ascii.push(id);
+        }
+        for trigram in name.as_bytes().windows(3) {
+            let ids = self.postings.entry(lowercase(trigram)).or_default();
+            // Repeated trigrams of the same name are listed once.
+            if ids.last() != Some(&id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    pub(crate) fn name(&self, id: u32) -> &str {
+        let NameRef { ptr, len } = self.names[id as usize];
+        unsafe { str::from_raw_parts(ptr, len) }
+    }
+
+    /// Ids of the names that may contain one of `literals`, in any ASCII
+    /// case, in ascending order. `None` when the index can't narrow them
+    /// down: without literals, or when one of them is shorter than a trigram.
+    pub(crate) fn candidates(&self, literals: &[&[u8]]) -> Option<Vec<u32>> {
+        if literals.is_empty() || literals.iter().any(|literal| literal.len() < 3) {
+            return None;
+        }
+        let mut ids: Vec<u32> = literals
+            .iter()
+            .flat_map(|literal| self.containing(literal))
+            .collect();
+        if literals.len() > 1 {
+            ids.sort_unstable();
+            ids.dedup();
+        }
+        Some(ids)
+    }
+
+    /// Like [`Self::candidates`], but for literals that only ASCII names have
+    /// to contain, ignoring case: every other name is a candidate too.
+    pub(crate) fn ascii_candidates(&self, literals: &[&[u8]]) -> Option<Vec<u32>> {
+        let mut ids = self.candidates(literals)?;
+        ids.extend_from_slice(&self.non_ascii);
+        ids.sort_unstable();
+        ids.dedup();
+        Some(ids)
+    }
+
+    /// Ids of the names having every trigram of `literal`, which is at least
+    /// three bytes long.
+    fn containing(&self, literal: &[u8]) -> Vec<u32> {
+        let mut trigrams: Vec<[u8; 3]> = literal.windows(3).map(lowercase).collect();
+        trigrams.sort_unstable();
+        trigrams.dedup();
+        let mut lists = Vec::with_capacity(trigrams.len());
+        for trigram in trigrams {
+            let Some(ids) = self.postings.get(&trigram) else {
+                return Vec::new();
+            };
+            lists.push(ids.as_slice());
+        }
+        // Intersecting from the shortest list keeps every step small.
+        lists.sort_unstable_by_key(|ids| ids.len());
+        let (first, rest) = lists.split_first().expect("literal has a trigram");
+        let mut ids = first.to_vec();
+        for list in rest {
+            let mut rest = *list;
+            ids.retain(|id| {
+                // Both lists are ascending, so the search resumes where the
+                // last one stopped.
+                let position = rest.partition_point(|other| other < id);
+                rest = &rest[position..];
+                rest.first() == Some(id)
+            });
+            if ids.is_empty() {
+                break;
+            }
+        }
+        ids
+    }
+}
+
+fn lowercase(trigram: &[u8]) -> [u8; 3] {
+    [
+        trigram[0].to_ascii_lowercase(),
+        trigram[1].to_ascii_lowercase(),
+        trigram[2].to_ascii_lowercase(),
+    ]
+}
+
+/// Literals one of which every match of `pattern` contains, for narrowing
+/// down a regex search with the index; empty when there is no such set.
+///
+/// The pattern is parsed ignoring case, as the regex may have been built with
+/// case folding that its text doesn't show. That only lists more spellings of
+/// the same literals. Both the literals matches start with and those they end
+/// with qualify, and the smaller set is used.
+pub(crate) fn regex_literals(pattern: &str) -> Vec<Vec<u8>> {
+    let Ok(hir) = ParserBuilder::new()
+        .case_insensitive(true)
+        .build()
+        .parse(pattern)
+    else {
+        return Vec::new();
+    };
+    [ExtractKind::Prefix, ExtractKind::Suffix]
+        .into_iter()
+        .filter_map(|kind| {
+            let seq = Extractor::new().kind(kind).extract(&hir);
+            let literals = seq.literals()?;
+            (!literals.is_empty() && literals.iter().all(|literal| literal.len() >= 3)).then(|| {
+                literals
+                    .iter()
+                    .map(|literal| literal.as_bytes().to_vec())
+                    .collect::<Vec<_>>()
+            })
+        })
+        .min_by_key(Vec::len)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(names: &[&'static str]) -> TrigramIndex {
+        let mut index = TrigramIndex::default();
+        for name in names {
+            index.insert(name);
+        }
+        index
+    }
+
+    #[test]
+    fn test_candidates() {
+        let index = index(&["hello", "world", "hello world", "yellow"]);
+        assert_eq!(index.candidates(&[b"ello"]), Some(vec![0, 2, 3]));
+        assert_eq!(index.candidates(&[b"lo w"]), Some(vec![2]));
+        assert_eq!(index.candidates(&[b"rld", b"yel"]), Some(vec![1, 2, 3]));
+        assert_eq!(index.candidates(&[b"xyz"]), Some(vec![]));
+        // Too short to narrow anything down.
+        assert_eq!(index.candidates(&[b"lo"]), None);
+        assert_eq!(index.candidates(&[b"rld", b"lo"]), None);
+        assert_eq!(index.candidates(&[]), None);
+    }
+
+    #[test]
+    fn test_candidates_ignore_ascii_case() {
+        let index = index(&["Hello", "HELLO.txt", "help", "héllo", "Ünïcode"]);
+        assert_eq!(index.candidates(&[b"hello"]), Some(vec![0, 1]));
+        assert_eq!(index.candidates(&[b"LLO"]), Some(vec![0, 1, 3]));
+        // Names outside of ASCII are always kept.
+        assert_eq!(index.ascii_candidates(&[b"hello"]), Some(vec![0, 1, 3, 4]));
+        assert_eq!(index.ascii_candidates(&[b"he"]), None);
+    }
+
+    #[test]
+    fn test_candidates_are_a_superset() {
+        // Every trigram of `abcab` is in `abcxbcab`, which doesn't contain it.
+        let index = index(&["abcxbcab", "abcab"]);
+        assert_eq!(index.candidates(&[b"abcab"]), Some(vec![0, 1]));
+    }
+
+    #[test]
+    fn test_regex_literals() {
+        let literals = |pattern: &str| {
+            let mut literals = regex_literals(pattern);
+            literals.sort();
+            literals
+                .into_iter()
+                .map(|literal| String::from_utf8(literal).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(literals(r"\.md$"), [".MD", ".Md", ".mD", ".md"]);
+        // Every spelling `(?i)` accepts, down to the long s.
+        assert_eq!(literals(r"\.rs$").len(), 6);
+        assert_eq!(literals(r"(?-i)\.rs$"), [".rs"]);
+        assert_eq!(literals("(?-i)report_\\d+"), ["report_"]);
+        assert_eq!(literals("(?-i)foo|bar"), ["bar", "foo"]);
+        // Nothing every match has to contain.
+        assert!(literals(r"\d+").is_empty());
+        assert!(literals("(?-i)ab.*").is_empty());
+        assert!(literals("(").is_empty());
+    }
+}
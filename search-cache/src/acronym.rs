@@ -0,0 +1,317 @@
+use crate::{
+    ResultDetails, SearchCache, SearchOptions, SegmentKind, SlabIndex,
+    relevance::name_terms,
+    segment::{segment_kind, segment_value},
+    wildcard::has_wildcards,
+};
+use cardinal_syntax::{Expr, FilterKind, Term};
+use query_segmentation::query_segmentation;
+use std::ops::Range;
+
+/// A query segment read as the starts of the words in a name, like the "go to
+/// file" dialogs of IDEs: `fbs` and `FoBaSe` both find `FooBarService.swift`.
+///
+/// Words start at the beginning of the name, at camel-case humps, at digits and
+/// after `_`, `-`, `.` and spaces. Every word the query touches has to be
+/// entered through its first character, after which the query may continue
+/// inside the word; words may be skipped. Letters are compared ignoring case.
+///
+/// The segment kind anchors the match: prefixes and exact segments start with
+/// the first word of the name, suffixes and exact segments end in its last word.
+#[derive(Debug, Clone)]
+pub(crate) struct AcronymPattern {
+    kind: SegmentKind,
+    /// Lowercase.
+    needle: Vec<char>,
+}
+
+/// A character of a name being matched.
+struct NameChar {
+    offset: usize,
+    lower: char,
+    word_start: bool,
+}
+
+impl AcronymPattern {
+    pub(crate) fn new(kind: SegmentKind, needle: &str) -> Self {
+        Self {
+            kind,
+            needle: needle.chars().flat_map(char::to_lowercase).collect(),
+        }
+    }
+
+    pub(crate) fn matches(&self, candidate: &str) -> bool {
+        self.positions(candidate).is_some()
+    }
+
+    /// Byte offsets of the name characters the query matched, in order.
+    pub(crate) fn positions(&self, candidate: &str) -> Option<Vec<usize>> {
+        if self.needle.is_empty() {
+            return None;
+        }
+        let chars = name_chars(candidate);
+        let last_word = chars.iter().rposition(|char| char.word_start)?;
+        let mut matcher = Matcher {
+            needle: &self.needle,
+            chars: &chars,
+            last_word: matches!(self.kind, SegmentKind::Suffix | SegmentKind::Exact)
+                .then_some(last_word),
+            failed: vec![false; self.needle.len() * chars.len()],
+            matched: Vec::with_capacity(self.needle.len()),
+        };
+        let found = match self.kind {
+            SegmentKind::Prefix | SegmentKind::Exact => matcher.enter_word(0, 0),
+            SegmentKind::Substr | SegmentKind::Suffix => {
+                (0..chars.len()).any(|start| matcher.enter_word(0, start))
+            }
+        };
+        found.then(|| {
+            matcher
+                .matched
+                .iter()
+                .map(|&index| chars[index].offset)
+                .collect()
+        })
+    }
+
+    /// [`Self::positions`] with neighbouring characters merged into byte ranges.
+    pub(crate) fn ranges(&self, candidate: &str) -> Option<Vec<Range<usize>>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for offset in self.positions(candidate)? {
+            let end = offset + candidate[offset..].chars().next().map_or(0, char::len_utf8);
+            match ranges.last_mut() {
+                Some(last) if last.end == offset => last.end = end,
+                _ => ranges.push(offset..end),
+            }
+        }
+        Some(ranges)
+    }
+}
+
+/// Depth-first search for the needle; `failed[needle * chars + char]`
+/// remembers the states already known to lead nowhere.
+struct Matcher<'a> {
+    needle: &'a [char],
+    chars: &'a [NameChar],
+    /// Where the last word starts when the match has to end in it.
+    last_word: Option<usize>,
+    failed: Vec<bool>,
+    /// Indices of the matched characters so far.
+    matched: Vec<usize>,
+}
+
+impl Matcher<'_> {
+    /// Matches `needle[at..]` with `needle[at]` entering the word at `char`.
+    fn enter_word(&mut self, at: usize, char: usize) -> bool {
+        self.chars[char].word_start && self.step(at, char)
+    }
+
+    /// Matches `needle[at..]` with `needle[at]` on `char`, either a word start
+    /// or the character right after the previous match.
+    fn step(&mut self, at: usize, char: usize) -> bool {
+        let state = at * self.chars.len() + char;
+        if self.failed[state] || self.chars[char].lower != self.needle[at] {
+            return false;
+        }
+        self.matched.push(char);
+        let next = at + 1;
+        let found = if next == self.needle.len() {
+            self.last_word.is_none_or(|last_word| char >= last_word)
+        } else {
+            // Keep going inside the word, or enter a later one.
+            (char + 1 < self.chars.len()
+                && !self.chars[char + 1].word_start
+                && self.step(next, char + 1))
+                || (char + 1..self.chars.len()).any(|start| self.enter_word(next, start))
+        };
+        if !found {
+            self.matched.pop();
+            self.failed[state] = true;
+        }
+        found
+    }
+}
+
+fn name_chars(name: &str) -> Vec<NameChar> {
+    let chars: Vec<(usize, char)> = name.char_indices().collect();
+    chars
+        .iter()
+        .enumerate()
+        .map(|(i, &(offset, char))| {
+            let previous = i.checked_sub(1).map(|i| chars[i].1);
+            let next = chars.get(i + 1).map(|&(_, char)| char);
+            NameChar {
+                offset,
+                lower: char.to_lowercase().next().unwrap_or(char),
+                word_start: is_word_start(previous, char, next),
+            }
+        })
+        .collect()
+}
+
+fn is_word_start(previous: Option<char>, char: char, next: Option<char>) -> bool {
+    if matches!(char, '_' | '-' | '.' | ' ') {
+        return false;
+    }
+    let Some(previous) = previous else {
+        return true;
+    };
+    if matches!(previous, '_' | '-' | '.' | ' ') {
+        return true;
+    }
+    if char.is_numeric() {
+        return !previous.is_numeric();
+    }
+    if previous.is_numeric() {
+        return true;
+    }
+    // `fooBar`, and `S` in `HTTPServer`.
+    char.is_uppercase()
+        && (previous.is_lowercase()
+            || (previous.is_uppercase() && next.is_some_and(char::is_lowercase)))
+}
+
+/// Finds where each result of a search with acronyms matched, for
+/// [`SearchResultNode::acronym_positions`](crate::SearchResultNode::acronym_positions).
+#[derive(Debug, Clone)]
+pub(crate) struct AcronymHighlighter {
+    /// The name segment of every word read as an acronym.
+    patterns: Vec<AcronymPattern>,
+}
+
+impl AcronymHighlighter {
+    /// `None` when nothing in the query is read as an acronym.
+    pub(crate) fn new(expr: &Expr, options: SearchOptions) -> Option<Self> {
+        let terms = if options.acronym && !options.use_regex {
+            name_terms(expr)
+        } else {
+            let mut terms = Vec::new();
+            collect_acronym_terms(expr, &mut terms);
+            terms
+        };
+        let patterns: Vec<_> = terms
+            .into_iter()
+            .filter_map(|text| {
+                query_segmentation(text).last().map(|segment| {
+                    AcronymPattern::new(segment_kind(segment), segment_value(segment))
+                })
+            })
+            .collect();
+        (!patterns.is_empty()).then_some(Self { patterns })
+    }
+
+    /// The positions of the first word the name matches.
+    fn positions(&self, name: &str) -> Option<Vec<usize>> {
+        self.patterns
+            .iter()
+            .find_map(|pattern| pattern.positions(name))
+    }
+}
+
+/// The arguments of `acronym:` modifiers outside of negations.
+fn collect_acronym_terms<'e>(expr: &'e Expr, terms: &mut Vec<&'e str>) {
+    match expr {
+        Expr::Term(Term::Filter(filter)) if filter.kind == FilterKind::Acronym => {
+            terms.extend(
+                filter
+                    .argument
+                    .iter()
+                    .map(|argument| argument.raw.as_str())
+                    .filter(|raw| !has_wildcards(raw)),
+            );
+        }
+        Expr::And(parts) | Expr::Or(parts) => {
+            for part in parts {
+                collect_acronym_terms(part, terms);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl SearchCache {
+    /// Where the node's name matched the acronyms of the search.
+    pub(crate) fn acronym_positions(
+        &self,
+        details: &ResultDetails,
+        index: SlabIndex,
+    ) -> Option<Vec<usize>> {
+        let highlighter = details.acronym.as_ref()?;
+        highlighter.positions(self.file_nodes.get(index)?.name_and_parent.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(kind: SegmentKind, needle: &str, name: &str) -> Option<Vec<usize>> {
+        AcronymPattern::new(kind, needle).positions(name)
+    }
+
+    fn substr(needle: &str, name: &str) -> Option<Vec<usize>> {
+        positions(SegmentKind::Substr, needle, name)
+    }
+
+    #[test]
+    fn test_word_starts() {
+        let starts = |name: &str| -> String {
+            name_chars(name)
+                .iter()
+                .zip(name.chars())
+                .filter(|(char, _)| char.word_start)
+                .map(|(_, char)| char)
+                .collect()
+        };
+        assert_eq!(starts("FooBarService.swift"), "FBSs");
+        assert_eq!(starts("HTTPServer2_config-v10 final"), "HS2cv1f");
+        assert_eq!(starts("__init__.py"), "ip");
+        assert_eq!(starts("ÉtéÀParis"), "ÉÀP");
+    }
+
+    #[test]
+    fn test_initials_and_humps() {
+        assert_eq!(substr("fbs", "FooBarService.swift"), Some(vec![0, 3, 6]));
+        assert_eq!(
+            substr("FoBaSe", "FooBarService.swift"),
+            Some(vec![0, 1, 3, 4, 6, 7])
+        );
+        assert_eq!(substr("fs", "FooBarService.swift"), Some(vec![0, 6]));
+        assert_eq!(substr("bss", "FooBarService.swift"), Some(vec![3, 6, 14]));
+        assert_eq!(substr("hs2", "HTTPServer2"), Some(vec![0, 4, 10]));
+        assert_eq!(substr("mrt", "my-react_tool.ts"), Some(vec![0, 3, 9]));
+        // Letters inside a word can't start the match.
+        assert_eq!(substr("oob", "FooBarService.swift"), None);
+        assert_eq!(substr("fsb", "FooBarService.swift"), None);
+        // Backtracks out of `Fo` to take the `o` of `Obar`.
+        assert_eq!(substr("fob", "Fo_Obar"), Some(vec![0, 3, 4]));
+    }
+
+    #[test]
+    fn test_anchored_kinds() {
+        assert!(positions(SegmentKind::Prefix, "fb", "FooBarService").is_some());
+        assert!(positions(SegmentKind::Prefix, "bs", "FooBarService").is_none());
+        assert!(positions(SegmentKind::Suffix, "bs", "FooBarService").is_some());
+        assert!(positions(SegmentKind::Suffix, "fb", "FooBarService").is_none());
+        assert!(positions(SegmentKind::Exact, "fbs", "FooBarService").is_some());
+        assert!(positions(SegmentKind::Exact, "fb", "FooBarService").is_none());
+        // The last word is `swift` here.
+        assert_eq!(
+            positions(SegmentKind::Exact, "fbs", "FooBarService.swift"),
+            Some(vec![0, 3, 14])
+        );
+    }
+
+    #[test]
+    fn test_ranges() {
+        let ranges = |needle: &str, name: &str| {
+            AcronymPattern::new(SegmentKind::Substr, needle).ranges(name)
+        };
+        assert_eq!(
+            ranges("FoBaSe", "FooBarService.swift"),
+            Some(vec![0..2, 3..5, 6..8])
+        );
+        assert_eq!(ranges("étàp", "ÉtéÀParis"), Some(vec![0..3, 5..8]));
+        assert_eq!(ranges("xyz", "FooBarService"), None);
+    }
+}
@@ -0,0 +1,204 @@
+use anyhow::{Result, bail};
+use cardinal_syntax::{ArgumentKind, FilterArgument};
+
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFLNK: u32 = 0o120000;
+const S_ISUID: u32 = 0o4000;
+const S_ISGID: u32 = 0o2000;
+const S_ISVTX: u32 = 0o1000;
+const S_IWUSR: u32 = 0o200;
+const EXECUTE_BITS: u32 = 0o111;
+
+/// Unix take on Everything's file attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Attribute {
+    /// `H`: dot-files.
+    Hidden,
+    /// `R`: the owner can't write to it.
+    ReadOnly,
+    /// `X`: anyone may execute it. Folders don't count.
+    Executable,
+    /// `L`
+    Symlink,
+    /// `D`
+    Directory,
+    /// `S`
+    Setuid,
+    /// `G`
+    Setgid,
+    /// `T`
+    Sticky,
+}
+
+impl Attribute {
+    fn from_letter(letter: char) -> Option<Self> {
+        Some(match letter.to_ascii_uppercase() {
+            'H' => Self::Hidden,
+            'R' => Self::ReadOnly,
+            'X' => Self::Executable,
+            'L' => Self::Symlink,
+            'D' => Self::Directory,
+            'S' => Self::Setuid,
+            'G' => Self::Setgid,
+            'T' => Self::Sticky,
+            _ => return None,
+        })
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "hidden" => Self::Hidden,
+            "readonly" | "read-only" => Self::ReadOnly,
+            "executable" | "exec" => Self::Executable,
+            "symlink" | "link" => Self::Symlink,
+            "directory" | "dir" | "folder" => Self::Directory,
+            "setuid" => Self::Setuid,
+            "setgid" => Self::Setgid,
+            "sticky" => Self::Sticky,
+            _ => return None,
+        })
+    }
+
+    fn matches(self, name: &str, mode: u32) -> bool {
+        let file_type = mode & S_IFMT;
+        match self {
+            Self::Hidden => name.starts_with('.'),
+            Self::ReadOnly => mode & S_IWUSR == 0,
+            Self::Executable => file_type != S_IFDIR && mode & EXECUTE_BITS != 0,
+            Self::Symlink => file_type == S_IFLNK,
+            Self::Directory => file_type == S_IFDIR,
+            Self::Setuid => mode & S_ISUID != 0,
+            Self::Setgid => mode & S_ISGID != 0,
+            Self::Sticky => mode & S_ISVTX != 0,
+        }
+    }
+}
+
+/// Attributes an `attrib:` filter requires, either as letters (`attrib:HR`)
+/// or long names (`attrib:executable`, `attrib:hidden;readonly`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AttributePredicate {
+    required: Vec<Attribute>,
+}
+
+impl AttributePredicate {
+    pub(crate) fn parse(argument: &FilterArgument) -> Result<Self> {
+        let mut required = Vec::new();
+        match &argument.kind {
+            ArgumentKind::List(items) => {
+                for item in items {
+                    parse_attributes(item, &mut required)?;
+                }
+            }
+            _ => parse_attributes(&argument.raw, &mut required)?,
+        }
+        if required.is_empty() {
+            bail!("attrib: requires at least one attribute");
+        }
+        Ok(Self { required })
+    }
+
+    /// Whether any attribute needs the node's mode, i.e. anything but `H`.
+    pub(crate) fn needs_mode(&self) -> bool {
+        self.required
+            .iter()
+            .any(|attribute| *attribute != Attribute::Hidden)
+    }
+
+    pub(crate) fn matches(&self, name: &str, mode: u32) -> bool {
+        self.required
+            .iter()
+            .all(|attribute| attribute.matches(name, mode))
+    }
+}
+
+fn parse_attributes(raw: &str, required: &mut Vec<Attribute>) -> Result<()> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(());
+    }
+    if let Some(attribute) = Attribute::from_name(raw) {
+        required.push(attribute);
+        return Ok(());
+    }
+    for letter in raw.chars() {
+        let Some(attribute) = Attribute::from_letter(letter) else {
+            bail!("Unknown attribute {letter:?} in {raw:?}");
+        };
+        required.push(attribute);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cardinal_syntax::{Expr, Term, parse_query};
+
+    const FILE: u32 = 0o100000;
+    const LINK: u32 = 0o120000;
+
+    fn predicate(query: &str) -> Result<AttributePredicate> {
+        let Expr::Term(Term::Filter(filter)) = parse_query(query).unwrap().expr else {
+            panic!("expected a filter");
+        };
+        AttributePredicate::parse(filter.argument.as_ref().unwrap())
+    }
+
+    #[test]
+    fn test_letters() {
+        let hidden = predicate("attrib:h").unwrap();
+        assert!(hidden.matches(".env", FILE | 0o644));
+        assert!(!hidden.matches("env", FILE | 0o644));
+        assert!(!hidden.needs_mode());
+
+        let readonly = predicate("attrib:R").unwrap();
+        assert!(readonly.matches("a", FILE | 0o444));
+        assert!(!readonly.matches("a", FILE | 0o644));
+
+        let executable = predicate("attrib:X").unwrap();
+        assert!(executable.matches("run.sh", FILE | 0o755));
+        assert!(executable.matches("run.sh", FILE | 0o701));
+        assert!(!executable.matches("bin", S_IFDIR | 0o755));
+
+        assert!(
+            predicate("attrib:D")
+                .unwrap()
+                .matches("bin", S_IFDIR | 0o755)
+        );
+        assert!(predicate("attrib:L").unwrap().matches("link", LINK | 0o777));
+        assert!(predicate("attrib:S").unwrap().matches("su", FILE | 0o4755));
+        assert!(predicate("attrib:G").unwrap().matches("sg", FILE | 0o2755));
+        assert!(
+            predicate("attrib:T")
+                .unwrap()
+                .matches("tmp", S_IFDIR | 0o1777)
+        );
+    }
+
+    #[test]
+    fn test_letters_are_combined() {
+        let predicate = predicate("attrib:HR").unwrap();
+        assert!(predicate.matches(".config", FILE | 0o444));
+        assert!(!predicate.matches(".config", FILE | 0o644));
+        assert!(!predicate.matches("config", FILE | 0o444));
+    }
+
+    #[test]
+    fn test_long_names() {
+        let executable = predicate("attrib:executable").unwrap();
+        assert!(executable.matches("tool", FILE | 0o755));
+        assert!(!executable.matches("tool", FILE | 0o644));
+
+        let list = predicate("attrib:hidden;readonly").unwrap();
+        assert!(list.matches(".lock", FILE | 0o400));
+        assert!(!list.matches(".lock", FILE | 0o600));
+    }
+
+    #[test]
+    fn test_unknown_attribute() {
+        assert!(predicate("attrib:Q").is_err());
+        assert!(predicate("attrib:writable").is_err());
+    }
+}
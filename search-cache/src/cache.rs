@@ -1,20 +1,27 @@
 use crate::{
-    FileNodes, NameIndex, SearchOptions, SearchResultNode, SlabIndex, SlabNode,
-    SlabNodeMetadataCompact, State, ThinSlab,
+    ContentSearchConfig, FileCategories, FileNodes, FilterProvider, NameIndex, SearchOptions,
+    SearchResultNode, SlabIndex, SlabNode, SlabNodeMetadataCompact, SortKey, State, ThinSlab,
+    acronym::AcronymHighlighter,
+    fuzzy::FuzzyScorer,
+    highlight::Highlighter,
+    image_header::{ImageInfo, read_image_info},
+    media_tags::{MediaTags, read_media_tags},
     persistent::{PersistentStorage, read_cache_from_file, write_cache_to_file},
+    relevance::RelevanceScorer,
 };
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 use cardinal_sdk::{EventFlag, FsEvent, ScanType, current_event_id};
-use cardinal_syntax::parse_query;
+use cardinal_syntax::{Expr, FilterKind, MacroRegistry, Query, Term, parse_query};
 use fswalk::{Node, NodeMetadata, WalkData, walk_it};
-use hashbrown::HashSet;
+use hashbrown::{HashMap, HashSet};
 use namepool::NamePool;
+use rayon::prelude::*;
 use search_cancel::CancellationToken;
 use std::{
     ffi::OsStr,
     io::ErrorKind,
     path::{Path, PathBuf},
-    sync::{LazyLock, atomic::AtomicBool},
+    sync::{Arc, LazyLock, atomic::AtomicBool},
     time::Instant,
 };
 use thin_vec::ThinVec;
@@ -27,6 +34,76 @@ pub struct SearchCache {
     pub(crate) name_index: NameIndex,
     ignore_paths: Option<Vec<PathBuf>>,
     cancel: Option<&'static AtomicBool>,
+    pub(crate) file_categories: FileCategories,
+    pub(crate) content_search: ContentSearchConfig,
+    /// User-defined filters such as `proj:`, expanded before evaluation.
+    macros: MacroRegistry,
+    /// Custom filters implemented in code, keyed by lowercase filter name.
+    filter_providers: HashMap<String, Arc<dyn FilterProvider>>,
+    /// Music tags read so far, `None` for audio files without any.
+    media_tags: HashMap<SlabIndex, Option<MediaTags>>,
+    /// Image headers read so far, `None` for pictures that couldn't be parsed.
+    image_info: HashMap<SlabIndex, Option<ImageInfo>>,
+}
+
+/// What [`SearchCache::expand_file_nodes`] reports about each node besides
+/// its path and metadata, which depends on the search that found it. Built by
+/// [`SearchCache::result_details`]; the default reports nothing.
+#[derive(Debug, Clone, Default)]
+pub struct ResultDetails {
+    /// Scores [`SearchResultNode::score`] for searches ranked by relevance.
+    pub(crate) relevance: Option<RelevanceScorer>,
+    /// Measures [`SearchResultNode::distance`] for fuzzy searches.
+    pub(crate) fuzzy: Option<FuzzyScorer>,
+    /// Finds [`SearchResultNode::acronym_positions`] for searches reading
+    /// words as acronyms.
+    pub(crate) acronym: Option<AcronymHighlighter>,
+    /// Finds [`SearchResultNode::highlights`] for searches asking for them.
+    pub(crate) highlight: Option<Highlighter>,
+}
+
+impl ResultDetails {
+    fn new(expr: &Expr, options: SearchOptions) -> Self {
+        Self {
+            relevance: options
+                .sort
+                .filter(|sort| sort.key == SortKey::Relevance)
+                .map(|_| RelevanceScorer::new(expr)),
+            fuzzy: options.fuzzy.then(|| FuzzyScorer::new(expr, options)),
+            acronym: AcronymHighlighter::new(expr, options),
+            highlight: options.highlight.then(|| Highlighter::new(expr, options)),
+        }
+    }
+}
+
+/// One window of search results, as asked for by [`SearchOptions::limit`] and
+/// [`SearchOptions::offset`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchPage<T = SlabIndex> {
+    pub nodes: Vec<T>,
+    /// How many nodes match in total, regardless of the window.
+    pub total: SearchTotal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchTotal {
+    Exact(usize),
+    /// Evaluation stopped once the window was filled, so only a lower bound
+    /// is known. Search again with [`SearchOptions::count_only`] for the
+    /// exact count.
+    AtLeast(usize),
+}
+
+impl SearchTotal {
+    pub fn count(self) -> usize {
+        match self {
+            Self::Exact(count) | Self::AtLeast(count) => count,
+        }
+    }
+
+    pub fn is_exact(self) -> bool {
+        matches!(self, Self::Exact(_))
+    }
 }
 
 impl std::fmt::Debug for SearchCache {
@@ -167,7 +244,93 @@ impl SearchCache {
             name_index,
             ignore_paths,
             cancel,
+            file_categories: FileCategories::default(),
+            content_search: ContentSearchConfig::default(),
+            macros: MacroRegistry::new(),
+            filter_providers: HashMap::new(),
+            media_tags: HashMap::new(),
+            image_info: HashMap::new(),
+        }
+    }
+
+    /// Extension table used by `type:`, `audio:`, `video:`, `doc:` and `exe:`.
+    pub fn file_categories(&self) -> &FileCategories {
+        &self.file_categories
+    }
+
+    /// Allows overriding or extending the category table, e.g. adding `heic` to pictures.
+    pub fn file_categories_mut(&mut self) -> &mut FileCategories {
+        &mut self.file_categories
+    }
+
+    /// Size limit and parallelism of `content:` searches.
+    pub fn content_search_config(&self) -> ContentSearchConfig {
+        self.content_search
+    }
+
+    pub fn content_search_config_mut(&mut self) -> &mut ContentSearchConfig {
+        &mut self.content_search
+    }
+
+    /// Filter macros available to queries, e.g. `proj:` or `pics:`.
+    pub fn macros(&self) -> &MacroRegistry {
+        &self.macros
+    }
+
+    pub fn macros_mut(&mut self) -> &mut MacroRegistry {
+        &mut self.macros
+    }
+
+    /// Keeps a trigram index of the names, so substring, suffix, regex and
+    /// case-insensitive searches only check the names that may match. See
+    /// [`NamePool::set_trigram_index`] for the memory it takes; the names are
+    /// shared by every cache, and so is the index.
+    pub fn set_trigram_index(&self, enabled: bool) {
+        NAME_POOL.set_trigram_index(enabled);
+    }
+
+    pub fn has_trigram_index(&self) -> bool {
+        NAME_POOL.has_trigram_index()
+    }
+
+    /// Makes `name:` filters evaluate through `provider`, replacing any provider
+    /// registered under the same name. Names of built-in filters are rejected.
+    pub fn register_filter_provider(
+        &mut self,
+        name: &str,
+        provider: Arc<dyn FilterProvider>,
+    ) -> Result<()> {
+        let is_custom = match parse_query(&format!("{name}:")).map(|query| query.expr) {
+            Ok(Expr::Term(Term::Filter(filter))) => {
+                matches!(filter.kind, FilterKind::Custom(custom) if custom == name)
+            }
+            _ => false,
+        };
+        if !is_custom {
+            bail!("{name:?} can't be used as a filter name");
         }
+        self.filter_providers
+            .insert(name.to_ascii_lowercase(), provider);
+        Ok(())
+    }
+
+    pub fn unregister_filter_provider(&mut self, name: &str) -> Option<Arc<dyn FilterProvider>> {
+        self.filter_providers.remove(&name.to_ascii_lowercase())
+    }
+
+    pub(crate) fn filter_provider(&self, name: &str) -> Option<Arc<dyn FilterProvider>> {
+        self.filter_providers
+            .get(&name.to_ascii_lowercase())
+            .cloned()
+    }
+
+    /// Replaces the filter macros with the `[macros]` table of a TOML file.
+    pub fn load_macros(&mut self, path: &Path) -> Result<()> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read macros from {}", path.display()))?;
+        self.macros = MacroRegistry::from_toml_str(&content)
+            .with_context(|| format!("Invalid macros in {}", path.display()))?;
+        Ok(())
     }
 
     pub fn search_empty(&self, cancellation_token: CancellationToken) -> Option<Vec<SlabIndex>> {
@@ -175,22 +338,101 @@ impl SearchCache {
     }
 
     #[cfg(test)]
-    pub fn search(&self, line: &str) -> Result<Vec<SlabIndex>> {
+    pub fn search(&mut self, line: &str) -> Result<Vec<SlabIndex>> {
         self.search_with_options(line, SearchOptions::default(), CancellationToken::noop())
             .map(|x| x.unwrap_or_default())
     }
 
     pub fn search_with_options(
-        &self,
+        &mut self,
         line: &str,
         options: SearchOptions,
         cancellation_token: CancellationToken,
     ) -> Result<Option<Vec<SlabIndex>>> {
+        self.search_page(line, options, cancellation_token)
+            .map(|page| page.map(|page| page.nodes))
+    }
+
+    /// Like [`Self::search_with_options`], but also reports how many nodes
+    /// match outside of the requested window.
+    ///
+    /// Without a sort, evaluation stops as soon as the window is filled where
+    /// the query allows it, and the total is then [`SearchTotal::AtLeast`].
+    /// With a sort every match is ranked, but only the window is kept in order.
+    pub fn search_page(
+        &mut self,
+        line: &str,
+        options: SearchOptions,
+        cancellation_token: CancellationToken,
+    ) -> Result<Option<SearchPage>> {
+        let parsed = self.parse_line(line)?;
+        self.search_parsed(&parsed, options, cancellation_token)
+    }
+
+    /// What to report about the nodes found by searching `line` with
+    /// `options`, for [`Self::expand_file_nodes`].
+    pub fn result_details(&self, line: &str, options: SearchOptions) -> Result<ResultDetails> {
+        let parsed = self.parse_line(line)?;
+        Ok(ResultDetails::new(&parsed.expr, options))
+    }
+
+    fn parse_line(&self, line: &str) -> Result<Query> {
         let parsed = parse_query(line).map_err(|err| anyhow!("Failed to parse query: {err}"))?;
+        self.macros
+            .expand(parsed)
+            .map_err(|err| anyhow!("Failed to expand query: {err}"))
+    }
+
+    fn search_parsed(
+        &mut self,
+        parsed: &Query,
+        options: SearchOptions,
+        cancellation_token: CancellationToken,
+    ) -> Result<Option<SearchPage>> {
+        let window_end = options
+            .limit
+            .map(|limit| options.offset.saturating_add(limit));
+        // One node past the window tells whether more would have followed.
+        let stop_after = window_end
+            .filter(|_| options.sort.is_none() && !options.count_only)
+            .map(|end| end.saturating_add(1));
         let search_time = Instant::now();
-        let result = self.evaluate_expr(&parsed.expr, options, cancellation_token);
+        let result = self.evaluate_root(&parsed.expr, options, stop_after, cancellation_token);
         info!("Search time: {:?}", search_time.elapsed());
-        result
+        let Some(mut nodes) = result? else {
+            return Ok(None);
+        };
+        let total = match stop_after {
+            Some(stop_after) if nodes.len() >= stop_after => SearchTotal::AtLeast(nodes.len()),
+            _ => SearchTotal::Exact(nodes.len()),
+        };
+        if options.count_only {
+            return Ok(Some(SearchPage {
+                nodes: Vec::new(),
+                total,
+            }));
+        }
+        if let Some(sort) = options.sort {
+            let sort_time = Instant::now();
+            // Relevance and distance sorts rank by the same scores the
+            // results report.
+            let details = ResultDetails::new(&parsed.expr, options);
+            let sorted = self.sort_nodes(nodes, sort, &details, window_end, cancellation_token);
+            info!("Sort time: {:?}", sort_time.elapsed());
+            let Some(sorted) = sorted else {
+                return Ok(None);
+            };
+            nodes = sorted;
+        }
+        let nodes = match (options.offset, options.limit) {
+            (0, None) => nodes,
+            (offset, limit) => nodes
+                .into_iter()
+                .skip(offset)
+                .take(limit.unwrap_or(usize::MAX))
+                .collect(),
+        };
+        Ok(Some(SearchPage { nodes, total }))
     }
 
     /// Get the path of the node in the slab.
@@ -350,7 +592,16 @@ impl SearchCache {
             info!("Rescan cancelled.");
             return None;
         };
-        *self = new_cache;
+        let file_categories = std::mem::take(&mut self.file_categories);
+        let macros = std::mem::take(&mut self.macros);
+        let filter_providers = std::mem::take(&mut self.filter_providers);
+        *self = Self {
+            file_categories,
+            content_search: self.content_search,
+            macros,
+            filter_providers,
+            ..new_cache
+        };
         Some(())
     }
 
@@ -365,12 +616,23 @@ impl SearchCache {
             info!("Rescan cancelled.");
             return;
         };
-        *self = new_cache;
+        let file_categories = std::mem::take(&mut self.file_categories);
+        let macros = std::mem::take(&mut self.macros);
+        let filter_providers = std::mem::take(&mut self.filter_providers);
+        *self = Self {
+            file_categories,
+            content_search: self.content_search,
+            macros,
+            filter_providers,
+            ..new_cache
+        };
     }
 
     /// Removes a node and its children recursively by index.
     fn remove_node(&mut self, index: SlabIndex) {
         fn remove_single_node(cache: &mut SearchCache, index: SlabIndex) {
+            cache.media_tags.remove(&index);
+            cache.image_info.remove(&index);
             if let Some(node) = cache.file_nodes.try_remove(index) {
                 let removed = cache
                     .name_index
@@ -397,6 +659,12 @@ impl SearchCache {
             name_index,
             ignore_paths: _,
             cancel: _,
+            file_categories: _,
+            content_search: _,
+            macros: _,
+            filter_providers: _,
+            media_tags: _,
+            image_info: _,
         } = self;
         let (path, slab_root, slab) = slab.into_parts();
         let name_index = name_index.into_persistent();
@@ -442,25 +710,56 @@ impl SearchCache {
         options: SearchOptions,
         cancellation_token: CancellationToken,
     ) -> Result<Option<Vec<SearchResultNode>>> {
-        self.search_with_options(&query, options, cancellation_token)
-            .map(|nodes| nodes.map(|nodes| self.expand_file_nodes_inner::<false>(&nodes)))
+        self.query_files_page(query, options, cancellation_token)
+            .map(|page| page.map(|page| page.nodes))
+    }
+
+    /// [`Self::search_page`] with the nodes expanded like [`Self::query_files`].
+    pub fn query_files_page(
+        &mut self,
+        query: String,
+        options: SearchOptions,
+        cancellation_token: CancellationToken,
+    ) -> Result<Option<SearchPage<SearchResultNode>>> {
+        let parsed = self.parse_line(&query)?;
+        let details = ResultDetails::new(&parsed.expr, options);
+        self.search_parsed(&parsed, options, cancellation_token)
+            .map(|page| {
+                page.map(|page| SearchPage {
+                    nodes: self.expand_file_nodes_inner::<false>(&page.nodes, &details),
+                    total: page.total,
+                })
+            })
     }
 
     /// Returns a node info vector with the same length as the input nodes.
     /// If the given node is not found, an empty SearchResultNode is returned.
-    pub fn expand_file_nodes(&mut self, nodes: &[SlabIndex]) -> Vec<SearchResultNode> {
-        self.expand_file_nodes_inner::<true>(nodes)
+    ///
+    /// `details` come from [`Self::result_details`] for the search that found
+    /// the nodes, or are the default when there was none.
+    pub fn expand_file_nodes(
+        &mut self,
+        nodes: &[SlabIndex],
+        details: &ResultDetails,
+    ) -> Vec<SearchResultNode> {
+        self.expand_file_nodes_inner::<true>(nodes, details)
     }
 
     fn expand_file_nodes_inner<const FETCH_META: bool>(
         &mut self,
         nodes: &[SlabIndex],
+        details: &ResultDetails,
     ) -> Vec<SearchResultNode> {
         nodes
             .iter()
             .copied()
             .map(|node_index| {
                 let path = self.node_path(node_index);
+                // Scored before fetching, like the ranking that only saw cached dates.
+                let score = self.relevance_score(details, node_index);
+                let distance = self.fuzzy_distance(details, node_index);
+                let acronym_positions = self.acronym_positions(details, node_index);
+                let highlights = self.highlights(details, node_index);
                 let metadata = self
                     .file_nodes
                     .get_mut(node_index)
@@ -468,10 +767,7 @@ impl SearchCache {
                         match (node.metadata.state(), &path) {
                             (State::None, Some(path)) if FETCH_META => {
                                 // try fetching metadata if it's not cached and cache them
-                                let metadata = match std::fs::symlink_metadata(path) {
-                                    Ok(metadata) => SlabNodeMetadataCompact::some(metadata.into()),
-                                    Err(_) => SlabNodeMetadataCompact::unaccessible(),
-                                };
+                                let metadata = fetch_metadata(path);
                                 node.metadata = metadata;
                                 metadata
                             }
@@ -482,11 +778,84 @@ impl SearchCache {
                 SearchResultNode {
                     path: path.unwrap_or_default(),
                     metadata,
+                    score,
+                    distance,
+                    acronym_positions,
+                    highlights,
                 }
             })
             .collect()
     }
 
+    /// Returns the metadata of the given node, fetching and caching it first if it
+    /// hasn't been loaded yet.
+    pub(crate) fn ensure_metadata(&mut self, index: SlabIndex) -> SlabNodeMetadataCompact {
+        let Some(node) = self.file_nodes.get(index) else {
+            return SlabNodeMetadataCompact::unaccessible();
+        };
+        if !node.metadata.is_none() {
+            return node.metadata;
+        }
+        let metadata = self
+            .node_path(index)
+            .map(|path| fetch_metadata(&path))
+            .unwrap_or_else(SlabNodeMetadataCompact::unaccessible);
+        self.file_nodes[index].metadata = metadata;
+        metadata
+    }
+
+    /// Fetches the metadata of the `nodes` that don't have it yet, statting the
+    /// files in parallel. What was fetched is kept even when cancelled.
+    pub(crate) fn fetch_missing_metadata(
+        &mut self,
+        nodes: &[SlabIndex],
+        token: CancellationToken,
+    ) -> Option<()> {
+        let missing: Vec<(SlabIndex, PathBuf)> = nodes
+            .iter()
+            .copied()
+            .filter(|&index| {
+                self.file_nodes
+                    .get(index)
+                    .is_some_and(|node| node.metadata.is_none())
+            })
+            .filter_map(|index| Some((index, self.node_path(index)?)))
+            .collect();
+        let fetched: Vec<(SlabIndex, SlabNodeMetadataCompact)> = missing
+            .into_par_iter()
+            .filter_map(|(index, path)| {
+                (!token.is_cancelled()).then(|| (index, fetch_metadata(&path)))
+            })
+            .collect();
+        for (index, metadata) in fetched {
+            self.file_nodes[index].metadata = metadata;
+        }
+        (!token.is_cancelled()).then_some(())
+    }
+
+    /// Music tags of the file at `index`, read from disk the first time they're asked for.
+    pub(crate) fn media_tags(&mut self, index: SlabIndex) -> Option<&MediaTags> {
+        if !self.media_tags.contains_key(&index) {
+            let tags = self
+                .node_path(index)
+                .and_then(|path| read_media_tags(&path));
+            self.media_tags.insert(index, tags);
+        }
+        self.media_tags.get(&index)?.as_ref()
+    }
+
+    /// Image header of the file at `index`, read from disk the first time it's asked for.
+    pub(crate) fn image_info(&mut self, index: SlabIndex) -> Option<ImageInfo> {
+        if let Some(info) = self.image_info.get(&index) {
+            return *info;
+        }
+        let info = self
+            .node_path(index)
+            .and_then(|path| read_image_info(&path));
+        self.image_info.insert(index, info);
+        info
+    }
+
     pub fn handle_fs_events(&mut self, events: Vec<FsEvent>) -> Result<(), HandleFSEError> {
         let max_event_id = events.iter().map(|e| e.id).max();
         // If rescan needed, early exit.
@@ -517,6 +886,13 @@ impl SearchCache {
     }
 }
 
+fn fetch_metadata(path: &Path) -> SlabNodeMetadataCompact {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) => SlabNodeMetadataCompact::some(metadata.into()),
+        Err(_) => SlabNodeMetadataCompact::unaccessible(),
+    }
+}
+
 /// Compute the minimal set of paths that must be rescanned for a batch of FsEvents.
 ///
 /// Goals:
@@ -675,7 +1051,15 @@ pub static NAME_POOL: LazyLock<NamePool> = LazyLock::new(NamePool::new);
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::{fs, path::PathBuf};
+    use crate::{
+        Filter, NodeView, SortDirection, SortSpec, planner::NodePredicate, query::NAMES_CHECKED,
+    };
+    use namepool::SearchHint;
+    use std::{
+        fs,
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
     use tempdir::TempDir;
 
     fn guard_indices(result: Result<Option<Vec<SlabIndex>>>) -> Vec<SlabIndex> {
@@ -690,6 +1074,24 @@ mod tests {
             .expect("noop cancellation token should not cancel")
     }
 
+    /// Sorted file names of the nodes matching `query`.
+    fn result_names(cache: &mut SearchCache, query: &str, options: SearchOptions) -> Vec<String> {
+        let mut names: Vec<_> = guard_nodes(cache.query_files_with_options(
+            query.to_string(),
+            options,
+            CancellationToken::noop(),
+        ))
+        .into_iter()
+        .filter_map(|node| {
+            node.path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .collect();
+        names.sort();
+        names
+    }
+
     fn query(cache: &mut SearchCache, query: impl Into<String>) -> Vec<SearchResultNode> {
         guard_nodes(cache.query_files(query.into(), CancellationToken::noop()))
     }
@@ -918,11 +1320,12 @@ mod tests {
         let opts = SearchOptions {
             use_regex: true,
             case_insensitive: false,
+            ..SearchOptions::default()
         };
         let indices =
             guard_indices(cache.search_with_options("foo\\d+", opts, CancellationToken::noop()));
         assert_eq!(indices.len(), 1);
-        let nodes = cache.expand_file_nodes(&indices);
+        let nodes = cache.expand_file_nodes(&indices, &ResultDetails::default());
         assert_eq!(nodes.len(), 1);
         assert!(nodes[0].path.ends_with("foo123.txt"));
 
@@ -930,12 +1333,231 @@ mod tests {
         let opts = SearchOptions {
             use_regex: true,
             case_insensitive: false,
+            ..SearchOptions::default()
         };
         let miss =
             guard_indices(cache.search_with_options("bar\\d+", opts, CancellationToken::noop()));
         assert!(miss.is_empty());
     }
 
+    #[test]
+    fn test_term_modifiers() {
+        let temp_dir = TempDir::new("test_term_modifiers").unwrap();
+        let dir = temp_dir.path();
+        for name in [
+            "README.md",
+            "readme.txt",
+            "Readme-old.md",
+            "q3_report.pdf",
+            "reports.pdf",
+            "annual report.pdf",
+            "song.mp3",
+            "song.mp3.bak",
+        ] {
+            fs::File::create(dir.join(name)).unwrap();
+        }
+
+        let mut cache = SearchCache::walk_fs(dir.to_path_buf());
+        let insensitive = SearchOptions {
+            use_regex: false,
+            case_insensitive: true,
+            ..SearchOptions::default()
+        };
+
+        assert_eq!(
+            result_names(&mut cache, "readme", insensitive),
+            vec!["README.md", "Readme-old.md", "readme.txt"]
+        );
+        // Only the wrapped term turns case-sensitive.
+        assert_eq!(
+            result_names(&mut cache, "case:README md", insensitive),
+            vec!["README.md"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "nocase:readme", SearchOptions::default()),
+            vec!["README.md", "Readme-old.md", "readme.txt"]
+        );
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "case:nocase:readme md",
+                SearchOptions::default()
+            ),
+            vec!["README.md", "Readme-old.md"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "case:regex:^R", insensitive),
+            vec!["README.md", "Readme-old.md"]
+        );
+
+        assert_eq!(
+            result_names(&mut cache, "ww:report", SearchOptions::default()),
+            vec!["annual report.pdf", "q3_report.pdf"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "wholeword:REPORT", insensitive),
+            vec!["annual report.pdf", "q3_report.pdf"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "ww:\"annual report\"", SearchOptions::default()),
+            vec!["annual report.pdf"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "ww:regex:rep.rt", SearchOptions::default()),
+            vec!["annual report.pdf", "q3_report.pdf"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "*.mp3", SearchOptions::default()),
+            vec!["song.mp3"]
+        );
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "nowholefilename:*.mp3",
+                SearchOptions::default()
+            ),
+            vec!["song.mp3", "song.mp3.bak"]
+        );
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "nowholefilename:r?port",
+                SearchOptions::default()
+            ),
+            vec!["annual report.pdf", "q3_report.pdf", "reports.pdf"]
+        );
+        assert!(
+            cache
+                .query_files("case:".into(), CancellationToken::noop())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_search_wildcards() {
+        let temp_dir = TempDir::new("test_search_wildcards").unwrap();
+        let dir = temp_dir.path();
+
+        fs::create_dir_all(dir.join("src/nested")).unwrap();
+        fs::create_dir_all(dir.join("docs")).unwrap();
+        fs::File::create(dir.join("src/main.rs")).unwrap();
+        fs::File::create(dir.join("src/lib.RS")).unwrap();
+        fs::File::create(dir.join("src/main.rs.bak")).unwrap();
+        fs::File::create(dir.join("src/nested/deep.rs")).unwrap();
+        fs::File::create(dir.join("docs/song1.mp3")).unwrap();
+        fs::File::create(dir.join("docs/song12.mp3")).unwrap();
+
+        let mut cache = SearchCache::walk_fs(dir.to_path_buf());
+        let sensitive = SearchOptions::default();
+        let insensitive = SearchOptions {
+            use_regex: false,
+            case_insensitive: true,
+            ..SearchOptions::default()
+        };
+
+        assert_eq!(
+            result_names(&mut cache, "*.rs", sensitive),
+            vec!["deep.rs", "main.rs"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "*.rs", insensitive),
+            vec!["deep.rs", "lib.RS", "main.rs"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "src/*.rs", sensitive),
+            vec!["main.rs"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "src/*.rs", insensitive),
+            vec!["lib.RS", "main.rs"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "m*n.rs", sensitive),
+            vec!["main.rs"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "song?.mp3", sensitive),
+            vec!["song1.mp3"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "SONG*.MP3", insensitive),
+            vec!["song1.mp3", "song12.mp3"]
+        );
+        assert!(result_names(&mut cache, "SONG*.MP3", sensitive).is_empty());
+    }
+
+    #[test]
+    fn test_category_filters() {
+        let temp_dir = TempDir::new("test_category_filters").unwrap();
+        let dir = temp_dir.path();
+
+        fs::create_dir(dir.join("beatles")).unwrap();
+        for name in [
+            "beatles - help.mp3",
+            "queen.FLAC",
+            "movie.mkv",
+            "beatles.pdf",
+            "photo.jpg",
+            "photo.heic",
+            "setup.exe",
+        ] {
+            fs::File::create(dir.join(name)).unwrap();
+        }
+        // Folders never belong to an extension category.
+        fs::create_dir(dir.join("album.mp3")).unwrap();
+
+        let mut cache = SearchCache::walk_fs(dir.to_path_buf());
+
+        assert_eq!(
+            result_names(&mut cache, "audio:", SearchOptions::default()),
+            vec!["beatles - help.mp3", "queen.FLAC"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "audio:beatles", SearchOptions::default()),
+            vec!["beatles - help.mp3"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "video:", SearchOptions::default()),
+            vec!["movie.mkv"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "doc:", SearchOptions::default()),
+            vec!["beatles.pdf"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "exe:", SearchOptions::default()),
+            vec!["setup.exe"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "type:pictures", SearchOptions::default()),
+            vec!["photo.jpg"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "type:\"JPEG image\"", SearchOptions::default()),
+            vec!["photo.jpg"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "beatles type:folder", SearchOptions::default()),
+            vec!["beatles"]
+        );
+        assert!(
+            cache
+                .query_files("type:".into(), CancellationToken::noop())
+                .is_err()
+        );
+
+        cache.file_categories_mut().extend("pictures", ["heic"]);
+        assert_eq!(
+            result_names(&mut cache, "type:picture", SearchOptions::default()),
+            vec!["photo.heic", "photo.jpg"]
+        );
+        cache.file_categories_mut().set("audio", ["flac"]);
+        assert_eq!(
+            result_names(&mut cache, "audio:", SearchOptions::default()),
+            vec!["queen.FLAC"]
+        );
+    }
+
     #[test]
     fn test_search_case_insensitive_option() {
         let temp_dir = TempDir::new("test_search_case_insensitive_option").unwrap();
@@ -948,17 +1570,19 @@ mod tests {
         let opts = SearchOptions {
             use_regex: false,
             case_insensitive: true,
+            ..SearchOptions::default()
         };
         let indices =
             guard_indices(cache.search_with_options("alpha.txt", opts, CancellationToken::noop()));
         assert_eq!(indices.len(), 1);
-        let nodes = cache.expand_file_nodes(&indices);
+        let nodes = cache.expand_file_nodes(&indices, &ResultDetails::default());
         assert_eq!(nodes.len(), 1);
         assert!(nodes[0].path.ends_with("Alpha.TXT"));
 
         let opts = SearchOptions {
             use_regex: false,
             case_insensitive: true,
+            ..SearchOptions::default()
         };
         let miss =
             guard_indices(cache.search_with_options("gamma.txt", opts, CancellationToken::noop()));
@@ -981,7 +1605,7 @@ mod tests {
     fn test_search_with_options_cancelled_returns_none() {
         let temp_dir = TempDir::new("search_with_options_cancelled").unwrap();
         fs::File::create(temp_dir.path().join("file_a.txt")).unwrap();
-        let cache = SearchCache::walk_fs(temp_dir.path().to_path_buf());
+        let mut cache = SearchCache::walk_fs(temp_dir.path().to_path_buf());
 
         let token = CancellationToken::new(2000);
         let _ = CancellationToken::new(2001);
@@ -991,6 +1615,7 @@ mod tests {
             SearchOptions {
                 use_regex: false,
                 case_insensitive: false,
+                ..SearchOptions::default()
             },
             token,
         );
@@ -1306,7 +1931,7 @@ mod tests {
         fs::create_dir(root_path.join("subdir1")).expect("Failed to create subdir1");
         fs::File::create(root_path.join("subdir1/file2.txt")).expect("Failed to create file1.txt");
 
-        let cache = SearchCache::walk_fs(root_path.to_path_buf());
+        let mut cache = SearchCache::walk_fs(root_path.to_path_buf());
 
         // Directory nodes should always carry metadata.
         assert!(cache.file_nodes[cache.file_nodes.root()].metadata.is_some());
@@ -1750,6 +2375,1727 @@ mod tests {
         assert!(infolder_results[0].path.ends_with("nested/child.txt"));
     }
 
+    #[test]
+    fn test_folder_filters_keep_name_order() {
+        let temp_dir = TempDir::new("test_folder_filter_order").unwrap();
+        let root = temp_dir.path();
+        let work = root.join("work");
+        fs::create_dir_all(work.join("sub")).unwrap();
+        for name in ["zeta", "mid", "beta", "alpha", "sub/alpha"] {
+            fs::File::create(work.join(name)).unwrap();
+        }
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        let mut paths = |query: String| -> Vec<String> {
+            guard_nodes(cache.query_files(query, CancellationToken::noop()))
+                .into_iter()
+                .map(|node| {
+                    node.path
+                        .strip_prefix(&work)
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect()
+        };
+
+        let work = work.to_string_lossy().into_owned();
+        // By name like every other search, not in the slab's walk order.
+        assert_eq!(
+            paths(format!(r#"infolder:"{work}""#)),
+            ["alpha", "sub/alpha", "beta", "mid", "sub", "zeta"]
+        );
+        assert_eq!(
+            paths(format!(r#"parent:"{work}""#)),
+            ["alpha", "beta", "mid", "sub", "zeta"]
+        );
+        assert_eq!(
+            paths(format!(r#"infolder:"{work}" !mid"#)),
+            ["alpha", "sub/alpha", "beta", "sub", "zeta"]
+        );
+    }
+
+    #[test]
+    fn test_nosubfolders_filter() {
+        let temp_dir = TempDir::new("test_nosubfolders_filter").unwrap();
+        let root = temp_dir.path();
+        let nested = root.join("nested");
+        fs::create_dir_all(nested.join("deeper")).unwrap();
+        fs::File::create(root.join("top.txt")).unwrap();
+        fs::File::create(nested.join("child.txt")).unwrap();
+        fs::File::create(nested.join("deeper/leaf.txt")).unwrap();
+
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+
+        let standalone = format!(r#"nosubfolders:"{}""#, nested.to_string_lossy());
+        assert_eq!(
+            result_names(&mut cache, &standalone, SearchOptions::default()),
+            vec!["child.txt", "deeper"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "nosubfolders:nested", SearchOptions::default()),
+            vec!["child.txt", "deeper"]
+        );
+
+        let recursive = format!(r#"infolder:"{}" ext:txt"#, nested.to_string_lossy());
+        assert_eq!(
+            result_names(&mut cache, &recursive, SearchOptions::default()),
+            vec!["child.txt", "leaf.txt"]
+        );
+        let shallow = format!(
+            r#"infolder:"{}" nosubfolders: ext:txt"#,
+            nested.to_string_lossy()
+        );
+        assert_eq!(
+            result_names(&mut cache, &shallow, SearchOptions::default()),
+            vec!["child.txt"]
+        );
+
+        assert!(query(&mut cache, "nosubfolders:missing").is_empty());
+        assert!(
+            cache
+                .query_files("nosubfolders:".into(), CancellationToken::noop())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_planned_and_matches_separate_evaluation() {
+        let temp_dir = TempDir::new("test_planned_and").unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src/foo")).unwrap();
+        fs::create_dir_all(root.join("docs/foo_notes")).unwrap();
+        for name in [
+            "src/foo.rs",
+            "src/foo/mod.rs",
+            "src/foo/bar.rs",
+            "src/main.rs",
+            "docs/foo.md",
+            "docs/foo_notes/foo.txt",
+            "docs/Foo.RS",
+            "foo.toml",
+        ] {
+            fs::File::create(root.join(name)).unwrap();
+        }
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        let sorted = |mut nodes: Vec<SlabIndex>| {
+            nodes.sort_unstable();
+            nodes
+        };
+
+        let src = root.join("src").to_string_lossy().into_owned();
+        let docs = root.join("docs").to_string_lossy().into_owned();
+        let above_root = root.parent().unwrap().to_string_lossy().into_owned();
+        let cases: Vec<Vec<String>> = vec![
+            vec!["foo".into(), "ext:rs".into()],
+            vec!["ext:rs".into(), "foo".into()],
+            vec!["file:".into(), "foo".into(), "!ext:md".into()],
+            vec!["folder:".into(), "foo".into()],
+            vec!["ext:rs;md".into(), format!("infolder:\"{docs}\"")],
+            vec![format!("parent:\"{src}\""), "ext:rs".into()],
+            vec![format!("infolder:\"{src}\""), "!bar".into(), "file:".into()],
+            vec![format!("infolder:\"{above_root}\""), "foo".into()],
+            vec!["infolder:missing".into(), "foo".into()],
+            vec!["src/foo".into(), "ext:rs".into()],
+            vec!["<ext:rs | ext:toml>".into(), "!main".into()],
+            vec!["regex:^foo\\.".into(), "!docs/foo".into()],
+            vec!["*.rs".into(), "!<src/ | mod>".into()],
+            vec!["doc:".into(), "foo".into()],
+            vec!["type:folder".into(), "notes".into()],
+            vec!["file:foo".into(), "ext:rs".into()],
+            vec!["folder:foo".into(), format!("infolder:\"{src}\"")],
+        ];
+        for parts in cases {
+            let query = parts.join(" ");
+            let mut expected: Option<Vec<SlabIndex>> = None;
+            for part in &parts {
+                let nodes = cache.search(part).unwrap();
+                expected = Some(match expected {
+                    Some(existing) => existing
+                        .into_iter()
+                        .filter(|index| nodes.contains(index))
+                        .collect(),
+                    None => nodes,
+                });
+            }
+            assert_eq!(
+                sorted(cache.search(&query).unwrap()),
+                sorted(expected.unwrap()),
+                "{query}"
+            );
+        }
+
+        let insensitive = SearchOptions {
+            case_insensitive: true,
+            ..SearchOptions::default()
+        };
+        let names = guard_nodes(cache.query_files_with_options(
+            "FOO ext:rs !infolder:src".to_string(),
+            insensitive,
+            CancellationToken::noop(),
+        ));
+        assert_eq!(names.len(), 1);
+        assert!(names[0].path.ends_with("docs/Foo.RS"));
+
+        // Folder filters walk the slab, including when they point above the root.
+        assert_eq!(
+            sorted(cache.search(&format!("infolder:\"{above_root}\"")).unwrap()),
+            sorted(cache.search_empty(CancellationToken::noop()).unwrap())
+        );
+        assert_eq!(
+            cache.search(&format!("infolder:\"{src}\"")).unwrap().len(),
+            5
+        );
+        assert!(cache.search("infolder:missing").unwrap().is_empty());
+        assert!(cache.search("foo ext:").is_err());
+        assert!(cache.search("foo regex:(").is_err());
+    }
+
+    #[test]
+    fn test_type_filter_sources_from_name() {
+        let temp_dir = TempDir::new("test_type_filter_source").unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("foo")).unwrap();
+        for name in ["foo.rs", "foo/bar.rs", "main.rs"] {
+            fs::File::create(root.join(name)).unwrap();
+        }
+        let cache = SearchCache::walk_fs(root.to_path_buf());
+
+        let expr = parse_query("file:foo").unwrap().expr;
+        let predicate = cache
+            .compile_predicate(&expr, SearchOptions::default(), false)
+            .unwrap()
+            .expect("file:foo compiles to a predicate");
+        // Ranked like the name alone, so it doesn't scan the whole index.
+        assert!(matches!(predicate, NodePredicate::All(_)));
+        assert_eq!(predicate.source_rank(), Some(2));
+        let nodes = cache
+            .predicate_source(&predicate, CancellationToken::noop())
+            .unwrap()
+            .unwrap();
+        let names: Vec<_> = nodes
+            .iter()
+            .map(|&index| cache.file_nodes[index].name_and_parent.as_str())
+            .collect();
+        assert_eq!(names, ["foo.rs"]);
+    }
+
+    #[test]
+    fn test_negations_keep_index_order() {
+        let temp_dir = TempDir::new("test_negations_order").unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("app/node_modules/foo")).unwrap();
+        fs::create_dir_all(root.join("app/target/foo")).unwrap();
+        fs::create_dir_all(root.join("lib/src")).unwrap();
+        for name in [
+            "app/node_modules/foo/index.js",
+            "app/target/foo/foo.d",
+            "app/foo.rs",
+            "lib/src/foo.rs",
+            "lib/src/bar.rs",
+            "foo.toml",
+        ] {
+            fs::File::create(root.join(name)).unwrap();
+        }
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        let all = cache.search_empty(CancellationToken::noop()).unwrap();
+        // What `!a !b ...` should return: the whole index, in index order,
+        // minus everything a negated part matches.
+        let mut without = |negated: &[&str]| -> Vec<SlabIndex> {
+            let mut removed = Vec::new();
+            for part in negated {
+                removed.extend(cache.search(part).unwrap());
+            }
+            all.iter()
+                .copied()
+                .filter(|index| !removed.contains(index))
+                .collect()
+        };
+        let expected = without(&["node_modules/", "target/", "bar"]);
+        let not_foo = without(&["foo"]);
+        let not_paths = without(&["app/node_modules", "app/target"]);
+
+        assert_eq!(
+            cache.search("!node_modules/ !target/ !bar").unwrap(),
+            expected
+        );
+        assert_eq!(cache.search("!foo").unwrap(), not_foo);
+        assert_eq!(
+            cache.search("!app/node_modules !app/target").unwrap(),
+            not_paths
+        );
+        let removed = [
+            cache.search("node_modules/").unwrap(),
+            cache.search("target/").unwrap(),
+        ]
+        .concat();
+        let foo_outside: Vec<SlabIndex> = cache
+            .search("foo")
+            .unwrap()
+            .into_iter()
+            .filter(|index| !removed.contains(index))
+            .collect();
+        assert!(!foo_outside.is_empty());
+        assert_eq!(
+            cache.search("!node_modules/ !target/ foo").unwrap(),
+            foo_outside
+        );
+        // Double negation gives back the matches, in index order.
+        let foo: Vec<SlabIndex> = all
+            .iter()
+            .copied()
+            .filter(|index| cache.search("foo").unwrap().contains(index))
+            .collect();
+        assert_eq!(cache.search("!<!foo>").unwrap(), foo);
+        assert_eq!(
+            cache.search("<foo | bar> <bar | foo.toml>").unwrap().len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_sorted_results() {
+        use SortDirection::{Ascending, Descending};
+
+        let temp_dir = TempDir::new("test_sorted_results").unwrap();
+        let root = temp_dir.path();
+        let docs = root.join("docs");
+        fs::create_dir_all(docs.join("sub")).unwrap();
+        let start = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        for (name, size, age) in [
+            ("b.txt", 3, 20),
+            ("A.rs", 10, 10),
+            ("e.TXT", 0, 30),
+            ("sub/A.rs", 1, 5),
+        ] {
+            let file = fs::File::create(docs.join(name)).unwrap();
+            file.set_len(size).unwrap();
+            file.set_modified(start + std::time::Duration::from_secs(age))
+                .unwrap();
+        }
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        let in_docs = format!("infolder:\"{}\"", docs.display());
+        let mut sorted = |query: &str, key: SortKey, direction: SortDirection| -> Vec<String> {
+            let options = SearchOptions {
+                sort: Some(SortSpec { key, direction }),
+                ..SearchOptions::default()
+            };
+            guard_indices(cache.search_with_options(query, options, CancellationToken::noop()))
+                .into_iter()
+                .map(|index| {
+                    let path = cache.node_path(index).unwrap();
+                    let path = path.strip_prefix(&docs).unwrap();
+                    path.to_string_lossy().into_owned()
+                })
+                .collect()
+        };
+        let files = format!("file: {in_docs}");
+
+        // Equal names fall back to the path, ascending even when descending.
+        assert_eq!(
+            sorted(&in_docs, SortKey::Name, Ascending),
+            ["A.rs", "sub/A.rs", "b.txt", "e.TXT", "sub"]
+        );
+        assert_eq!(
+            sorted(&in_docs, SortKey::Name, Descending),
+            ["sub", "e.TXT", "b.txt", "A.rs", "sub/A.rs"]
+        );
+        assert_eq!(
+            sorted(&in_docs, SortKey::Path, Ascending),
+            ["A.rs", "b.txt", "e.TXT", "sub", "sub/A.rs"]
+        );
+        assert_eq!(
+            sorted(&files, SortKey::Size, Ascending),
+            ["e.TXT", "sub/A.rs", "b.txt", "A.rs"]
+        );
+        assert_eq!(
+            sorted(&files, SortKey::Size, Descending),
+            ["A.rs", "b.txt", "sub/A.rs", "e.TXT"]
+        );
+        assert_eq!(
+            sorted(&files, SortKey::DateModified, Descending),
+            ["e.TXT", "b.txt", "A.rs", "sub/A.rs"]
+        );
+        assert_eq!(
+            sorted(&in_docs, SortKey::Extension, Ascending),
+            ["sub", "A.rs", "sub/A.rs", "b.txt", "e.TXT"]
+        );
+        assert_eq!(
+            sorted(&in_docs, SortKey::Type, Descending),
+            ["b.txt", "e.TXT", "A.rs", "sub/A.rs", "sub"]
+        );
+    }
+
+    #[test]
+    fn test_search_pages() {
+        let temp_dir = TempDir::new("test_search_pages").unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src/foo")).unwrap();
+        fs::create_dir_all(root.join("node_modules/foo")).unwrap();
+        for name in [
+            "src/foo.rs",
+            "src/foo/mod.rs",
+            "src/foo/bar.rs",
+            "src/main.rs",
+            "node_modules/foo/index.js",
+            "node_modules/foo/bar.js",
+            "foo.toml",
+            "bar.md",
+        ] {
+            fs::File::create(root.join(name)).unwrap();
+        }
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        let src = root.join("src").to_string_lossy().into_owned();
+
+        let queries = [
+            String::new(),
+            "foo".into(),
+            "ext:rs".into(),
+            "!bar".into(),
+            "foo !bar".into(),
+            "file: !node_modules/".into(),
+            "foo | bar".into(),
+            format!("infolder:\"{src}\""),
+        ];
+        for query in &queries {
+            let all = cache.search(query).unwrap();
+            for (offset, limit) in [(0, 1), (0, 3), (2, 2), (1, 100), (100, 1)] {
+                let options = SearchOptions {
+                    limit: Some(limit),
+                    offset,
+                    ..SearchOptions::default()
+                };
+                let page = cache
+                    .search_page(query, options, CancellationToken::noop())
+                    .unwrap()
+                    .unwrap();
+                let expected: Vec<_> = all.iter().copied().skip(offset).take(limit).collect();
+                assert_eq!(page.nodes, expected, "{query:?} {offset}+{limit}");
+                match page.total {
+                    SearchTotal::Exact(total) => assert_eq!(total, all.len(), "{query:?}"),
+                    SearchTotal::AtLeast(total) => {
+                        assert!(total > offset + limit && total <= all.len(), "{query:?}")
+                    }
+                }
+            }
+
+            let count_only = SearchOptions {
+                count_only: true,
+                limit: Some(1),
+                ..SearchOptions::default()
+            };
+            let count = cache
+                .search_page(query, count_only, CancellationToken::noop())
+                .unwrap()
+                .unwrap();
+            assert!(count.nodes.is_empty());
+            assert_eq!(count.total, SearchTotal::Exact(all.len()), "{query:?}");
+        }
+
+        // Evaluation stops early once the window is filled.
+        let first = SearchOptions {
+            limit: Some(2),
+            ..SearchOptions::default()
+        };
+        for query in ["", "!bar", "file:"] {
+            let page = cache
+                .search_page(query, first, CancellationToken::noop())
+                .unwrap()
+                .unwrap();
+            assert_eq!(page.total, SearchTotal::AtLeast(3), "{query:?}");
+        }
+
+        // Sorted pages rank every match but only keep the window.
+        let by_name = SortSpec::default();
+        for direction in [SortDirection::Ascending, SortDirection::Descending] {
+            let sort = SortSpec {
+                direction,
+                ..by_name
+            };
+            let full = guard_indices(cache.search_with_options(
+                "ext:rs",
+                SearchOptions {
+                    sort: Some(sort),
+                    ..SearchOptions::default()
+                },
+                CancellationToken::noop(),
+            ));
+            let page = cache
+                .search_page(
+                    "ext:rs",
+                    SearchOptions {
+                        sort: Some(sort),
+                        limit: Some(2),
+                        offset: 1,
+                        ..SearchOptions::default()
+                    },
+                    CancellationToken::noop(),
+                )
+                .unwrap()
+                .unwrap();
+            assert_eq!(page.nodes, full[1..3]);
+            assert_eq!(page.total, SearchTotal::Exact(4));
+        }
+    }
+
+    #[test]
+    fn test_relevance_ranking() {
+        let temp_dir = TempDir::new("test_relevance_ranking").unwrap();
+        let root = temp_dir.path();
+        let registry = root.join(".cargo/registry/src/index");
+        fs::create_dir_all(registry.join("cargo-foo-1.2.3/src")).unwrap();
+        fs::create_dir_all(root.join("src/app")).unwrap();
+        for name in [
+            "src/app/Cargo.toml",
+            "src/app/subcargos.txt",
+            ".cargo/registry/src/index/cargo-foo-1.2.3/Cargo.toml",
+            ".cargo/registry/src/index/cargo-foo-1.2.3/src/cargo_util.rs",
+        ] {
+            fs::File::create(root.join(name)).unwrap();
+        }
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        let relevance = SearchOptions {
+            sort: Some(SortSpec {
+                key: SortKey::Relevance,
+                direction: SortDirection::Ascending,
+            }),
+            case_insensitive: true,
+            ..SearchOptions::default()
+        };
+        let page = cache
+            .query_files_page("cargo".into(), relevance, CancellationToken::noop())
+            .unwrap()
+            .unwrap();
+        let ranked: Vec<_> = page
+            .nodes
+            .iter()
+            .map(|node| node.path.strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        // The project manifest beats everything deep in the registry, the
+        // registry's own manifest included.
+        let manifest = ranked
+            .iter()
+            .position(|path| path == Path::new("src/app/Cargo.toml"))
+            .unwrap();
+        let registry = ranked
+            .iter()
+            .position(|path| path.starts_with(".cargo/registry"))
+            .unwrap();
+        assert!(manifest < registry, "{ranked:?}");
+        assert_eq!(
+            ranked.last().unwrap(),
+            &PathBuf::from("src/app/subcargos.txt")
+        );
+        let scores: Vec<_> = page.nodes.iter().map(|node| node.score.unwrap()).collect();
+        assert!(scores.is_sorted_by(|a, b| a >= b), "{scores:?}");
+
+        // The same page without ranking reports no scores.
+        let page = cache
+            .query_files_page(
+                "cargo".into(),
+                SearchOptions {
+                    sort: None,
+                    ..relevance
+                },
+                CancellationToken::noop(),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(page.nodes.len(), ranked.len());
+        assert!(page.nodes.iter().all(|node| node.score.is_none()));
+    }
+
+    #[test]
+    fn test_fuzzy_search() {
+        let temp_dir = TempDir::new("test_fuzzy_search").unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        for name in [
+            "src/receive.rs",
+            "src/recieve_old.rs",
+            "src/on_reseive.rs",
+            "src/main.rs",
+            "docs/receive.md",
+            "docs/recent.md",
+        ] {
+            fs::File::create(root.join(name)).unwrap();
+        }
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        let fuzzy = SearchOptions {
+            fuzzy: true,
+            sort: Some(SortSpec {
+                key: SortKey::Distance,
+                direction: SortDirection::Ascending,
+            }),
+            ..SearchOptions::default()
+        };
+        let mut search = |query: &str, options: SearchOptions| -> Vec<(String, Option<u32>)> {
+            cache
+                .query_files_page(query.into(), options, CancellationToken::noop())
+                .unwrap()
+                .unwrap()
+                .nodes
+                .into_iter()
+                .map(|node| {
+                    let path = node.path.strip_prefix(root).unwrap();
+                    (path.to_string_lossy().into_owned(), node.distance)
+                })
+                .collect()
+        };
+
+        assert_eq!(search("recieve ext:rs", SearchOptions::default()).len(), 1);
+        assert_eq!(
+            search("recieve ext:rs", fuzzy),
+            [
+                ("src/recieve_old.rs".to_string(), Some(0)),
+                ("src/receive.rs".to_string(), Some(1)),
+                ("src/on_reseive.rs".to_string(), Some(2)),
+            ]
+        );
+        // Path segments keep their meaning: `docs/` must be the parent and
+        // `recieve` has to start the name.
+        assert_eq!(
+            search("docs/recieve", fuzzy),
+            [("docs/receive.md".to_string(), Some(1))]
+        );
+        // Short words stay exact, wildcards are never fuzzy.
+        assert_eq!(search("mn", fuzzy), []);
+        assert_eq!(search("mian.*", fuzzy), []);
+        // Outside of fuzzy searches there is no distance to report.
+        assert!(
+            search("receive", SearchOptions::default())
+                .iter()
+                .all(|(_, distance)| distance.is_none())
+        );
+    }
+
+    #[test]
+    fn test_acronym_search() {
+        let temp_dir = TempDir::new("test_acronym_search").unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("Sources")).unwrap();
+        for name in [
+            "Sources/FooBarService.swift",
+            "Sources/FooBarServiceTests.swift",
+            "Sources/fbs.txt",
+            "Sources/foobar_sync.rs",
+            "Sources/OffBeatSong.mp3",
+        ] {
+            fs::File::create(root.join(name)).unwrap();
+        }
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        let mut search =
+            |query: &str, options: SearchOptions| -> Vec<(String, Option<Vec<usize>>)> {
+                let mut nodes: Vec<_> = cache
+                    .query_files_page(query.into(), options, CancellationToken::noop())
+                    .unwrap()
+                    .unwrap()
+                    .nodes
+                    .into_iter()
+                    .map(|node| {
+                        let name = node
+                            .path
+                            .file_name()
+                            .unwrap()
+                            .to_string_lossy()
+                            .into_owned();
+                        (name, node.acronym_positions)
+                    })
+                    .collect();
+                nodes.sort();
+                nodes
+            };
+        let acronym = SearchOptions {
+            acronym: true,
+            ..SearchOptions::default()
+        };
+
+        let service = |name: &str| (name.to_string(), Some(vec![0, 3, 6]));
+        let expected = [
+            service("FooBarService.swift"),
+            service("FooBarServiceTests.swift"),
+            ("fbs.txt".to_string(), Some(vec![0, 1, 2])),
+        ];
+        assert_eq!(
+            search("fbs", SearchOptions::default()),
+            [("fbs.txt".to_string(), None)]
+        );
+        assert_eq!(search("fbs", acronym), expected);
+        // `ac:` reads a single term as an acronym, the rest of the query as usual.
+        assert_eq!(search("ac:fbs", SearchOptions::default()), expected);
+        assert_eq!(
+            search("ac:fbs ext:swift", SearchOptions::default()),
+            expected[..2]
+        );
+        // Path segments keep their meaning, and `fbs/` must end the name.
+        assert_eq!(search("sources/fbs", acronym), expected);
+        assert_eq!(
+            search("fbs/", acronym),
+            [
+                ("FooBarService.swift".to_string(), Some(vec![0, 3, 14])),
+                ("FooBarServiceTests.swift".to_string(), Some(vec![0, 3, 19])),
+            ]
+        );
+        assert_eq!(
+            search("fsy", acronym),
+            [("foobar_sync.rs".to_string(), Some(vec![0, 7, 8]))]
+        );
+    }
+
+    #[test]
+    fn test_unicode_folding() {
+        let temp_dir = TempDir::new("test_unicode_folding").unwrap();
+        let root = temp_dir.path();
+        // Decomposed like names created on macOS.
+        let resume = "Re\u{301}sume\u{301}.pdf";
+        for name in [resume, "cafe\u{301} notes.txt", "Straße.txt", "STRASSE.md"] {
+            fs::File::create(root.join(name)).unwrap();
+        }
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        let nocase = SearchOptions {
+            case_insensitive: true,
+            ..SearchOptions::default()
+        };
+        let no_accents = SearchOptions {
+            ignore_diacritics: true,
+            ..nocase
+        };
+
+        // Composed queries find decomposed names, whatever the case.
+        assert_eq!(
+            result_names(&mut cache, "café", SearchOptions::default()),
+            ["cafe\u{301} notes.txt"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "Café", SearchOptions::default()),
+            [] as [&str; 0]
+        );
+        assert_eq!(
+            result_names(&mut cache, "CAFÉ", nocase),
+            ["cafe\u{301} notes.txt"]
+        );
+        assert_eq!(result_names(&mut cache, "/résumé.pdf/", nocase), [resume]);
+        assert_eq!(result_names(&mut cache, "ww:résumé", nocase), [resume]);
+        // `?` takes a whole character, decomposed or not.
+        assert_eq!(result_names(&mut cache, "r?sum?.*", nocase), [resume]);
+        assert_eq!(result_names(&mut cache, "résumé*", nocase), [resume]);
+        // Full case folding: `ß` is `ss`.
+        assert_eq!(
+            result_names(&mut cache, "strasse", nocase),
+            ["STRASSE.md", "Straße.txt"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "STRASSE", SearchOptions::default()),
+            ["STRASSE.md"]
+        );
+
+        // Accents only matter without `ignore_diacritics`.
+        assert_eq!(result_names(&mut cache, "resume", nocase), [] as [&str; 0]);
+        assert_eq!(result_names(&mut cache, "resume", no_accents), [resume]);
+        assert_eq!(result_names(&mut cache, "RÉSUME.pdf", no_accents), [resume]);
+        assert_eq!(result_names(&mut cache, "ww:resume", no_accents), [resume]);
+        assert_eq!(result_names(&mut cache, "resum?.*", no_accents), [resume]);
+        assert_eq!(
+            result_names(&mut cache, "cafe ext:txt", no_accents),
+            ["cafe\u{301} notes.txt"]
+        );
+    }
+
+    #[test]
+    fn test_trigram_index_narrows_name_searches() {
+        let temp_dir = TempDir::new("test_trigram_index").unwrap();
+        let root = temp_dir.path();
+        for i in 0..50 {
+            fs::File::create(root.join(format!("filler{i}.txt"))).unwrap();
+        }
+        for name in ["TrigramProbe.rs", "old_trigramprobe.md", "probe.rs"] {
+            fs::File::create(root.join(name)).unwrap();
+        }
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        cache.set_trigram_index(true);
+        assert!(cache.has_trigram_index());
+
+        // Checks that only `candidates` names, along with the names outside
+        // of ASCII the index always hands out, were matched.
+        let mut search = |query: &str, options: SearchOptions, candidates: usize| {
+            NAMES_CHECKED.set(0);
+            let names = result_names(&mut cache, query, options);
+            let checked = NAMES_CHECKED.get();
+            // The pool is shared with the other tests, which add names of their
+            // own. Counted after the search, so it includes any added meanwhile.
+            let non_ascii = NAME_POOL
+                .search_with(
+                    SearchHint::None,
+                    |name| !name.is_ascii(),
+                    CancellationToken::noop(),
+                )
+                .unwrap()
+                .len();
+            assert!(
+                (candidates..=candidates + non_ascii).contains(&checked),
+                "{query}: checked {checked} names"
+            );
+            names
+        };
+        let nocase = SearchOptions {
+            case_insensitive: true,
+            ..SearchOptions::default()
+        };
+        assert_eq!(
+            search("trigramprobe", nocase, 2),
+            ["TrigramProbe.rs", "old_trigramprobe.md"]
+        );
+        assert_eq!(
+            search("regex:Probe\\.rs$", SearchOptions::default(), 2),
+            ["TrigramProbe.rs"]
+        );
+        assert_eq!(
+            search("regex:probe\\.", nocase, 3),
+            ["TrigramProbe.rs", "old_trigramprobe.md", "probe.rs"]
+        );
+        cache.set_trigram_index(false);
+    }
+
+    #[test]
+    fn test_highlights() {
+        let temp_dir = TempDir::new("test_highlights").unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        for name in [
+            "src/main.rs",
+            "docs/Main Notes.md",
+            "Re\u{301}sume\u{301}.pdf",
+        ] {
+            fs::File::create(root.join(name)).unwrap();
+        }
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        type Highlights = Option<Vec<(usize, usize, usize)>>;
+        let mut search = |query: &str, options: SearchOptions| -> Vec<(String, Highlights)> {
+            let mut nodes: Vec<_> = cache
+                .query_files_page(query.into(), options, CancellationToken::noop())
+                .unwrap()
+                .unwrap()
+                .nodes
+                .into_iter()
+                .map(|node| {
+                    let name = node
+                        .path
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned();
+                    let highlights = node.highlights.map(|ranges| {
+                        ranges
+                            .into_iter()
+                            .map(|range| (range.level, range.start, range.end))
+                            .collect()
+                    });
+                    (name, highlights)
+                })
+                .collect();
+            nodes.sort();
+            nodes
+        };
+        let highlight = SearchOptions {
+            highlight: true,
+            ..SearchOptions::default()
+        };
+        let nocase = SearchOptions {
+            case_insensitive: true,
+            ..highlight
+        };
+        let found = |name: &str, ranges: &[(usize, usize, usize)]| {
+            (name.to_string(), Some(ranges.to_vec()))
+        };
+
+        assert_eq!(
+            search("main.rs", SearchOptions::default()),
+            [("main.rs".to_string(), None)]
+        );
+        assert_eq!(search("main", highlight), [found("main.rs", &[(0, 0, 4)])]);
+        assert_eq!(
+            search("MAIN", nocase),
+            [
+                found("Main Notes.md", &[(0, 0, 4)]),
+                found("main.rs", &[(0, 0, 4)])
+            ]
+        );
+        // Every segment of a path query lights up its own component.
+        assert_eq!(
+            search("src/main", highlight),
+            [found("main.rs", &[(0, 0, 4), (1, 0, 3)])]
+        );
+        // Only the branch of the query that matched counts.
+        assert_eq!(
+            search("<docs/ | main.rs> !lib", highlight),
+            [found("docs", &[(0, 0, 4)]), found("main.rs", &[(0, 0, 7)])]
+        );
+        assert_eq!(
+            search("regex:a.n\\.rs$", highlight),
+            [found("main.rs", &[(0, 1, 7)])]
+        );
+        assert_eq!(search("*.rs", highlight), [found("main.rs", &[(0, 4, 7)])]);
+        // Separators around a whole word stay out.
+        assert_eq!(
+            search("ww:notes", nocase),
+            [found("Main Notes.md", &[(0, 5, 10)])]
+        );
+        // Ranges cover the name's own bytes, however it was folded.
+        let no_accents = SearchOptions {
+            ignore_diacritics: true,
+            ..nocase
+        };
+        assert_eq!(
+            search("resume", no_accents),
+            [found("Re\u{301}sume\u{301}.pdf", &[(0, 0, 10)])]
+        );
+        assert_eq!(
+            search("acronym:mn", highlight),
+            [found("Main Notes.md", &[(0, 0, 1), (0, 5, 6)])]
+        );
+
+        // Nodes are expanded with the details of the search they came from,
+        // not whichever search ran last.
+        let indices = cache
+            .search_with_options("main", highlight, CancellationToken::noop())
+            .unwrap()
+            .unwrap();
+        let details = cache.result_details("main", highlight).unwrap();
+        cache
+            .search_with_options("notes", nocase, CancellationToken::noop())
+            .unwrap();
+        let nodes = cache.expand_file_nodes(&indices, &details);
+        let ranges: Vec<_> = nodes[0]
+            .highlights
+            .iter()
+            .flatten()
+            .map(|range| (range.level, range.start, range.end))
+            .collect();
+        assert_eq!(ranges, [(0, 0, 4)]);
+        let nodes = cache.expand_file_nodes(&indices, &ResultDetails::default());
+        assert_eq!(nodes[0].highlights, None);
+    }
+
+    #[test]
+    fn test_child_filter() {
+        let temp_dir = TempDir::new("test_child_filter").unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("crate_a/src")).unwrap();
+        fs::create_dir_all(root.join("crate_b")).unwrap();
+        fs::create_dir_all(root.join("web")).unwrap();
+        fs::create_dir_all(root.join("backup")).unwrap();
+        fs::File::create(root.join("crate_a/Cargo.toml")).unwrap();
+        fs::File::create(root.join("crate_a/src/main.rs")).unwrap();
+        fs::File::create(root.join("crate_b/Cargo.toml")).unwrap();
+        fs::File::create(root.join("web/package.json")).unwrap();
+        fs::File::create(root.join("backup/Cargo.toml.bak")).unwrap();
+
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+
+        assert_eq!(
+            result_names(&mut cache, "child:/Cargo.toml/", SearchOptions::default()),
+            vec!["crate_a", "crate_b"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "child:Cargo.toml", SearchOptions::default()),
+            vec!["backup", "crate_a", "crate_b"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "child:package.json", SearchOptions::default()),
+            vec!["web"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "child:*.rs", SearchOptions::default()),
+            vec!["src"]
+        );
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "child:regex:^Cargo\\.toml$",
+                SearchOptions::default()
+            ),
+            vec!["crate_a", "crate_b"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "crate child:*.toml", SearchOptions::default()),
+            vec!["crate_a", "crate_b"]
+        );
+        assert!(
+            cache
+                .query_files("child:".into(), CancellationToken::noop())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_duplicate_filters() {
+        let temp_dir = TempDir::new("test_duplicate_filters").unwrap();
+        let root = temp_dir.path();
+        for dir in ["a", "b", "c"] {
+            fs::create_dir(root.join(dir)).unwrap();
+        }
+        fs::write(root.join("a/report.pdf"), b"1").unwrap();
+        fs::write(root.join("b/report.pdf"), b"22").unwrap();
+        fs::write(root.join("a/report.docx"), b"333").unwrap();
+        fs::write(root.join("a/notes.txt"), b"hello").unwrap();
+        fs::write(root.join("c/todo.md"), b"world").unwrap();
+        // Only report.docx and todo.md share a modification time.
+        for (name, secs) in [
+            ("a/report.pdf", 1_500_000_000),
+            ("b/report.pdf", 1_500_000_100),
+            ("a/report.docx", 1_592_222_400),
+            ("a/notes.txt", 1_500_000_200),
+            ("c/todo.md", 1_592_222_400),
+        ] {
+            let file = fs::File::options()
+                .write(true)
+                .open(root.join(name))
+                .unwrap();
+            file.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+                .unwrap();
+        }
+
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        let relative = |cache: &mut SearchCache, query: &str| -> Vec<String> {
+            let root = cache.file_nodes.path().to_path_buf();
+            guard_nodes(cache.query_files(query.to_string(), CancellationToken::noop()))
+                .into_iter()
+                .map(|node| {
+                    node.path
+                        .strip_prefix(&root)
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            relative(&mut cache, "dupe:"),
+            vec!["a/report.pdf", "b/report.pdf"]
+        );
+        assert_eq!(
+            relative(&mut cache, "dupe: report"),
+            vec!["a/report.pdf", "b/report.pdf"]
+        );
+        assert!(relative(&mut cache, "dupe: ext:docx").is_empty());
+        assert_eq!(
+            relative(&mut cache, "namepartdupe: ext:pdf;docx"),
+            vec!["a/report.docx", "a/report.pdf", "b/report.pdf"]
+        );
+        assert_eq!(
+            relative(&mut cache, "sizedupe:"),
+            vec!["a/notes.txt", "c/todo.md"]
+        );
+        assert_eq!(
+            relative(&mut cache, "dmdupe: file:"),
+            vec!["a/report.docx", "c/todo.md"]
+        );
+        assert_eq!(
+            relative(&mut cache, "attribdupe: ext:md;pdf"),
+            vec!["a/report.pdf", "b/report.pdf", "c/todo.md"]
+        );
+
+        // Groups stay together even when their names interleave.
+        let sizes = root.join("sizes");
+        fs::create_dir(&sizes).unwrap();
+        for (name, size) in [("a", 10), ("b", 20), ("c", 10), ("d", 20)] {
+            fs::write(sizes.join(name), vec![0u8; size]).unwrap();
+        }
+        let mut cache = SearchCache::walk_fs(sizes);
+        assert_eq!(relative(&mut cache, "sizedupe:"), vec!["a", "c", "b", "d"]);
+        assert_eq!(
+            relative(&mut cache, "sizedupe: file:"),
+            vec!["a", "c", "b", "d"]
+        );
+    }
+
+    #[test]
+    fn test_filter_macros() {
+        let temp_dir = TempDir::new("test_filter_macros").unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("work/cardinal/src")).unwrap();
+        fs::create_dir_all(root.join("work/other")).unwrap();
+        fs::create_dir(root.join("pics")).unwrap();
+        for name in [
+            "work/cardinal/Cargo.toml",
+            "work/cardinal/src/lib.rs",
+            "work/cardinal/src/lib_test.rs",
+            "work/cardinal/README.md",
+            "work/other/main.rs",
+            "pics/beach.jpg",
+            "pics/city.png",
+            "pics/beach.txt",
+        ] {
+            fs::File::create(root.join(name)).unwrap();
+        }
+        let macros_path = root.join("cardinal.toml");
+        fs::write(
+            &macros_path,
+            format!(
+                "[macros]\nproj = 'infolder:{}/work/$param: ext:rs;toml !test'\npics = \"ext:jpg;png\"\n",
+                root.display()
+            ),
+        )
+        .unwrap();
+
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        cache.load_macros(&macros_path).unwrap();
+
+        assert_eq!(
+            result_names(&mut cache, "proj:cardinal", SearchOptions::default()),
+            vec!["Cargo.toml", "lib.rs"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "pics:", SearchOptions::default()),
+            vec!["beach.jpg", "city.png"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "pics:beach", SearchOptions::default()),
+            vec!["beach.jpg"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "PICS: | proj:other", SearchOptions::default()),
+            vec!["beach.jpg", "city.png", "main.rs"]
+        );
+        assert!(cache.search("unknown:").is_err());
+
+        cache
+            .macros_mut()
+            .insert("wallpapers", "!city pics:")
+            .unwrap();
+        assert_eq!(
+            result_names(&mut cache, "wallpapers:", SearchOptions::default()),
+            vec!["beach.jpg"]
+        );
+        cache.macros_mut().insert("pics", "wallpapers:").unwrap();
+        let err = cache.search("wallpapers:").unwrap_err();
+        assert!(err.to_string().contains("recursive"), "{err}");
+
+        // Macros survive a rescan.
+        cache.macros_mut().remove("wallpapers");
+        cache.macros_mut().insert("pics", "ext:jpg").unwrap();
+        cache.rescan();
+        assert_eq!(
+            result_names(&mut cache, "pics:", SearchOptions::default()),
+            vec!["beach.jpg"]
+        );
+        assert_eq!(cache.macros().len(), 2);
+        assert!(cache.load_macros(&root.join("missing.toml")).is_err());
+    }
+
+    /// Resolves `owner:` from a CODEOWNERS-style list of path prefixes.
+    struct OwnerProvider {
+        owners: Vec<(&'static str, &'static str)>,
+        seen: AtomicUsize,
+    }
+
+    impl FilterProvider for OwnerProvider {
+        fn filter(
+            &self,
+            filter: &Filter,
+            candidates: Vec<SlabIndex>,
+            nodes: NodeView<'_>,
+            token: CancellationToken,
+        ) -> Result<Option<Vec<SlabIndex>>> {
+            let team = filter
+                .argument
+                .as_ref()
+                .ok_or_else(|| anyhow!("owner: requires a team"))?;
+            self.seen.fetch_add(candidates.len(), Ordering::Relaxed);
+            if token.is_cancelled() {
+                return Ok(None);
+            }
+            Ok(Some(
+                candidates
+                    .into_iter()
+                    .filter(|&index| {
+                        let Some(path) = nodes.path(index) else {
+                            return false;
+                        };
+                        let Ok(relative) = path.strip_prefix(nodes.root_path()) else {
+                            return false;
+                        };
+                        self.owners.iter().any(|(prefix, owner)| {
+                            *owner == team.raw && relative.starts_with(prefix)
+                        })
+                    })
+                    .collect(),
+            ))
+        }
+    }
+
+    /// Returns its candidates along with a node it wasn't given and an index
+    /// past the end of the slab.
+    struct SloppyProvider;
+
+    impl FilterProvider for SloppyProvider {
+        fn filter(
+            &self,
+            _filter: &Filter,
+            mut candidates: Vec<SlabIndex>,
+            _nodes: NodeView<'_>,
+            _token: CancellationToken,
+        ) -> Result<Option<Vec<SlabIndex>>> {
+            candidates.push(SlabIndex::new(0));
+            candidates.push(SlabIndex::new(1_000_000));
+            Ok(Some(candidates))
+        }
+    }
+
+    #[test]
+    fn test_filter_provider_results_stay_within_candidates() {
+        let temp_dir = TempDir::new("test_filter_provider_results").unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::File::create(root.join("src/lib.rs")).unwrap();
+        fs::File::create(root.join("Cargo.toml")).unwrap();
+
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        cache
+            .register_filter_provider("sloppy", Arc::new(SloppyProvider))
+            .unwrap();
+        assert_eq!(
+            result_names(&mut cache, "ext:toml sloppy:", SearchOptions::default()),
+            vec!["Cargo.toml"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "sloppy:", SearchOptions::default()),
+            result_names(&mut cache, "", SearchOptions::default())
+        );
+    }
+
+    #[test]
+    fn test_filter_providers() {
+        let temp_dir = TempDir::new("test_filter_providers").unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("core/src")).unwrap();
+        fs::create_dir_all(root.join("ui")).unwrap();
+        for name in [
+            "core/src/lib.rs",
+            "core/Cargo.toml",
+            "ui/app.tsx",
+            "ui/lib.rs",
+        ] {
+            fs::File::create(root.join(name)).unwrap();
+        }
+
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        let provider = Arc::new(OwnerProvider {
+            owners: vec![("core", "team-x"), ("ui", "team-y")],
+            seen: AtomicUsize::new(0),
+        });
+        cache
+            .register_filter_provider("owner", provider.clone())
+            .unwrap();
+
+        assert_eq!(
+            result_names(&mut cache, "owner:team-x", SearchOptions::default()),
+            vec!["Cargo.toml", "core", "lib.rs", "src"]
+        );
+        // Inside an AND the provider only sees what the other parts matched.
+        provider.seen.store(0, Ordering::Relaxed);
+        assert_eq!(
+            result_names(&mut cache, "lib.rs OWNER:team-y", SearchOptions::default()),
+            vec!["lib.rs"]
+        );
+        assert_eq!(provider.seen.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            result_names(&mut cache, "ext:rs !owner:team-x", SearchOptions::default()),
+            vec!["lib.rs"]
+        );
+        assert!(cache.search("owner:").is_err());
+        assert!(cache.search("reviewer:team-x").is_err());
+
+        // Macros can build on providers.
+        cache.macros_mut().insert("core", "owner:team-x").unwrap();
+        assert_eq!(
+            result_names(&mut cache, "ext:toml core:", SearchOptions::default()),
+            vec!["Cargo.toml"]
+        );
+
+        assert!(
+            cache
+                .register_filter_provider("ext", provider.clone())
+                .is_err()
+        );
+        assert!(
+            cache
+                .register_filter_provider("not a name", provider.clone())
+                .is_err()
+        );
+
+        // Providers survive a rescan.
+        cache.rescan();
+        assert_eq!(
+            result_names(&mut cache, "owner:team-y", SearchOptions::default()),
+            vec!["app.tsx", "lib.rs", "ui"]
+        );
+        assert!(cache.unregister_filter_provider("Owner").is_some());
+        assert!(cache.search("owner:team-y").is_err());
+    }
+
+    #[test]
+    fn test_attribute_filter() {
+        use std::os::unix::fs::{PermissionsExt, symlink};
+
+        let temp_dir = TempDir::new("test_attribute_filter").unwrap();
+        let root = temp_dir.path();
+        let set_mode = |name: &str, mode: u32| {
+            fs::set_permissions(root.join(name), fs::Permissions::from_mode(mode)).unwrap();
+        };
+        fs::write(root.join("run.sh"), b"#!/bin/sh").unwrap();
+        set_mode("run.sh", 0o755);
+        fs::write(root.join("frozen.txt"), b"").unwrap();
+        set_mode("frozen.txt", 0o444);
+        fs::write(root.join(".hidden_frozen"), b"").unwrap();
+        set_mode(".hidden_frozen", 0o444);
+        fs::write(root.join(".hidden"), b"").unwrap();
+        fs::write(root.join("plain.txt"), b"").unwrap();
+        set_mode("plain.txt", 0o644);
+        fs::write(root.join("suid"), b"").unwrap();
+        set_mode("suid", 0o4755);
+        fs::create_dir(root.join("bin")).unwrap();
+        symlink(root.join("plain.txt"), root.join("alias")).unwrap();
+
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+
+        assert_eq!(
+            result_names(&mut cache, "attrib:H", SearchOptions::default()),
+            vec![".hidden", ".hidden_frozen"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "attrib:R", SearchOptions::default()),
+            vec![".hidden_frozen", "frozen.txt"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "attrib:HR", SearchOptions::default()),
+            vec![".hidden_frozen"]
+        );
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "attrib:executable file:",
+                SearchOptions::default()
+            ),
+            vec!["run.sh", "suid"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "attrib:S", SearchOptions::default()),
+            vec!["suid"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "attrib:L", SearchOptions::default()),
+            vec!["alias"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "bin attrib:D", SearchOptions::default()),
+            vec!["bin"]
+        );
+        assert!(
+            cache
+                .query_files("attrib:Q".into(), CancellationToken::noop())
+                .is_err()
+        );
+
+        let index = cache.search("run.sh").unwrap()[0];
+        let nodes = cache.expand_file_nodes(&[index], &ResultDetails::default());
+        let mode = nodes[0].metadata.as_ref().unwrap().mode();
+        assert_eq!(mode & 0o7777, 0o755);
+    }
+
+    #[test]
+    fn test_content_filter() {
+        let temp_dir = TempDir::new("test_content_filter").unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/ffi.rs"),
+            "pub fn f() {\n    unsafe { g() }\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/safe.rs"), "pub fn f() {}\n").unwrap();
+        fs::write(root.join("src/notes.md"), "Unsafe code lives in ffi.rs\n").unwrap();
+        fs::write(root.join("src/blob.rs"), b"unsafe\0").unwrap();
+        fs::write(root.join("huge.rs"), format!("{}unsafe", " ".repeat(4096))).unwrap();
+
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+        cache.content_search_config_mut().max_file_size = 1024;
+        let insensitive = SearchOptions {
+            case_insensitive: true,
+            ..SearchOptions::default()
+        };
+
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "ext:rs content:unsafe",
+                SearchOptions::default()
+            ),
+            vec!["ffi.rs"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "content:unsafe", SearchOptions::default()),
+            vec!["ffi.rs"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "content:unsafe", insensitive),
+            vec!["ffi.rs", "notes.md"]
+        );
+        // The content filter only sees what the rest of the query kept.
+        assert_eq!(
+            result_names(&mut cache, "content:unsafe ext:md", insensitive),
+            vec!["notes.md"]
+        );
+        assert_eq!(
+            result_names(
+                &mut cache,
+                r"content:regex:^\s+unsafe",
+                SearchOptions::default()
+            ),
+            vec!["ffi.rs"]
+        );
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "ext:rs content:\"fn f()\"",
+                SearchOptions::default()
+            ),
+            vec!["ffi.rs", "safe.rs"]
+        );
+
+        cache.content_search_config_mut().max_file_size = u64::MAX;
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "ext:rs content:unsafe",
+                SearchOptions::default()
+            ),
+            vec!["ffi.rs", "huge.rs"]
+        );
+    }
+
+    #[test]
+    fn test_image_filters() {
+        use crate::image_header::tests::png_header;
+
+        let temp_dir = TempDir::new("test_image_filters").unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("wallpaper.png"), png_header(3840, 2160)).unwrap();
+        fs::write(root.join("phone.png"), png_header(1080, 1920)).unwrap();
+        fs::write(root.join("icon.PNG"), png_header(512, 512)).unwrap();
+        fs::write(root.join("broken.png"), b"not a png").unwrap();
+        // Only pictures are inspected, whatever they contain.
+        fs::write(root.join("header.bin"), png_header(3840, 2160)).unwrap();
+
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+
+        assert_eq!(
+            result_names(&mut cache, "width:>=3840", SearchOptions::default()),
+            vec!["wallpaper.png"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "height:>1000", SearchOptions::default()),
+            vec!["phone.png", "wallpaper.png"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "dimensions:1080x1920", SearchOptions::default()),
+            vec!["phone.png"]
+        );
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "dimensions:500x500..2000x2000",
+                SearchOptions::default()
+            ),
+            vec!["icon.PNG", "phone.png"]
+        );
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "orientation:landscape",
+                SearchOptions::default()
+            ),
+            vec!["wallpaper.png"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "orientation:portrait", SearchOptions::default()),
+            vec!["phone.png"]
+        );
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "icon orientation:square",
+                SearchOptions::default()
+            ),
+            vec!["icon.PNG"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "bitdepth:32", SearchOptions::default()).len(),
+            3
+        );
+        assert!(
+            cache
+                .query_files("orientation:sideways".into(), CancellationToken::noop())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_music_tag_filters() {
+        use crate::media_tags::tests::{flac_file, id3v2_file};
+
+        let temp_dir = TempDir::new("test_music_tag_filters").unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join("one more time.mp3"),
+            id3v2_file(&[
+                (b"TPE1", "Daft Punk"),
+                (b"TALB", "Discovery"),
+                (b"TYER", "2001"),
+                (b"TRCK", "1/14"),
+            ]),
+        )
+        .unwrap();
+        fs::write(
+            root.join("reckoner.flac"),
+            flac_file(&[
+                "ARTIST=Radiohead",
+                "ALBUM=In Rainbows",
+                "DATE=2007",
+                "TRACKNUMBER=7",
+                "GENRE=Alternative",
+            ]),
+        )
+        .unwrap();
+        fs::write(root.join("untagged.mp3"), b"no tags").unwrap();
+        // Only audio files are inspected, whatever they contain.
+        fs::write(
+            root.join("not audio.txt"),
+            id3v2_file(&[(b"TPE1", "Daft Punk")]),
+        )
+        .unwrap();
+
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+
+        assert_eq!(
+            result_names(&mut cache, "artist:daft", SearchOptions::default()),
+            vec!["one more time.mp3"]
+        );
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "album:\"in rainbows\"",
+                SearchOptions::default()
+            ),
+            vec!["reckoner.flac"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "genre:alt*", SearchOptions::default()),
+            vec!["reckoner.flac"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "year:2000..2010", SearchOptions::default()),
+            vec!["one more time.mp3", "reckoner.flac"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "year:>2001", SearchOptions::default()),
+            vec!["reckoner.flac"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "track:<=1", SearchOptions::default()),
+            vec!["one more time.mp3"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "ext:flac track:7", SearchOptions::default()),
+            vec!["reckoner.flac"]
+        );
+        assert!(result_names(&mut cache, "comment:live", SearchOptions::default()).is_empty());
+        assert!(
+            cache
+                .query_files("year:soon".into(), CancellationToken::noop())
+                .is_err()
+        );
+
+        // Retagging a file drops its cached tags.
+        fs::write(
+            root.join("untagged.mp3"),
+            id3v2_file(&[(b"TPE1", "Daft Punk")]),
+        )
+        .unwrap();
+        cache
+            .handle_fs_events(vec![FsEvent {
+                path: root.join("untagged.mp3"),
+                id: cache.last_event_id + 1,
+                flag: EventFlag::ItemModified,
+            }])
+            .unwrap();
+        assert_eq!(
+            result_names(&mut cache, "artist:daft", SearchOptions::default()),
+            vec!["one more time.mp3", "untagged.mp3"]
+        );
+    }
+
+    #[test]
+    fn test_size_filters() {
+        let temp_dir = TempDir::new("test_size_filters").unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("dir")).unwrap();
+        fs::File::create(root.join("empty.bin")).unwrap();
+        fs::write(root.join("small.bin"), vec![0u8; 2 * 1024]).unwrap();
+        fs::write(root.join("large.bin"), vec![0u8; 2 * 1024 * 1024]).unwrap();
+
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+
+        assert_eq!(
+            result_names(&mut cache, "size:>1mb", SearchOptions::default()),
+            vec!["large.bin"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "size:<=2kib", SearchOptions::default()),
+            vec!["empty.bin", "small.bin"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "size:empty", SearchOptions::default()),
+            vec!["empty.bin"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "size:1kb..1mb", SearchOptions::default()),
+            vec!["small.bin"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "size:2kb", SearchOptions::default()),
+            vec!["small.bin"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "size:!=2mb", SearchOptions::default()),
+            vec!["empty.bin", "small.bin"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "large size:large", SearchOptions::default()),
+            vec!["large.bin"]
+        );
+        assert!(query(&mut cache, "small size:large").is_empty());
+
+        // Metadata fetched while filtering is cached on the node.
+        let large = cache.search("large.bin").unwrap();
+        assert!(cache.file_nodes[large[0]].metadata.is_some());
+
+        assert!(
+            cache
+                .query_files("size:1parsec".into(), CancellationToken::noop())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_date_filters() {
+        let temp_dir = TempDir::new("test_date_filters").unwrap();
+        let root = temp_dir.path();
+        let old = fs::File::create(root.join("old_report.txt")).unwrap();
+        fs::File::create(root.join("new_report.txt")).unwrap();
+        // 2020-06-15T12:00:00Z
+        let old_time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_592_222_400);
+        old.set_modified(old_time).unwrap();
+        drop(old);
+
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+
+        assert_eq!(
+            result_names(&mut cache, "report dm:today", SearchOptions::default()),
+            vec!["new_report.txt"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "report dm:thisweek", SearchOptions::default()),
+            vec!["new_report.txt"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "report dm:2020", SearchOptions::default()),
+            vec!["old_report.txt"]
+        );
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "report dm:2020/6/1-2020/6/30",
+                SearchOptions::default()
+            ),
+            vec!["old_report.txt"]
+        );
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "report dm:<2021-01-01",
+                SearchOptions::default()
+            ),
+            vec!["old_report.txt"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "report dm:>2021", SearchOptions::default()),
+            vec!["new_report.txt"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "report !dm:pastyear", SearchOptions::default()),
+            vec!["old_report.txt"]
+        );
+        assert!(
+            cache
+                .query_files("dm:someday".into(), CancellationToken::noop())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_date_accessed_filter() {
+        let temp_dir = TempDir::new("test_date_accessed_filter").unwrap();
+        let root = temp_dir.path();
+        let stale = fs::File::create(root.join("stale_notes.txt")).unwrap();
+        fs::File::create(root.join("fresh_notes.txt")).unwrap();
+        // 2020-06-15T12:00:00Z
+        let old_time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_592_222_400);
+        stale
+            .set_times(fs::FileTimes::new().set_accessed(old_time))
+            .unwrap();
+        drop(stale);
+
+        let mut cache = SearchCache::walk_fs(root.to_path_buf());
+
+        assert_eq!(
+            result_names(&mut cache, "notes da:2020-06", SearchOptions::default()),
+            vec!["stale_notes.txt"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "notes da:<2021", SearchOptions::default()),
+            vec!["stale_notes.txt"]
+        );
+        assert_eq!(
+            result_names(&mut cache, "notes da:pastweek", SearchOptions::default()),
+            vec!["fresh_notes.txt"]
+        );
+        assert_eq!(
+            result_names(
+                &mut cache,
+                "notes da:2020/1/1..2020/12/31",
+                SearchOptions::default()
+            ),
+            vec!["stale_notes.txt"]
+        );
+        let nodes = query(&mut cache, "stale_notes.txt");
+        assert_eq!(
+            nodes[0]
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.atime())
+                .map(|atime| atime.get()),
+            Some(1_592_222_400)
+        );
+        assert!(
+            cache
+                .query_files("da:".into(), CancellationToken::noop())
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_query_files_metadata_consistency_after_walk_and_event() {
         let temp_dir = TempDir::new("test_query_meta_consistency").unwrap();
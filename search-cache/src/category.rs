@@ -0,0 +1,325 @@
+use crate::fold::Folding;
+use fswalk::NodeFileType;
+use std::collections::{BTreeMap, BTreeSet};
+
+const AUDIO: &str = "audio";
+const COMPRESSED: &str = "compressed";
+const DOCUMENT: &str = "document";
+const EXECUTABLE: &str = "executable";
+const PICTURE: &str = "picture";
+const VIDEO: &str = "video";
+
+/// Alternative spellings accepted wherever a category name is expected.
+const CATEGORY_ALIASES: &[(&str, &str)] = &[
+    ("music", AUDIO),
+    ("sound", AUDIO),
+    ("archive", COMPRESSED),
+    ("zip", COMPRESSED),
+    ("doc", DOCUMENT),
+    ("exe", EXECUTABLE),
+    ("app", EXECUTABLE),
+    ("application", EXECUTABLE),
+    ("program", EXECUTABLE),
+    ("image", PICTURE),
+    ("photo", PICTURE),
+    ("pic", PICTURE),
+    ("movie", VIDEO),
+];
+
+/// Format names that don't match their extensions, used by human file-kind
+/// strings such as `type:"JPEG image"`.
+const FORMAT_EXTENSIONS: &[(&str, &[&str])] = &[
+    ("jpeg", &["jpe", "jpeg", "jpg"]),
+    ("tiff", &["tif", "tiff"]),
+    ("plain text", &["text", "txt"]),
+    ("text", &["text", "txt"]),
+    ("markdown", &["markdown", "md"]),
+    ("word", &["doc", "docm", "docx"]),
+    ("excel", &["xls", "xlsb", "xlsm", "xlsx"]),
+    ("powerpoint", &["pps", "ppsx", "ppt", "pptm", "pptx"]),
+    ("html", &["htm", "html"]),
+    ("midi", &["mid", "midi"]),
+    ("mpeg-4", &["m4v", "mp4"]),
+    ("mpeg", &["mpeg", "mpg"]),
+    ("quicktime", &["mov", "qt"]),
+    ("gzip", &["gz", "gzip", "tgz"]),
+    ("shell", &["command", "sh"]),
+];
+
+/// Extension table behind `type:`, `audio:`, `video:`, `doc:` and `exe:`.
+///
+/// Defaults follow Everything's filters. Category names are case-insensitive,
+/// and aliases like `music` or `image` resolve to their canonical category.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileCategories {
+    categories: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Default for FileCategories {
+    fn default() -> Self {
+        let mut categories = Self::empty();
+        categories.set(
+            AUDIO,
+            [
+                "aac", "ac3", "aif", "aifc", "aiff", "amr", "ape", "au", "cda", "dts", "fla",
+                "flac", "it", "m1a", "m2a", "m3u", "m4a", "m4b", "m4p", "mid", "midi", "mka",
+                "mod", "mp2", "mp3", "mpa", "ogg", "opus", "ra", "rmi", "snd", "spc", "umx", "voc",
+                "wav", "wma", "xm",
+            ],
+        );
+        categories.set(
+            COMPRESSED,
+            [
+                "7z", "ace", "arj", "bz2", "cab", "gz", "gzip", "jar", "rar", "tar", "tgz", "xz",
+                "z", "zip", "zst",
+            ],
+        );
+        categories.set(
+            DOCUMENT,
+            [
+                "c", "chm", "cpp", "csv", "cxx", "doc", "docm", "docx", "dot", "dotm", "dotx", "h",
+                "hpp", "htm", "html", "hxx", "ini", "java", "key", "lua", "md", "mht", "mhtml",
+                "numbers", "odp", "ods", "odt", "pages", "pdf", "potm", "potx", "ppam", "pps",
+                "ppsm", "ppsx", "ppt", "pptm", "pptx", "rtf", "sldm", "sldx", "thmx", "txt", "vsd",
+                "wpd", "wps", "wri", "xlam", "xls", "xlsb", "xlsm", "xlsx", "xltm", "xltx", "xml",
+            ],
+        );
+        categories.set(
+            EXECUTABLE,
+            [
+                "bat", "cmd", "command", "exe", "msi", "msp", "pkg", "scr", "sh",
+            ],
+        );
+        categories.set(
+            PICTURE,
+            [
+                "ani", "bmp", "gif", "ico", "jpe", "jpeg", "jpg", "pcx", "png", "psd", "svg",
+                "tga", "tif", "tiff", "webp", "wmf",
+            ],
+        );
+        categories.set(
+            VIDEO,
+            [
+                "3g2", "3gp", "3gp2", "3gpp", "amv", "asf", "avi", "bik", "divx", "drc", "dv",
+                "f4v", "flv", "gvi", "gxf", "m1v", "m2t", "m2ts", "m2v", "m4v", "mkv", "mov",
+                "mp2v", "mp4", "mp4v", "mpe", "mpeg", "mpeg1", "mpeg2", "mpeg4", "mpg", "mpv2",
+                "mts", "mtv", "mxf", "nsv", "nuv", "ogm", "ogv", "ogx", "rm", "rmvb", "ts", "vob",
+                "webm", "wm", "wmv", "wtv",
+            ],
+        );
+        categories
+    }
+}
+
+impl FileCategories {
+    /// A table without any category.
+    pub fn empty() -> Self {
+        Self {
+            categories: BTreeMap::new(),
+        }
+    }
+
+    /// Replaces the extensions of `category`, creating it when needed.
+    pub fn set<S: AsRef<str>>(&mut self, category: &str, extensions: impl IntoIterator<Item = S>) {
+        let extensions = extensions
+            .into_iter()
+            .filter_map(|ext| normalize_extension(ext.as_ref()))
+            .collect();
+        self.categories
+            .insert(canonical_category(category), extensions);
+    }
+
+    /// Adds extensions to `category`, creating it when needed.
+    pub fn extend<S: AsRef<str>>(
+        &mut self,
+        category: &str,
+        extensions: impl IntoIterator<Item = S>,
+    ) {
+        self.categories
+            .entry(canonical_category(category))
+            .or_default()
+            .extend(
+                extensions
+                    .into_iter()
+                    .filter_map(|ext| normalize_extension(ext.as_ref())),
+            );
+    }
+
+    pub fn remove(&mut self, category: &str) -> Option<BTreeSet<String>> {
+        self.categories.remove(&canonical_category(category))
+    }
+
+    /// Lowercase extensions (without the leading dot) of `category`.
+    pub fn extensions(&self, category: &str) -> Option<&BTreeSet<String>> {
+        self.categories.get(&canonical_category(category))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &BTreeSet<String>)> {
+        self.categories
+            .iter()
+            .map(|(category, extensions)| (category.as_str(), extensions))
+    }
+
+    /// Resolves a `type:` argument: a node kind (`folder`), a category
+    /// (`pictures`), a human file-kind string (`JPEG image`) or an extension (`mp3`).
+    pub(crate) fn resolve_kind(&self, raw: &str) -> Option<FileKind> {
+        let normalized = raw.trim().to_lowercase();
+        let words: Vec<&str> = normalized
+            .split(|c: char| c.is_whitespace() || c == '_')
+            .filter(|word| !word.is_empty())
+            .collect();
+        match words.as_slice() {
+            [] => return None,
+            ["folder" | "folders" | "directory" | "directories" | "dir"] => {
+                return Some(FileKind::Node(NodeFileType::Dir));
+            }
+            ["file" | "files"] => return Some(FileKind::Node(NodeFileType::File)),
+            ["symlink" | "symlinks" | "alias" | "link"] => {
+                return Some(FileKind::Node(NodeFileType::Symlink));
+            }
+            _ => {}
+        }
+        if let Some(extensions) = self.extensions(&words.join(" ")) {
+            return Some(FileKind::Extensions(extensions.clone()));
+        }
+        // "MP3 audio file" => "mp3"
+        let mut format = words.as_slice();
+        while let [rest @ .., last] = format {
+            if rest.is_empty() || !(*last == "file" || self.extensions(last).is_some()) {
+                break;
+            }
+            format = rest;
+        }
+        let name = format.join(" ");
+        if let Some((_, extensions)) = FORMAT_EXTENSIONS.iter().find(|(format, _)| *format == name)
+        {
+            return Some(FileKind::Extensions(
+                extensions.iter().map(|ext| ext.to_string()).collect(),
+            ));
+        }
+        // A single word that isn't a known format is taken as an extension.
+        match format {
+            [extension] => normalize_extension(extension)
+                .map(|extension| FileKind::Extensions(BTreeSet::from([extension]))),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FileKind {
+    Node(NodeFileType),
+    Extensions(BTreeSet<String>),
+}
+
+fn canonical_category(category: &str) -> String {
+    let category = category.trim().to_lowercase();
+    let singular = category.strip_suffix('s').unwrap_or(&category);
+    CATEGORY_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == category || *alias == singular)
+        .map(|(_, canonical)| canonical.to_string())
+        .or_else(|| {
+            [AUDIO, COMPRESSED, DOCUMENT, EXECUTABLE, PICTURE, VIDEO]
+                .contains(&singular)
+                .then(|| singular.to_string())
+        })
+        .unwrap_or(category)
+}
+
+fn normalize_extension(raw: &str) -> Option<String> {
+    let trimmed = raw.trim().trim_start_matches('.');
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(Folding::CASE.apply(trimmed).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extensions(kind: Option<FileKind>) -> Vec<String> {
+        match kind {
+            Some(FileKind::Extensions(extensions)) => extensions.into_iter().collect(),
+            other => panic!("expected extensions, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_aliases_resolve_to_canonical_categories() {
+        let categories = FileCategories::default();
+        assert_eq!(
+            categories.extensions("Pictures"),
+            categories.extensions("picture")
+        );
+        assert_eq!(
+            categories.extensions("music"),
+            categories.extensions("audio")
+        );
+        assert_eq!(
+            categories.extensions("docs"),
+            categories.extensions("document")
+        );
+        assert!(categories.extensions("audio").unwrap().contains("mp3"));
+        assert!(categories.extensions("nonsense").is_none());
+    }
+
+    #[test]
+    fn test_override_and_extend() {
+        let mut categories = FileCategories::default();
+        assert!(!categories.extensions("picture").unwrap().contains("heic"));
+        categories.extend("pictures", [".HEIC"]);
+        assert!(categories.extensions("picture").unwrap().contains("heic"));
+        assert!(categories.extensions("picture").unwrap().contains("png"));
+
+        categories.set("audio", ["mp3"]);
+        assert_eq!(
+            categories
+                .extensions("audio")
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec!["mp3"]
+        );
+
+        categories.extend("code", ["rs", "go"]);
+        assert!(categories.extensions("Code").unwrap().contains("rs"));
+        assert!(categories.remove("code").is_some());
+        assert!(categories.extensions("code").is_none());
+    }
+
+    #[test]
+    fn test_resolve_kind() {
+        let categories = FileCategories::default();
+        assert_eq!(
+            categories.resolve_kind("folder"),
+            Some(FileKind::Node(NodeFileType::Dir))
+        );
+        assert_eq!(
+            categories.resolve_kind("File"),
+            Some(FileKind::Node(NodeFileType::File))
+        );
+        assert!(extensions(categories.resolve_kind("pictures")).contains(&"png".to_string()));
+        assert_eq!(
+            extensions(categories.resolve_kind("JPEG image")),
+            vec!["jpe", "jpeg", "jpg"]
+        );
+        assert_eq!(
+            extensions(categories.resolve_kind("MP3 audio file")),
+            vec!["mp3"]
+        );
+        assert_eq!(
+            extensions(categories.resolve_kind("PDF document")),
+            vec!["pdf"]
+        );
+        assert_eq!(
+            extensions(categories.resolve_kind("plain text")),
+            vec!["text", "txt"]
+        );
+        assert_eq!(extensions(categories.resolve_kind(".flac")), vec!["flac"]);
+        assert_eq!(categories.resolve_kind("no such kind"), None);
+        assert_eq!(categories.resolve_kind(""), None);
+    }
+}
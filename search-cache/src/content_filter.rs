@@ -0,0 +1,229 @@
+use crate::SearchOptions;
+use anyhow::{Result, anyhow, bail};
+use cardinal_syntax::FilterArgument;
+use rayon::{ThreadPoolBuilder, prelude::*};
+use regex::bytes::{Regex, RegexBuilder};
+use search_cancel::CancellationToken;
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    thread::available_parallelism,
+};
+
+const CHUNK_LEN: usize = 64 * 1024;
+
+/// Limits applied to `content:` searches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentSearchConfig {
+    /// Files larger than this many bytes are skipped.
+    pub max_file_size: u64,
+    /// How many files are read at the same time.
+    pub concurrency: usize,
+}
+
+impl Default for ContentSearchConfig {
+    fn default() -> Self {
+        Self {
+            max_file_size: 32 * 1024 * 1024,
+            concurrency: available_parallelism().map_or(4, |threads| threads.get().min(8)),
+        }
+    }
+}
+
+/// What a `content:` filter looks for: a literal, or a regex when written as
+/// `content:regex:<pattern>` or when regex mode is on.
+#[derive(Debug, Clone)]
+pub(crate) struct ContentMatcher {
+    regex: Regex,
+}
+
+impl ContentMatcher {
+    pub(crate) fn new(argument: &FilterArgument, options: SearchOptions) -> Result<Self> {
+        let (pattern, is_regex) = match argument.raw.strip_prefix("regex:") {
+            Some(pattern) => (pattern, true),
+            None => (argument.raw.as_str(), options.use_regex),
+        };
+        if pattern.is_empty() {
+            bail!("content: requires text to look for");
+        }
+        let pattern = if is_regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(options.case_insensitive)
+            .multi_line(true)
+            .build()
+            .map_err(|err| anyhow!("Invalid regex pattern: {err}"))?;
+        Ok(Self { regex })
+    }
+
+    /// Streams the file at `path` line by line. Binary files (any NUL byte) and
+    /// files above `max_file_size` never match. Returns `Ok(None)` once cancelled.
+    fn matches_file(
+        &self,
+        path: &Path,
+        max_file_size: u64,
+        token: CancellationToken,
+    ) -> io::Result<Option<bool>> {
+        let mut file = File::open(path)?;
+        if file.metadata()?.len() > max_file_size {
+            return Ok(Some(false));
+        }
+        let mut chunk = vec![0u8; CHUNK_LEN];
+        let mut pending = Vec::with_capacity(CHUNK_LEN);
+        loop {
+            if token.is_cancelled() {
+                return Ok(None);
+            }
+            let read = match file.read(&mut chunk) {
+                Ok(read) => read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            if read == 0 {
+                return Ok(Some(self.regex.is_match(&pending)));
+            }
+            let chunk = &chunk[..read];
+            if chunk.contains(&0) {
+                return Ok(Some(false));
+            }
+            pending.extend_from_slice(chunk);
+            // Only complete lines are searched; the rest waits for the next chunk.
+            if let Some(end) = pending.iter().rposition(|&byte| byte == b'\n') {
+                if self.regex.is_match(&pending[..end]) {
+                    return Ok(Some(true));
+                }
+                pending.drain(..=end);
+            }
+        }
+    }
+}
+
+/// Keeps the candidates whose file contents match, reading up to
+/// `config.concurrency` files at once. Order is preserved. Returns `Ok(None)`
+/// when cancelled.
+pub(crate) fn filter_by_content<T: Send>(
+    candidates: Vec<(T, PathBuf)>,
+    matcher: &ContentMatcher,
+    config: ContentSearchConfig,
+    token: CancellationToken,
+) -> Result<Option<Vec<T>>> {
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(config.concurrency.max(1))
+        .build()
+        .map_err(|err| anyhow!("Failed to start content search: {err}"))?;
+    let matched: Vec<T> = pool.install(|| {
+        candidates
+            .into_par_iter()
+            .filter_map(|(item, path)| {
+                if token.is_cancelled() {
+                    return None;
+                }
+                match matcher.matches_file(&path, config.max_file_size, token) {
+                    Ok(Some(true)) => Some(item),
+                    _ => None,
+                }
+            })
+            .collect()
+    });
+    if token.is_cancelled() {
+        return Ok(None);
+    }
+    Ok(Some(matched))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cardinal_syntax::{Expr, Term, parse_query};
+    use std::fs;
+    use tempdir::TempDir;
+
+    fn matcher(query: &str, options: SearchOptions) -> Result<ContentMatcher> {
+        let Expr::Term(Term::Filter(filter)) = parse_query(query).unwrap().expr else {
+            panic!("expected a filter");
+        };
+        ContentMatcher::new(filter.argument.as_ref().unwrap(), options)
+    }
+
+    fn file_matches(matcher: &ContentMatcher, path: &Path, max_file_size: u64) -> bool {
+        matcher
+            .matches_file(path, max_file_size, CancellationToken::noop())
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_literal_and_regex() {
+        let temp_dir = TempDir::new("test_content_literal_and_regex").unwrap();
+        let path = temp_dir.path().join("lib.rs");
+        fs::write(&path, "fn main() {\n    unsafe { exit(1.5) }\n}\n").unwrap();
+
+        let literal = matcher("content:unsafe", SearchOptions::default()).unwrap();
+        assert!(file_matches(&literal, &path, u64::MAX));
+        // Literals aren't regexes, so the dot must be there.
+        let dotted = matcher("content:1.5", SearchOptions::default()).unwrap();
+        assert!(file_matches(&dotted, &path, u64::MAX));
+        let dotted = matcher("content:1x5", SearchOptions::default()).unwrap();
+        assert!(!file_matches(&dotted, &path, u64::MAX));
+
+        let regex = matcher(r"content:regex:^\s+unsafe", SearchOptions::default()).unwrap();
+        assert!(file_matches(&regex, &path, u64::MAX));
+        let regex_mode = SearchOptions {
+            use_regex: true,
+            ..SearchOptions::default()
+        };
+        let regex = matcher(r"content:exit\(\d", regex_mode).unwrap();
+        assert!(file_matches(&regex, &path, u64::MAX));
+        assert!(matcher("content:regex:(", SearchOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_case_sensitivity() {
+        let temp_dir = TempDir::new("test_content_case_sensitivity").unwrap();
+        let path = temp_dir.path().join("notes.txt");
+        fs::write(&path, "TODO: ship it").unwrap();
+
+        let sensitive = matcher("content:todo", SearchOptions::default()).unwrap();
+        assert!(!file_matches(&sensitive, &path, u64::MAX));
+        let insensitive = SearchOptions {
+            case_insensitive: true,
+            ..SearchOptions::default()
+        };
+        let insensitive = matcher("content:todo", insensitive).unwrap();
+        assert!(file_matches(&insensitive, &path, u64::MAX));
+    }
+
+    #[test]
+    fn test_skips_binary_and_large_files() {
+        let temp_dir = TempDir::new("test_content_skips").unwrap();
+        let binary = temp_dir.path().join("blob.bin");
+        fs::write(&binary, b"needle\0needle").unwrap();
+        let text = temp_dir.path().join("text.txt");
+        fs::write(&text, b"needle").unwrap();
+
+        let needle = matcher("content:needle", SearchOptions::default()).unwrap();
+        assert!(!file_matches(&needle, &binary, u64::MAX));
+        assert!(file_matches(&needle, &text, u64::MAX));
+        assert!(!file_matches(&needle, &text, 3));
+    }
+
+    #[test]
+    fn test_matches_across_chunks() {
+        let temp_dir = TempDir::new("test_content_chunks").unwrap();
+        let path = temp_dir.path().join("big.txt");
+        // The needle straddles the first chunk boundary.
+        let mut contents = "a".repeat(CHUNK_LEN - 3);
+        contents.push_str("needle\n");
+        contents.push_str(&"b\n".repeat(CHUNK_LEN));
+        fs::write(&path, contents).unwrap();
+
+        let needle = matcher("content:needle", SearchOptions::default()).unwrap();
+        assert!(file_matches(&needle, &path, u64::MAX));
+        let missing = matcher("content:haystack", SearchOptions::default()).unwrap();
+        assert!(!file_matches(&missing, &path, u64::MAX));
+    }
+}
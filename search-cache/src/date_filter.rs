@@ -0,0 +1,370 @@
+use anyhow::{Result, anyhow, bail};
+use cardinal_syntax::{ArgumentKind, ComparisonOp, FilterArgument, RangeValue};
+use chrono::{
+    DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+};
+use std::ops::{Bound, RangeBounds};
+
+/// Unix timestamp range a `dm:`/`dc:`/`da:` filter accepts.
+///
+/// Every date literal names a span of time: `2024` is the whole year, `2024-05`
+/// the whole month, `2024-05-17` the whole day and `2024-05-17T10:30` a single
+/// minute. A bare literal matches anything inside its span, comparisons are
+/// made against the span's edges (`>2024` starts at 2025-01-01), and ranges
+/// cover both endpoints entirely. Relative keywords are resolved in the local
+/// timezone; weeks start on Monday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct DatePredicate {
+    start: Bound<i64>,
+    end: Bound<i64>,
+    negated: bool,
+}
+
+impl DatePredicate {
+    pub(crate) fn parse(argument: &FilterArgument) -> Result<Self> {
+        Self::parse_at(argument, Local::now())
+    }
+
+    fn parse_at(argument: &FilterArgument, now: DateTime<Local>) -> Result<Self> {
+        match &argument.kind {
+            ArgumentKind::Comparison(comparison) => {
+                let span = parse_span(&comparison.value, now)?;
+                Ok(match comparison.op {
+                    ComparisonOp::Lt => Self::new(Bound::Unbounded, Bound::Excluded(span.start)),
+                    ComparisonOp::Lte => Self::new(Bound::Unbounded, Bound::Excluded(span.end)),
+                    ComparisonOp::Gt => Self::new(Bound::Included(span.end), Bound::Unbounded),
+                    ComparisonOp::Gte => Self::new(Bound::Included(span.start), Bound::Unbounded),
+                    ComparisonOp::Eq => span.into(),
+                    ComparisonOp::Ne => Self {
+                        negated: true,
+                        ..span.into()
+                    },
+                })
+            }
+            ArgumentKind::Range(RangeValue { start, end, .. }) => {
+                let start = match start {
+                    Some(start) => Bound::Included(parse_span(start, now)?.start),
+                    None => Bound::Unbounded,
+                };
+                let end = match end {
+                    Some(end) => Bound::Excluded(parse_span(end, now)?.end),
+                    None => Bound::Unbounded,
+                };
+                Ok(Self::new(start, end))
+            }
+            ArgumentKind::Bare | ArgumentKind::Phrase => Ok(parse_span(&argument.raw, now)?.into()),
+            ArgumentKind::List(_) => bail!("Date filters don't accept a list: {:?}", argument.raw),
+        }
+    }
+
+    pub(crate) fn matches(&self, timestamp: i64) -> bool {
+        (self.start, self.end).contains(&timestamp) != self.negated
+    }
+
+    fn new(start: Bound<i64>, end: Bound<i64>) -> Self {
+        Self {
+            start,
+            end,
+            negated: false,
+        }
+    }
+}
+
+/// Half-open `[start, end)` span of unix timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    start: i64,
+    end: i64,
+}
+
+impl From<Span> for DatePredicate {
+    fn from(span: Span) -> Self {
+        Self::new(Bound::Included(span.start), Bound::Excluded(span.end))
+    }
+}
+
+fn parse_span(raw: &str, now: DateTime<Local>) -> Result<Span> {
+    let raw = raw.trim();
+    keyword_span(&raw.to_ascii_lowercase(), now)
+        .or_else(|| literal_span(raw))
+        .ok_or_else(|| anyhow!("Invalid date: {raw:?}"))
+}
+
+fn keyword_span(keyword: &str, now: DateTime<Local>) -> Option<Span> {
+    let today = now.date_naive();
+    let week_start = today - Duration::days(today.weekday().num_days_from_monday().into());
+    let month_start = today.with_day(1)?;
+    let year_start = NaiveDate::from_ymd_opt(today.year(), 1, 1)?;
+    let now_ts = now.timestamp();
+    let span = match keyword {
+        "today" => days_span(today, 1),
+        "yesterday" => days_span(today - Duration::days(1), 1),
+        "thisweek" => days_span(week_start, 7),
+        "lastweek" => days_span(week_start - Duration::days(7), 7),
+        "thismonth" => months_span(month_start, 1)?,
+        "lastmonth" => months_span(month_start.checked_sub_months(Months::new(1))?, 1)?,
+        "thisyear" => months_span(year_start, 12)?,
+        "lastyear" => months_span(year_start.checked_sub_months(Months::new(12))?, 12)?,
+        _ => {
+            let rest = keyword
+                .strip_prefix("past")
+                .or_else(|| keyword.strip_prefix("last"))?;
+            // `pastweek` is the rolling window of one week up to now.
+            let split = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let (count, unit) = rest.split_at(split);
+            let count: u32 = if count.is_empty() {
+                if keyword.starts_with("last") {
+                    return None;
+                }
+                1
+            } else {
+                count.parse().ok()?
+            };
+            let start = rolling_start(now, count, unit)?;
+            Span {
+                start: start.timestamp(),
+                end: now_ts + 1,
+            }
+        }
+    };
+    Some(span)
+}
+
+fn rolling_start(now: DateTime<Local>, count: u32, unit: &str) -> Option<DateTime<Local>> {
+    let count_i64 = i64::from(count);
+    match unit.trim_end_matches('s') {
+        "min" | "minute" => now.checked_sub_signed(Duration::minutes(count_i64)),
+        "hour" => now.checked_sub_signed(Duration::hours(count_i64)),
+        "day" => now.checked_sub_signed(Duration::days(count_i64)),
+        "week" => now.checked_sub_signed(Duration::weeks(count_i64)),
+        "month" => now.checked_sub_months(Months::new(count)),
+        "year" => now.checked_sub_months(Months::new(count.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
+/// Parses `YYYY`, `YYYY-MM`, `YYYY-MM-DD` and date-time literals such as
+/// `YYYY-MM-DDTHH:MM[:SS]`. `/` and `.` are accepted as date separators too.
+/// A slash date ending in a four-digit year is read as US-style `M/D/YYYY`, so
+/// a day-first `17/05/2024` is rejected as having no 17th month.
+fn literal_span(raw: &str) -> Option<Span> {
+    let (date, time) = match raw.split_once(['T', 't', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (raw, None),
+    };
+    let mut parts = date
+        .split(['-', '/', '.'])
+        .map(|part| {
+            (!part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+                .then(|| part.parse::<u32>().ok())
+                .flatten()
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let us_order =
+        date.contains('/') && date.rsplit('/').next().is_some_and(|year| year.len() == 4);
+    if parts.len() == 3 && us_order {
+        parts.rotate_right(1);
+    }
+    let year = i32::try_from(*parts.first()?).ok()?;
+    match (parts.as_slice(), time) {
+        ([_], None) => months_span(NaiveDate::from_ymd_opt(year, 1, 1)?, 12),
+        ([_, month], None) => months_span(NaiveDate::from_ymd_opt(year, *month, 1)?, 1),
+        ([_, month, day], None) => Some(days_span(NaiveDate::from_ymd_opt(year, *month, *day)?, 1)),
+        ([_, month, day], Some(time)) => {
+            let date = NaiveDate::from_ymd_opt(year, *month, *day)?;
+            let (time, width) = match NaiveTime::parse_from_str(time, "%H:%M:%S") {
+                Ok(time) => (time, Duration::seconds(1)),
+                Err(_) => (
+                    NaiveTime::parse_from_str(time, "%H:%M").ok()?,
+                    Duration::minutes(1),
+                ),
+            };
+            let start = date.and_time(time);
+            Some(Span {
+                start: local_timestamp(start),
+                end: local_timestamp(start + width),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn days_span(start: NaiveDate, days: i64) -> Span {
+    Span {
+        start: local_timestamp(start.and_time(NaiveTime::MIN)),
+        end: local_timestamp((start + Duration::days(days)).and_time(NaiveTime::MIN)),
+    }
+}
+
+fn months_span(start: NaiveDate, months: u32) -> Option<Span> {
+    let end = start.checked_add_months(Months::new(months))?;
+    Some(Span {
+        start: local_timestamp(start.and_time(NaiveTime::MIN)),
+        end: local_timestamp(end.and_time(NaiveTime::MIN)),
+    })
+}
+
+/// Converts a local wall-clock time to a unix timestamp. Times skipped by a DST
+/// transition resolve to the first valid instant after them.
+fn local_timestamp(naive: NaiveDateTime) -> i64 {
+    let mut candidate = naive;
+    for _ in 0..4 {
+        if let Some(time) = Local.from_local_datetime(&candidate).earliest() {
+            return time.timestamp();
+        }
+        candidate += Duration::minutes(30);
+    }
+    naive.and_utc().timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cardinal_syntax::{Expr, Term, parse_query};
+
+    fn now() -> DateTime<Local> {
+        // Wednesday
+        Local.with_ymd_and_hms(2024, 5, 15, 12, 0, 0).unwrap()
+    }
+
+    fn ts(year: i32, month: u32, day: u32, hour: u32, min: u32, sec: u32) -> i64 {
+        Local
+            .with_ymd_and_hms(year, month, day, hour, min, sec)
+            .unwrap()
+            .timestamp()
+    }
+
+    fn predicate(query: &str) -> DatePredicate {
+        let Expr::Term(Term::Filter(filter)) = parse_query(query).unwrap().expr else {
+            panic!("expected a filter");
+        };
+        DatePredicate::parse_at(filter.argument.as_ref().unwrap(), now()).unwrap()
+    }
+
+    #[test]
+    fn test_absolute_dates() {
+        let day = predicate("dm:2024-05-01");
+        assert!(!day.matches(ts(2024, 4, 30, 23, 59, 59)));
+        assert!(day.matches(ts(2024, 5, 1, 0, 0, 0)));
+        assert!(day.matches(ts(2024, 5, 1, 23, 59, 59)));
+        assert!(!day.matches(ts(2024, 5, 2, 0, 0, 0)));
+
+        let slash = predicate("dm:2024/5/1");
+        assert!(slash.matches(ts(2024, 5, 1, 8, 0, 0)));
+
+        let us = predicate("dm:5/17/2024");
+        assert!(us.matches(ts(2024, 5, 17, 8, 0, 0)));
+        assert!(!us.matches(ts(2024, 5, 18, 0, 0, 0)));
+        let us_minute = predicate("dm:\"05/17/2024 10:30\"");
+        assert!(us_minute.matches(ts(2024, 5, 17, 10, 30, 0)));
+
+        let month = predicate("dm:2024-02");
+        assert!(month.matches(ts(2024, 2, 29, 12, 0, 0)));
+        assert!(!month.matches(ts(2024, 3, 1, 0, 0, 0)));
+
+        let year = predicate("dm:2023");
+        assert!(year.matches(ts(2023, 12, 31, 23, 0, 0)));
+        assert!(!year.matches(ts(2024, 1, 1, 0, 0, 0)));
+
+        let minute = predicate("dm:2024-05-01T10:30");
+        assert!(minute.matches(ts(2024, 5, 1, 10, 30, 59)));
+        assert!(!minute.matches(ts(2024, 5, 1, 10, 31, 0)));
+
+        let second = predicate("dm:\"2024-05-01 10:30:15\"");
+        assert!(second.matches(ts(2024, 5, 1, 10, 30, 15)));
+        assert!(!second.matches(ts(2024, 5, 1, 10, 30, 16)));
+    }
+
+    #[test]
+    fn test_comparisons_and_ranges() {
+        let after = predicate("dm:>2024-05-01");
+        assert!(!after.matches(ts(2024, 5, 1, 23, 0, 0)));
+        assert!(after.matches(ts(2024, 5, 2, 0, 0, 0)));
+
+        let since = predicate("dm:>=2024-05-01");
+        assert!(since.matches(ts(2024, 5, 1, 0, 0, 0)));
+
+        let before = predicate("dm:<2024-05-01");
+        assert!(before.matches(ts(2024, 4, 30, 23, 0, 0)));
+        assert!(!before.matches(ts(2024, 5, 1, 0, 0, 0)));
+
+        let until = predicate("dm:<=2024-05-01");
+        assert!(until.matches(ts(2024, 5, 1, 23, 0, 0)));
+
+        let not = predicate("dm:!=2024-05-01");
+        assert!(!not.matches(ts(2024, 5, 1, 1, 0, 0)));
+        assert!(not.matches(ts(2024, 5, 2, 1, 0, 0)));
+
+        let hyphen = predicate("dc:2024/01/01-2024/01/31");
+        assert!(hyphen.matches(ts(2024, 1, 31, 22, 0, 0)));
+        assert!(!hyphen.matches(ts(2024, 2, 1, 0, 0, 0)));
+
+        let us_range = predicate("dc:1/15/2024-1/31/2024");
+        assert!(us_range.matches(ts(2024, 1, 20, 0, 0, 0)));
+        assert!(!us_range.matches(ts(2024, 1, 14, 0, 0, 0)));
+
+        let dots = predicate("dc:2024-01-01..2024-01-31");
+        assert!(dots.matches(ts(2024, 1, 1, 0, 0, 0)));
+        assert!(!dots.matches(ts(2023, 12, 31, 0, 0, 0)));
+
+        let open = predicate("dc:2024-01-01..");
+        assert!(open.matches(ts(2030, 1, 1, 0, 0, 0)));
+        assert!(!open.matches(ts(2023, 12, 31, 0, 0, 0)));
+    }
+
+    #[test]
+    fn test_relative_keywords() {
+        let today = predicate("dm:today");
+        assert!(today.matches(ts(2024, 5, 15, 0, 0, 0)));
+        assert!(!today.matches(ts(2024, 5, 14, 23, 59, 59)));
+
+        let yesterday = predicate("dm:yesterday");
+        assert!(yesterday.matches(ts(2024, 5, 14, 1, 0, 0)));
+        assert!(!yesterday.matches(ts(2024, 5, 15, 1, 0, 0)));
+
+        let this_week = predicate("dm:thisweek");
+        assert!(this_week.matches(ts(2024, 5, 13, 0, 0, 0)));
+        assert!(!this_week.matches(ts(2024, 5, 12, 23, 0, 0)));
+
+        let last_week = predicate("dm:lastweek");
+        assert!(last_week.matches(ts(2024, 5, 6, 0, 0, 0)));
+        assert!(!last_week.matches(ts(2024, 5, 13, 0, 0, 0)));
+
+        let last_month = predicate("dm:lastmonth");
+        assert!(last_month.matches(ts(2024, 4, 30, 0, 0, 0)));
+        assert!(!last_month.matches(ts(2024, 5, 1, 0, 0, 0)));
+
+        let last_year = predicate("dm:lastyear");
+        assert!(last_year.matches(ts(2023, 6, 1, 0, 0, 0)));
+        assert!(!last_year.matches(ts(2024, 1, 1, 0, 0, 0)));
+
+        let past_month = predicate("dm:pastmonth");
+        assert!(past_month.matches(ts(2024, 4, 16, 0, 0, 0)));
+        assert!(!past_month.matches(ts(2024, 4, 14, 0, 0, 0)));
+
+        let last_3_days = predicate("dm:last3days");
+        assert!(last_3_days.matches(ts(2024, 5, 12, 13, 0, 0)));
+        assert!(!last_3_days.matches(ts(2024, 5, 12, 11, 0, 0)));
+
+        let past_2_hours = predicate("dm:past2hours");
+        assert!(past_2_hours.matches(ts(2024, 5, 15, 10, 30, 0)));
+        assert!(!past_2_hours.matches(ts(2024, 5, 15, 9, 30, 0)));
+    }
+
+    #[test]
+    fn test_invalid_dates() {
+        for raw in [
+            "someday",
+            "2024-13-01",
+            "2024-02-30",
+            // Slash dates ending in a year are month first.
+            "17/05/2024",
+            "lastdays",
+            "past3eons",
+        ] {
+            assert!(parse_span(raw, now()).is_err(), "{raw} should be rejected");
+        }
+    }
+}
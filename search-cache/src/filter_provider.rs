@@ -0,0 +1,60 @@
+use crate::{FileNodes, SlabIndex, SlabNodeMetadata};
+use anyhow::Result;
+use cardinal_syntax::Filter;
+use search_cancel::CancellationToken;
+use std::path::{Path, PathBuf};
+
+/// A filter implemented outside of Cardinal, such as `owner:team-x` backed by a
+/// CODEOWNERS parser. Providers are registered by name with
+/// [`SearchCache::register_filter_provider`](crate::SearchCache::register_filter_provider)
+/// and receive every `name:` filter that isn't built in or a macro.
+pub trait FilterProvider: Send + Sync {
+    /// Keeps the `candidates` that match `filter`, in their original order.
+    /// `candidates` is either every indexed node or what the rest of an AND
+    /// query left over. Return `Ok(None)` once `token` is cancelled.
+    fn filter(
+        &self,
+        filter: &Filter,
+        candidates: Vec<SlabIndex>,
+        nodes: NodeView<'_>,
+        token: CancellationToken,
+    ) -> Result<Option<Vec<SlabIndex>>>;
+}
+
+/// Read-only access to the indexed nodes for a [`FilterProvider`].
+#[derive(Clone, Copy)]
+pub struct NodeView<'a> {
+    file_nodes: &'a FileNodes,
+}
+
+impl<'a> NodeView<'a> {
+    pub(crate) fn new(file_nodes: &'a FileNodes) -> Self {
+        Self { file_nodes }
+    }
+
+    /// The watched root all paths start with.
+    pub fn root_path(&self) -> &'a Path {
+        self.file_nodes.path()
+    }
+
+    /// File name of the node.
+    pub fn name(&self, index: SlabIndex) -> Option<&'a str> {
+        self.file_nodes
+            .get(index)
+            .map(|node| node.name_and_parent.as_str())
+    }
+
+    pub fn parent(&self, index: SlabIndex) -> Option<SlabIndex> {
+        self.file_nodes.get(index)?.name_and_parent.parent()
+    }
+
+    pub fn path(&self, index: SlabIndex) -> Option<PathBuf> {
+        self.file_nodes.node_path(index)
+    }
+
+    /// Metadata of the node if it has been fetched already. Providers that need
+    /// it for every candidate should stat [`Self::path`] themselves.
+    pub fn metadata(&self, index: SlabIndex) -> Option<SlabNodeMetadata<'a>> {
+        self.file_nodes.get(index)?.metadata.as_ref()
+    }
+}
@@ -0,0 +1,174 @@
+use crate::SearchOptions;
+use caseless::default_case_fold_str;
+use std::{borrow::Cow, ops::Range};
+use unicode_normalization::{
+    IsNormalized, UnicodeNormalization, char::is_combining_mark, is_nfc_quick,
+};
+
+/// The canonical form names and queries are compared in.
+///
+/// Text is always brought to NFC, since macOS stores names decomposed while
+/// typed queries are usually composed, so `café` has to match either way. On
+/// top of that, case folding uses the full Unicode mapping (`Straße` matches
+/// `STRASSE`) and diacritics can be dropped (`resume` matches `Résumé`). Only
+/// the compared copies are folded; names keep their bytes for display.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Folding {
+    pub(crate) case: bool,
+    pub(crate) diacritics: bool,
+}
+
+impl Folding {
+    /// Case folding alone, for extensions and tags.
+    pub(crate) const CASE: Self = Self {
+        case: true,
+        diacritics: false,
+    };
+
+    pub(crate) fn new(options: SearchOptions) -> Self {
+        Self {
+            case: options.case_insensitive,
+            diacritics: options.ignore_diacritics,
+        }
+    }
+
+    /// Whether [`Self::apply`] only normalizes to NFC.
+    pub(crate) fn is_exact(self) -> bool {
+        !self.case && !self.diacritics
+    }
+
+    pub(crate) fn apply(self, text: &str) -> Cow<'_, str> {
+        if text.is_ascii() {
+            return if self.case && text.bytes().any(|byte| byte.is_ascii_uppercase()) {
+                Cow::Owned(text.to_ascii_lowercase())
+            } else {
+                Cow::Borrowed(text)
+            };
+        }
+        if self.is_exact() && is_nfc_quick(text.chars()) == IsNormalized::Yes {
+            return Cow::Borrowed(text);
+        }
+        let mut folded: String = text.nfd().collect();
+        if self.case {
+            folded = default_case_fold_str(&folded);
+        }
+        if self.diacritics {
+            // Folding may have decomposed more, so marks are dropped afterwards.
+            folded = folded
+                .nfd()
+                .filter(|&char| !is_combining_mark(char))
+                .collect();
+        }
+        Cow::Owned(folded.nfc().collect())
+    }
+
+    /// [`Self::apply`], remembering which part of `text` each byte of the
+    /// result comes from.
+    ///
+    /// Every character is folded along with the marks that follow it, which
+    /// gives the same text as folding it whole for everything but the rare
+    /// scripts composing across characters, like Hangul jamo.
+    pub(crate) fn apply_mapped(self, text: &str) -> FoldedText {
+        let mut folded = FoldedText {
+            text: String::with_capacity(text.len()),
+            origins: Vec::with_capacity(text.len()),
+        };
+        let mut chars = text.char_indices().peekable();
+        while let Some((start, _)) = chars.next() {
+            let mut end = text.len();
+            while let Some(&(offset, char)) = chars.peek() {
+                if !is_combining_mark(char) {
+                    end = offset;
+                    break;
+                }
+                chars.next();
+            }
+            let part = self.apply(&text[start..end]);
+            folded.text.push_str(&part);
+            folded
+                .origins
+                .extend(std::iter::repeat_n(start..end, part.len()));
+        }
+        folded
+    }
+}
+
+/// Text after [`Folding::apply_mapped`].
+pub(crate) struct FoldedText {
+    pub(crate) text: String,
+    /// For every byte of `text`, the range of the original it was folded from.
+    origins: Vec<Range<usize>>,
+}
+
+impl FoldedText {
+    /// The part of the original text `range` of the folded one comes from.
+    pub(crate) fn original(&self, range: Range<usize>) -> Range<usize> {
+        if range.is_empty() {
+            let offset = self.origins.get(range.start).map_or_else(
+                || self.origins.last().map_or(0, |last| last.end),
+                |origin| origin.start,
+            );
+            return offset..offset;
+        }
+        self.origins[range.start].start..self.origins[range.end - 1].end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXACT: Folding = Folding {
+        case: false,
+        diacritics: false,
+    };
+    const DIACRITICS: Folding = Folding {
+        case: true,
+        diacritics: true,
+    };
+
+    #[test]
+    fn test_nfc() {
+        let decomposed = "cafe\u{301}";
+        assert_eq!(EXACT.apply(decomposed), "café");
+        assert_eq!(EXACT.apply("Café"), "Café");
+        assert!(matches!(EXACT.apply("Café"), Cow::Borrowed(_)));
+        assert!(matches!(EXACT.apply("README"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_case_folding() {
+        assert_eq!(Folding::CASE.apply("README.md"), "readme.md");
+        assert!(matches!(Folding::CASE.apply("readme.md"), Cow::Borrowed(_)));
+        assert_eq!(Folding::CASE.apply("Straße"), "strasse");
+        assert_eq!(
+            Folding::CASE.apply("ΣΊΣΥΦΟΣ"),
+            Folding::CASE.apply("σίσυφος")
+        );
+        assert_eq!(Folding::CASE.apply("CAFE\u{301}"), "café");
+    }
+
+    #[test]
+    fn test_diacritics() {
+        assert_eq!(DIACRITICS.apply("Résumé.pdf"), "resume.pdf");
+        assert_eq!(DIACRITICS.apply("re\u{301}sume\u{301}"), "resume");
+        assert_eq!(DIACRITICS.apply("Ångström"), "angstrom");
+        // Letters that aren't a base plus a mark stay.
+        assert_eq!(DIACRITICS.apply("Øresund"), "øresund");
+    }
+
+    #[test]
+    fn test_apply_mapped() {
+        let name = "Re\u{301}sume\u{301}.PDF";
+        let folded = DIACRITICS.apply_mapped(name);
+        assert_eq!(folded.text, "resume.pdf");
+        assert_eq!(folded.original(0..2), 0..4);
+        assert_eq!(folded.original(6..10), 10..14);
+        assert_eq!(folded.original(10..10), 14..14);
+        let folded = Folding::CASE.apply_mapped("Straße");
+        assert_eq!(folded.text, "strasse");
+        // Both `s` of `ß` lead back to it.
+        assert_eq!(folded.original(4..5), 4..6);
+        assert_eq!(&"Straße"[folded.original(3..6)], "aß");
+    }
+}
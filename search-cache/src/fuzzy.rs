@@ -0,0 +1,291 @@
+use crate::{
+    ResultDetails, SearchCache, SearchOptions, SegmentKind, SlabIndex,
+    fold::Folding,
+    relevance::name_terms,
+    segment::{segment_kind, segment_value},
+};
+use cardinal_syntax::Expr;
+use query_segmentation::query_segmentation;
+use std::ops::Range;
+
+/// A query segment matched with a few typos allowed, for
+/// [`SearchOptions::fuzzy`].
+///
+/// The distance is the optimal string alignment variant of Damerau–Levenshtein:
+/// insertions, deletions, substitutions and swaps of two neighbouring
+/// characters each cost one edit, so `recieve` finds `receive.rs`. The segment
+/// kind still decides which part of the name has to line up: a substring
+/// segment may match anywhere in the name, a prefix only its start, and so on.
+#[derive(Debug, Clone)]
+pub(crate) struct FuzzyPattern {
+    kind: SegmentKind,
+    /// Folded like the names it's compared to.
+    needle: Vec<char>,
+    folding: Folding,
+    max_distance: u32,
+}
+
+impl FuzzyPattern {
+    pub(crate) fn new(kind: SegmentKind, needle: &str, folding: Folding) -> Self {
+        let needle: Vec<char> = folding.apply(needle).chars().collect();
+        Self {
+            kind,
+            max_distance: max_distance(needle.len()),
+            needle,
+            folding,
+        }
+    }
+
+    pub(crate) fn matches(&self, candidate: &str) -> bool {
+        self.distance(candidate).is_some()
+    }
+
+    /// Edits needed to match `candidate`, `None` past the allowed distance.
+    pub(crate) fn distance(&self, candidate: &str) -> Option<u32> {
+        self.align(self.folding.apply(candidate).chars())
+            .map(|(distance, _)| distance)
+    }
+
+    /// The byte range of `candidate` the segment lined up with.
+    pub(crate) fn span(&self, candidate: &str) -> Option<Range<usize>> {
+        let folded = self.folding.apply_mapped(candidate);
+        let (_, columns) = self.align(folded.text.chars())?;
+        let mut offsets = folded
+            .text
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain(std::iter::once(folded.text.len()));
+        let start = offsets.nth(columns.start)?;
+        let end = if columns.is_empty() {
+            start
+        } else {
+            offsets.nth(columns.len() - 1)?
+        };
+        Some(folded.original(start..end))
+    }
+
+    /// Runs the edit distance one candidate character at a time, keeping the
+    /// last three columns of the table: `current[i]` is the cost of matching
+    /// the first `i` needle characters up to the current candidate character,
+    /// and `starts[i]` the column that alignment started at. Returns the
+    /// distance and the columns of the best alignment.
+    fn align(&self, candidate: impl Iterator<Item = char>) -> Option<(u32, Range<usize>)> {
+        let needle = &self.needle;
+        let len = needle.len();
+        // Substrings and suffixes may start anywhere, so skipping the head of
+        // the candidate is free.
+        let free_start = matches!(self.kind, SegmentKind::Substr | SegmentKind::Suffix);
+        // Substrings and prefixes may stop anywhere.
+        let free_end = matches!(self.kind, SegmentKind::Substr | SegmentKind::Prefix);
+
+        let mut before: Vec<(u32, usize)> = Vec::new();
+        let mut previous: Vec<(u32, usize)> = (0..=len as u32).map(|cost| (cost, 0)).collect();
+        let mut current = vec![(0, 0); len + 1];
+        let mut best = (previous[len].0, 0..0);
+        let mut last_char = None;
+        for (column, char) in candidate.enumerate() {
+            current[0] = if free_start {
+                (0, column + 1)
+            } else {
+                (column as u32 + 1, 0)
+            };
+            for i in 1..=len {
+                let substitution = u32::from(needle[i - 1] != char);
+                let step = |(cost, start): (u32, usize), extra| (cost + extra, start);
+                let mut cell = [
+                    step(previous[i - 1], substitution),
+                    step(previous[i], 1),
+                    step(current[i - 1], 1),
+                ]
+                .into_iter()
+                .min_by_key(|&(cost, _)| cost)
+                .unwrap_or_default();
+                let swapped = i > 1 && last_char == Some(needle[i - 1]) && needle[i - 2] == char;
+                if swapped && before[i - 2].0 + 1 < cell.0 {
+                    cell = step(before[i - 2], 1);
+                }
+                current[i] = cell;
+            }
+            let (cost, start) = current[len];
+            if !free_end || cost < best.0 {
+                best = (cost, start..column + 1);
+            }
+            if free_end && best.0 == 0 {
+                break;
+            }
+            // Without a free start no later column can get back under the
+            // limit, and a free end already kept its best.
+            if !free_start
+                && current
+                    .iter()
+                    .map(|&(cost, _)| cost)
+                    .min()
+                    .is_some_and(|min| min > self.max_distance)
+            {
+                break;
+            }
+            last_char = Some(char);
+            std::mem::swap(&mut before, &mut previous);
+            std::mem::swap(&mut previous, &mut current);
+            current.resize(len + 1, (0, 0));
+        }
+        (best.0 <= self.max_distance).then_some(best)
+    }
+}
+
+/// Longer segments allow more typos; two characters or fewer must match
+/// exactly, or nearly every name would.
+fn max_distance(len: usize) -> u32 {
+    match len {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+/// Measures how far each result of a fuzzy search is from the query, for
+/// [`SearchResultNode::distance`](crate::SearchResultNode::distance).
+#[derive(Debug, Clone)]
+pub(crate) struct FuzzyScorer {
+    /// The name segment of every word in the query.
+    patterns: Vec<FuzzyPattern>,
+}
+
+impl FuzzyScorer {
+    pub(crate) fn new(expr: &Expr, options: SearchOptions) -> Self {
+        let patterns = name_terms(expr)
+            .into_iter()
+            .filter_map(|text| {
+                query_segmentation(text).last().map(|segment| {
+                    FuzzyPattern::new(
+                        segment_kind(segment),
+                        segment_value(segment),
+                        Folding::new(options),
+                    )
+                })
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    /// Edits summed over the words the name matches, `None` if it matches none.
+    fn distance(&self, name: &str) -> Option<u32> {
+        self.patterns
+            .iter()
+            .filter_map(|pattern| pattern.distance(name))
+            .reduce(|total, distance| total + distance)
+    }
+}
+
+impl SearchCache {
+    /// The node's distance from the query of a fuzzy search.
+    pub(crate) fn fuzzy_distance(&self, details: &ResultDetails, index: SlabIndex) -> Option<u32> {
+        let scorer = details.fuzzy.as_ref()?;
+        scorer.distance(self.file_nodes.get(index)?.name_and_parent.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance(kind: SegmentKind, needle: &str, candidate: &str) -> Option<u32> {
+        FuzzyPattern::new(kind, needle, Folding::CASE).distance(candidate)
+    }
+
+    #[test]
+    fn test_thresholds_scale_with_length() {
+        assert_eq!(distance(SegmentKind::Exact, "ab", "ab"), Some(0));
+        assert_eq!(distance(SegmentKind::Exact, "ab", "ac"), None);
+        assert_eq!(distance(SegmentKind::Exact, "main", "maln"), Some(1));
+        assert_eq!(distance(SegmentKind::Exact, "main", "mxyn"), None);
+        assert_eq!(distance(SegmentKind::Exact, "receive", "rcvie"), None);
+        assert_eq!(distance(SegmentKind::Exact, "receive", "recve"), Some(2));
+    }
+
+    #[test]
+    fn test_edits() {
+        // Swap, deletion, insertion and substitution each cost one.
+        assert_eq!(distance(SegmentKind::Exact, "recieve", "receive"), Some(1));
+        assert_eq!(distance(SegmentKind::Exact, "recive", "receive"), Some(1));
+        assert_eq!(distance(SegmentKind::Exact, "receeive", "receive"), Some(1));
+        assert_eq!(distance(SegmentKind::Exact, "reseive", "receive"), Some(1));
+        assert_eq!(distance(SegmentKind::Exact, "Receive", "rECEIVE"), Some(0));
+        assert_eq!(
+            FuzzyPattern::new(SegmentKind::Exact, "Receive", Folding::default())
+                .distance("receive"),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_segment_kinds() {
+        assert_eq!(
+            distance(SegmentKind::Substr, "recieve", "on_receive.rs"),
+            Some(1)
+        );
+        assert_eq!(
+            distance(SegmentKind::Substr, "recieve", "receiver"),
+            Some(1)
+        );
+        assert_eq!(distance(SegmentKind::Substr, "receive", "receive"), Some(0));
+        assert_eq!(
+            distance(SegmentKind::Prefix, "recieve", "receive.rs"),
+            Some(1)
+        );
+        assert_eq!(
+            distance(SegmentKind::Prefix, "recieve", "on_receive.rs"),
+            None
+        );
+        assert_eq!(
+            distance(SegmentKind::Suffix, "recieve", "on_receive"),
+            Some(1)
+        );
+        assert_eq!(distance(SegmentKind::Suffix, "recieve", "receive.rs"), None);
+        assert_eq!(distance(SegmentKind::Exact, "recieve", "receive.rs"), None);
+    }
+
+    #[test]
+    fn test_unicode_names() {
+        assert_eq!(distance(SegmentKind::Exact, "résumé", "resume"), Some(2));
+        assert_eq!(
+            distance(SegmentKind::Substr, "café", "my_cafe.txt"),
+            Some(1)
+        );
+        // Names are compared folded: composed and with `ß` as `ss`.
+        assert_eq!(distance(SegmentKind::Exact, "Straße", "strasse"), Some(0));
+        assert_eq!(distance(SegmentKind::Exact, "café", "cafe\u{301}"), Some(0));
+        let no_accents = Folding {
+            case: true,
+            diacritics: true,
+        };
+        assert_eq!(
+            FuzzyPattern::new(SegmentKind::Exact, "résumé", no_accents).distance("Resume"),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        let span = |kind, needle: &str, candidate: &str| {
+            FuzzyPattern::new(kind, needle, Folding::CASE).span(candidate)
+        };
+        assert_eq!(
+            span(SegmentKind::Substr, "recieve", "on_Receive.rs"),
+            Some(3..10)
+        );
+        assert_eq!(
+            span(SegmentKind::Substr, "receive", "my_receiver"),
+            Some(3..10)
+        );
+        assert_eq!(
+            span(SegmentKind::Prefix, "recieve", "receive.rs"),
+            Some(0..7)
+        );
+        assert_eq!(
+            span(SegmentKind::Exact, "resume", "re\u{301}sume"),
+            Some(0..8)
+        );
+        assert_eq!(span(SegmentKind::Exact, "main", "mxyn"), None);
+    }
+}
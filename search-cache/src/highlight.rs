@@ -0,0 +1,169 @@
+use crate::{
+    ResultDetails, SearchCache, SearchOptions, SegmentMatcher, SlabIndex, TermOverrides,
+    build_segment_matchers,
+    query::{is_term_modifier, modified_term},
+    regex_matcher, whole_word_pattern,
+};
+use cardinal_syntax::{Expr, FilterKind, Term};
+use query_segmentation::query_segmentation;
+use serde::Serialize;
+
+/// A part of a search result's path that matched the query, for
+/// [`SearchResultNode::highlights`](crate::SearchResultNode::highlights).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct MatchRange {
+    /// The path component it's in: 0 for the node's own name, 1 for its
+    /// parent's name and so on, as multi-segment queries like `src/main`
+    /// match the folders above the node too.
+    pub level: usize,
+    /// Byte offsets in that component's name.
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Finds what each result of a search with [`SearchOptions::highlight`]
+/// matched, running the same [`SegmentMatcher`]s again on its name and the
+/// names of its ancestors.
+#[derive(Debug, Clone)]
+pub(crate) struct Highlighter {
+    /// The matchers of every term outside of negations, one per segment from
+    /// the topmost folder down to the name.
+    terms: Vec<Vec<SegmentMatcher>>,
+    /// How many names up from the node the longest term reaches.
+    levels: usize,
+}
+
+impl Highlighter {
+    pub(crate) fn new(expr: &Expr, options: SearchOptions) -> Self {
+        let mut terms = Vec::new();
+        collect_terms(expr, options, TermOverrides::default(), &mut terms);
+        let levels = terms.iter().map(Vec::len).max().unwrap_or_default();
+        Self { terms, levels }
+    }
+
+    /// Merged ranges, ordered by level and offset. Terms that don't match
+    /// all their segments, like the other side of an `|`, add nothing.
+    fn ranges(&self, names: &[&str]) -> Vec<MatchRange> {
+        let mut ranges = Vec::new();
+        for matchers in &self.terms {
+            if matchers.len() > names.len() {
+                continue;
+            }
+            let mut found = Vec::new();
+            let matched =
+                matchers
+                    .iter()
+                    .rev()
+                    .zip(names)
+                    .enumerate()
+                    .all(|(level, (matcher, name))| match matcher.ranges(name) {
+                        Some(matches) => {
+                            found.extend(matches.into_iter().map(|range| MatchRange {
+                                level,
+                                start: range.start,
+                                end: range.end,
+                            }));
+                            true
+                        }
+                        None => false,
+                    });
+            if matched {
+                ranges.extend(found);
+            }
+        }
+        ranges.retain(|range| range.start < range.end);
+        ranges.sort_unstable();
+        let mut merged: Vec<MatchRange> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if last.level == range.level && range.start <= last.end => {
+                    last.end = last.end.max(range.end);
+                }
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+}
+
+/// Builds matchers the way `evaluate_term` does, skipping terms that fail to.
+fn collect_terms(
+    expr: &Expr,
+    options: SearchOptions,
+    overrides: TermOverrides,
+    terms: &mut Vec<Vec<SegmentMatcher>>,
+) {
+    match expr {
+        Expr::Term(Term::Word(text) | Term::Phrase(text)) => {
+            terms.extend(word_matchers(text, options, overrides));
+        }
+        Expr::Term(Term::Regex(pattern)) => {
+            let matcher = if overrides.whole_word {
+                regex_matcher(&whole_word_pattern(pattern), overrides.apply(options))
+            } else {
+                regex_matcher(pattern, overrides.apply(options))
+            };
+            if let Ok(matcher) = matcher {
+                terms.push(vec![matcher]);
+            }
+        }
+        Expr::Term(Term::Filter(filter)) => {
+            let Some(argument) = &filter.argument else {
+                return;
+            };
+            if is_term_modifier(filter) {
+                let (term, overrides) = modified_term(&filter.kind, argument, overrides);
+                collect_terms(&Expr::Term(term), options, overrides, terms);
+            } else if matches!(
+                filter.kind,
+                FilterKind::File
+                    | FilterKind::Folder
+                    | FilterKind::Audio
+                    | FilterKind::Video
+                    | FilterKind::Doc
+                    | FilterKind::Exe
+            ) {
+                // Their argument is a word of its own, see `evaluate_type_filter`.
+                terms.extend(word_matchers(
+                    &argument.raw,
+                    overrides.apply(options),
+                    TermOverrides::default(),
+                ));
+            }
+        }
+        Expr::And(parts) | Expr::Or(parts) => {
+            for part in parts {
+                collect_terms(part, options, overrides, terms);
+            }
+        }
+        Expr::Not(_) | Expr::Empty => {}
+    }
+}
+
+fn word_matchers(
+    text: &str,
+    options: SearchOptions,
+    overrides: TermOverrides,
+) -> Option<Vec<SegmentMatcher>> {
+    build_segment_matchers(&query_segmentation(text), options, overrides)
+        .ok()
+        .filter(|matchers| !matchers.is_empty())
+}
+
+impl SearchCache {
+    /// What the node matched in a search asking for highlights.
+    pub(crate) fn highlights(
+        &self,
+        details: &ResultDetails,
+        index: SlabIndex,
+    ) -> Option<Vec<MatchRange>> {
+        let highlighter = details.highlight.as_ref()?;
+        let mut names = vec![self.file_nodes.get(index)?.name_and_parent.as_str()];
+        let mut parent = self.file_nodes[index].name_and_parent.parent();
+        while let Some(index) = parent.filter(|_| names.len() < highlighter.levels) {
+            names.push(self.file_nodes[index].name_and_parent.as_str());
+            parent = self.file_nodes[index].name_and_parent.parent();
+        }
+        Some(highlighter.ranges(&names))
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{image_header::ImageInfo, number_filter::NumberPredicate};
+use anyhow::{Result, anyhow, bail};
+use cardinal_syntax::{ArgumentKind, Filter, FilterKind};
+
+/// A `width:`, `height:`, `dimensions:`, `orientation:` or `bitdepth:` filter.
+#[derive(Debug, Clone)]
+pub(crate) enum ImagePredicate {
+    Width(NumberPredicate),
+    Height(NumberPredicate),
+    BitDepth(NumberPredicate),
+    /// `dimensions:1920x1080`. Comparisons and ranges apply to both sides, so
+    /// `dimensions:>=800x600` needs at least 800 pixels across and 600 down.
+    Dimensions {
+        width: NumberPredicate,
+        height: NumberPredicate,
+    },
+    Orientation(Orientation),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Orientation {
+    Landscape,
+    Portrait,
+    Square,
+}
+
+impl ImagePredicate {
+    pub(crate) fn parse(filter: &Filter) -> Result<Self> {
+        let name = match filter.kind {
+            FilterKind::Width => "width",
+            FilterKind::Height => "height",
+            FilterKind::BitDepth => "bitdepth",
+            FilterKind::Dimensions => "dimensions",
+            FilterKind::Orientation => "orientation",
+            _ => bail!("Filter {:?} isn't an image filter", filter.kind),
+        };
+        let argument = filter
+            .argument
+            .as_ref()
+            .ok_or_else(|| anyhow!("{name}: requires a value"))?;
+        Ok(match filter.kind {
+            FilterKind::Width => Self::Width(NumberPredicate::parse(name, argument)?),
+            FilterKind::Height => Self::Height(NumberPredicate::parse(name, argument)?),
+            FilterKind::BitDepth => Self::BitDepth(NumberPredicate::parse(name, argument)?),
+            FilterKind::Dimensions => Self::Dimensions {
+                width: NumberPredicate::parse_with(argument, |raw| {
+                    parse_dimensions(raw).map(|(width, _)| width)
+                })?,
+                height: NumberPredicate::parse_with(argument, |raw| {
+                    parse_dimensions(raw).map(|(_, height)| height)
+                })?,
+            },
+            _ => {
+                if !matches!(argument.kind, ArgumentKind::Bare | ArgumentKind::Phrase) {
+                    bail!("orientation: expects landscape, portrait or square");
+                }
+                let raw = argument.raw.trim();
+                Self::Orientation(match raw.to_ascii_lowercase().as_str() {
+                    "landscape" | "horizontal" => Orientation::Landscape,
+                    "portrait" | "vertical" => Orientation::Portrait,
+                    "square" => Orientation::Square,
+                    _ => bail!("Unknown orientation {raw:?}"),
+                })
+            }
+        })
+    }
+
+    pub(crate) fn matches(&self, info: &ImageInfo) -> bool {
+        match self {
+            Self::Width(predicate) => predicate.matches(info.width),
+            Self::Height(predicate) => predicate.matches(info.height),
+            Self::BitDepth(predicate) => info
+                .bit_depth
+                .is_some_and(|bit_depth| predicate.matches(bit_depth)),
+            Self::Dimensions { width, height } => {
+                // `dimensions:!=WxH` rejects only that exact size.
+                if width.is_negated() {
+                    width.matches(info.width) || height.matches(info.height)
+                } else {
+                    width.matches(info.width) && height.matches(info.height)
+                }
+            }
+            Self::Orientation(orientation) => match orientation {
+                Orientation::Landscape => info.width > info.height,
+                Orientation::Portrait => info.width < info.height,
+                Orientation::Square => info.width == info.height,
+            },
+        }
+    }
+}
+
+/// Parses `1920x1080` (`×` and `*` work too) into width and height.
+fn parse_dimensions(raw: &str) -> Result<(u32, u32)> {
+    let invalid = || anyhow!("dimensions: expects WIDTHxHEIGHT, got {raw:?}");
+    let (width, height) = raw
+        .trim()
+        .split_once(['x', 'X', '×', '*'])
+        .ok_or_else(invalid)?;
+    let width = width.trim().parse().map_err(|_| invalid())?;
+    let height = height.trim().parse().map_err(|_| invalid())?;
+    Ok((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cardinal_syntax::{Expr, Term, parse_query};
+
+    fn predicate(query: &str) -> Result<ImagePredicate> {
+        let Expr::Term(Term::Filter(filter)) = parse_query(query).unwrap().expr else {
+            panic!("expected a filter");
+        };
+        ImagePredicate::parse(&filter)
+    }
+
+    fn image(width: u32, height: u32) -> ImageInfo {
+        ImageInfo {
+            width,
+            height,
+            bit_depth: Some(24),
+        }
+    }
+
+    #[test]
+    fn test_width_height_and_bitdepth() {
+        assert!(
+            predicate("width:>1000")
+                .unwrap()
+                .matches(&image(1920, 1080))
+        );
+        assert!(
+            !predicate("height:>1080")
+                .unwrap()
+                .matches(&image(1920, 1080))
+        );
+        assert!(
+            predicate("height:720..1080")
+                .unwrap()
+                .matches(&image(1920, 1080))
+        );
+        assert!(predicate("bitdepth:24").unwrap().matches(&image(1, 1)));
+        assert!(predicate("bitdepth:8;24").unwrap().matches(&image(1, 1)));
+        let unknown_depth = ImageInfo {
+            bit_depth: None,
+            ..image(1, 1)
+        };
+        assert!(!predicate("bitdepth:<=32").unwrap().matches(&unknown_depth));
+    }
+
+    #[test]
+    fn test_dimensions() {
+        let full_hd = image(1920, 1080);
+        assert!(predicate("dimensions:1920x1080").unwrap().matches(&full_hd));
+        assert!(!predicate("dimensions:1080x1920").unwrap().matches(&full_hd));
+        assert!(predicate("dimensions:>=800x600").unwrap().matches(&full_hd));
+        assert!(
+            !predicate("dimensions:>=800x2000")
+                .unwrap()
+                .matches(&full_hd)
+        );
+        assert!(
+            predicate("dimensions:1280x720..2560x1440")
+                .unwrap()
+                .matches(&full_hd)
+        );
+        assert!(
+            !predicate("dimensions:!=1920x1080")
+                .unwrap()
+                .matches(&full_hd)
+        );
+        assert!(
+            predicate("dimensions:!=1920x1200")
+                .unwrap()
+                .matches(&full_hd)
+        );
+        assert!(predicate("dimensions:1920").is_err());
+    }
+
+    #[test]
+    fn test_orientation() {
+        let landscape = predicate("orientation:landscape").unwrap();
+        assert!(landscape.matches(&image(1920, 1080)));
+        assert!(!landscape.matches(&image(1080, 1920)));
+        assert!(
+            predicate("orientation:portrait")
+                .unwrap()
+                .matches(&image(1080, 1920))
+        );
+        assert!(
+            predicate("orientation:horizontal")
+                .unwrap()
+                .matches(&image(2, 1))
+        );
+        assert!(
+            predicate("orientation:square")
+                .unwrap()
+                .matches(&image(5, 5))
+        );
+        assert!(predicate("orientation:diagonal").is_err());
+    }
+}
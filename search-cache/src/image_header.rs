@@ -0,0 +1,380 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// Size and depth of an image, read from its header without decoding pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ImageInfo {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    /// Bits per pixel across all channels, when the format records it.
+    pub(crate) bit_depth: Option<u32>,
+}
+
+/// Reads the dimensions of a PNG, JPEG, GIF, BMP, WebP or TIFF file.
+pub(crate) fn read_image_info(path: &Path) -> Option<ImageInfo> {
+    let file = File::open(path).ok()?;
+    read_image_header(&mut BufReader::new(file)).ok().flatten()
+}
+
+fn read_image_header<R: Read + Seek>(reader: &mut R) -> io::Result<Option<ImageInfo>> {
+    let mut magic = [0u8; 12];
+    reader.read_exact(&mut magic)?;
+    reader.seek(SeekFrom::Start(0))?;
+    let info = if magic.starts_with(b"\x89PNG\r\n\x1a\n") {
+        read_png(reader)?
+    } else if magic.starts_with(&[0xff, 0xd8]) {
+        read_jpeg(reader)?
+    } else if magic.starts_with(b"GIF87a") || magic.starts_with(b"GIF89a") {
+        read_gif(reader)?
+    } else if magic.starts_with(b"BM") {
+        read_bmp(reader)?
+    } else if magic.starts_with(b"RIFF") && &magic[8..12] == b"WEBP" {
+        read_webp(reader)?
+    } else if magic.starts_with(b"II*\0") || magic.starts_with(b"MM\0*") {
+        read_tiff(reader, magic[0] == b'I')?
+    } else {
+        None
+    };
+    Ok(info.filter(|info| info.width > 0 && info.height > 0))
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_png<R: Read + Seek>(reader: &mut R) -> io::Result<Option<ImageInfo>> {
+    // Signature, then the IHDR chunk's length and type.
+    let header: [u8; 16] = read_array(reader)?;
+    if &header[12..16] != b"IHDR" {
+        return Ok(None);
+    }
+    let ihdr: [u8; 10] = read_array(reader)?;
+    let channels = match ihdr[9] {
+        // Grayscale and palette
+        0 | 3 => 1,
+        // Grayscale with alpha
+        4 => 2,
+        // RGB
+        2 => 3,
+        // RGBA
+        6 => 4,
+        _ => return Ok(None),
+    };
+    Ok(Some(ImageInfo {
+        width: u32::from_be_bytes([ihdr[0], ihdr[1], ihdr[2], ihdr[3]]),
+        height: u32::from_be_bytes([ihdr[4], ihdr[5], ihdr[6], ihdr[7]]),
+        bit_depth: Some(u32::from(ihdr[8]) * channels),
+    }))
+}
+
+fn read_jpeg<R: Read + Seek>(reader: &mut R) -> io::Result<Option<ImageInfo>> {
+    reader.seek(SeekFrom::Start(2))?;
+    loop {
+        let [prefix, mut marker]: [u8; 2] = read_array(reader)?;
+        if prefix != 0xff {
+            return Ok(None);
+        }
+        // Markers may be preceded by any number of fill bytes.
+        while marker == 0xff {
+            [marker] = read_array(reader)?;
+        }
+        match marker {
+            // Standalone markers without a length.
+            0x01 | 0xd0..=0xd7 => continue,
+            // End of image or start of scan before any frame header.
+            0xd9 | 0xda => return Ok(None),
+            _ => {}
+        }
+        let length = u16::from_be_bytes(read_array(reader)?);
+        // SOF0..SOF15, except DHT (c4), JPG (c8) and DAC (cc).
+        if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+            let frame: [u8; 6] = read_array(reader)?;
+            return Ok(Some(ImageInfo {
+                height: u32::from(u16::from_be_bytes([frame[1], frame[2]])),
+                width: u32::from(u16::from_be_bytes([frame[3], frame[4]])),
+                bit_depth: Some(u32::from(frame[0]) * u32::from(frame[5])),
+            }));
+        }
+        if length < 2 {
+            return Ok(None);
+        }
+        reader.seek(SeekFrom::Current(i64::from(length) - 2))?;
+    }
+}
+
+fn read_gif<R: Read>(reader: &mut R) -> io::Result<Option<ImageInfo>> {
+    let header: [u8; 11] = read_array(reader)?;
+    Ok(Some(ImageInfo {
+        width: u32::from(u16::from_le_bytes([header[6], header[7]])),
+        height: u32::from(u16::from_le_bytes([header[8], header[9]])),
+        // Size of the global color table.
+        bit_depth: Some(u32::from(header[10] & 0x07) + 1),
+    }))
+}
+
+fn read_bmp<R: Read + Seek>(reader: &mut R) -> io::Result<Option<ImageInfo>> {
+    reader.seek(SeekFrom::Start(14))?;
+    let size = u32::from_le_bytes(read_array(reader)?);
+    if size == 12 {
+        // BITMAPCOREHEADER
+        let core: [u8; 8] = read_array(reader)?;
+        return Ok(Some(ImageInfo {
+            width: u32::from(u16::from_le_bytes([core[0], core[1]])),
+            height: u32::from(u16::from_le_bytes([core[2], core[3]])),
+            bit_depth: Some(u32::from(u16::from_le_bytes([core[6], core[7]]))),
+        }));
+    }
+    let info: [u8; 12] = read_array(reader)?;
+    let width = i32::from_le_bytes([info[0], info[1], info[2], info[3]]);
+    // Top-down bitmaps have a negative height.
+    let height = i32::from_le_bytes([info[4], info[5], info[6], info[7]]);
+    Ok(Some(ImageInfo {
+        width: width.unsigned_abs(),
+        height: height.unsigned_abs(),
+        bit_depth: Some(u32::from(u16::from_le_bytes([info[10], info[11]]))),
+    }))
+}
+
+fn read_webp<R: Read + Seek>(reader: &mut R) -> io::Result<Option<ImageInfo>> {
+    reader.seek(SeekFrom::Start(12))?;
+    let chunk: [u8; 8] = read_array(reader)?;
+    let data: [u8; 10] = read_array(reader)?;
+    let u24 = |bytes: &[u8]| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]);
+    Ok(match &chunk[..4] {
+        // Lossy: frame tag, start code, then 14-bit dimensions.
+        b"VP8 " if data[3..6] == [0x9d, 0x01, 0x2a] => Some(ImageInfo {
+            width: u32::from(u16::from_le_bytes([data[6], data[7]]) & 0x3fff),
+            height: u32::from(u16::from_le_bytes([data[8], data[9]]) & 0x3fff),
+            bit_depth: Some(24),
+        }),
+        // Lossless: signature, then width - 1, height - 1 and the alpha hint.
+        b"VP8L" if data[0] == 0x2f => {
+            let bits = u32::from_le_bytes([data[1], data[2], data[3], data[4]]);
+            Some(ImageInfo {
+                width: (bits & 0x3fff) + 1,
+                height: ((bits >> 14) & 0x3fff) + 1,
+                bit_depth: Some(if bits & (1 << 28) != 0 { 32 } else { 24 }),
+            })
+        }
+        // Extended: flags, reserved bytes, then 24-bit canvas width - 1 and height - 1.
+        b"VP8X" => Some(ImageInfo {
+            width: u24(&data[4..7]) + 1,
+            height: u24(&data[7..10]) + 1,
+            bit_depth: Some(if data[0] & 0x10 != 0 { 32 } else { 24 }),
+        }),
+        _ => None,
+    })
+}
+
+fn read_tiff<R: Read + Seek>(reader: &mut R, little_endian: bool) -> io::Result<Option<ImageInfo>> {
+    let u16_at = |bytes: [u8; 2]| {
+        if little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        }
+    };
+    let u32_at = |bytes: [u8; 4]| {
+        if little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        }
+    };
+    reader.seek(SeekFrom::Start(4))?;
+    let ifd = u32_at(read_array(reader)?);
+    reader.seek(SeekFrom::Start(u64::from(ifd)))?;
+    let count = u16_at(read_array(reader)?);
+    let mut width = None;
+    let mut height = None;
+    let mut bits_per_sample = None;
+    let mut samples_per_pixel = 1;
+    for _ in 0..count {
+        let entry: [u8; 12] = read_array(reader)?;
+        let tag = u16_at([entry[0], entry[1]]);
+        let field_type = u16_at([entry[2], entry[3]]);
+        let values = u32_at([entry[4], entry[5], entry[6], entry[7]]);
+        // SHORT values are left-aligned in the value field, LONGs fill it.
+        let value = match field_type {
+            3 => u32::from(u16_at([entry[8], entry[9]])),
+            4 => u32_at([entry[8], entry[9], entry[10], entry[11]]),
+            _ => continue,
+        };
+        match tag {
+            256 => width = Some(value),
+            257 => height = Some(value),
+            // More than two samples don't fit inline; they're usually all equal,
+            // so the first one is read from where the value field points.
+            258 if field_type == 3 && values > 2 => {
+                let resume = reader.stream_position()?;
+                reader.seek(SeekFrom::Start(u64::from(u32_at([
+                    entry[8], entry[9], entry[10], entry[11],
+                ]))))?;
+                bits_per_sample = Some(u32::from(u16_at(read_array(reader)?)));
+                reader.seek(SeekFrom::Start(resume))?;
+            }
+            258 => bits_per_sample = Some(value),
+            277 => samples_per_pixel = value,
+            _ => {}
+        }
+    }
+    let (Some(width), Some(height)) = (width, height) else {
+        return Ok(None);
+    };
+    Ok(Some(ImageInfo {
+        width,
+        height,
+        // A fuzzed header can claim more bits than fit; the depth is unknown then.
+        bit_depth: bits_per_sample.and_then(|bits| bits.checked_mul(samples_per_pixel)),
+    }))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn read(bytes: Vec<u8>) -> Option<ImageInfo> {
+        read_image_header(&mut Cursor::new(bytes)).unwrap()
+    }
+
+    fn info(width: u32, height: u32, bit_depth: u32) -> Option<ImageInfo> {
+        Some(ImageInfo {
+            width,
+            height,
+            bit_depth: Some(bit_depth),
+        })
+    }
+
+    /// Signature and IHDR chunk of an 8-bit RGBA PNG.
+    pub(crate) fn png_header(width: u32, height: u32) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend(width.to_be_bytes());
+        png.extend(height.to_be_bytes());
+        png.extend([8, 6, 0, 0, 0]);
+        png.extend([0; 4]);
+        png
+    }
+
+    #[test]
+    fn test_png() {
+        assert_eq!(read(png_header(1920, 1080)), info(1920, 1080, 32));
+    }
+
+    #[test]
+    fn test_jpeg() {
+        let mut jpeg = vec![0xff, 0xd8];
+        // APP0 segment to skip over.
+        jpeg.extend([0xff, 0xe0, 0x00, 0x10]);
+        jpeg.extend(b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0");
+        // Fill bytes, then a progressive frame header.
+        jpeg.extend([0xff, 0xff, 0xc2, 0x00, 0x11, 8]);
+        jpeg.extend(480u16.to_be_bytes());
+        jpeg.extend(640u16.to_be_bytes());
+        jpeg.extend([3; 10]);
+        assert_eq!(read(jpeg), info(640, 480, 24));
+    }
+
+    #[test]
+    fn test_gif() {
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend(320u16.to_le_bytes());
+        gif.extend(200u16.to_le_bytes());
+        gif.extend([0xf7, 0, 0]);
+        assert_eq!(read(gif), info(320, 200, 8));
+    }
+
+    #[test]
+    fn test_bmp() {
+        let mut bmp = b"BM".to_vec();
+        bmp.extend([0; 12]);
+        bmp.extend(40u32.to_le_bytes());
+        bmp.extend(800i32.to_le_bytes());
+        bmp.extend((-600i32).to_le_bytes());
+        bmp.extend(1u16.to_le_bytes());
+        bmp.extend(24u16.to_le_bytes());
+        bmp.extend([0; 24]);
+        assert_eq!(read(bmp), info(800, 600, 24));
+    }
+
+    #[test]
+    fn test_webp() {
+        let webp = |chunk: &[u8; 4], data: &[u8]| {
+            let mut webp = b"RIFF\0\0\0\0WEBP".to_vec();
+            webp.extend(chunk);
+            webp.extend((data.len() as u32).to_le_bytes());
+            webp.extend(data);
+            webp
+        };
+        let mut lossy = vec![0, 0, 0, 0x9d, 0x01, 0x2a];
+        lossy.extend(1024u16.to_le_bytes());
+        lossy.extend(768u16.to_le_bytes());
+        assert_eq!(read(webp(b"VP8 ", &lossy)), info(1024, 768, 24));
+
+        let bits: u32 = 99 | (49 << 14) | (1 << 28);
+        let mut lossless = vec![0x2f];
+        lossless.extend(bits.to_le_bytes());
+        lossless.extend([0; 5]);
+        assert_eq!(read(webp(b"VP8L", &lossless)), info(100, 50, 32));
+
+        let mut extended = vec![0x10, 0, 0, 0];
+        extended.extend(&4095u32.to_le_bytes()[..3]);
+        extended.extend(&2159u32.to_le_bytes()[..3]);
+        assert_eq!(read(webp(b"VP8X", &extended)), info(4096, 2160, 32));
+    }
+
+    /// A big-endian TIFF IFD entry.
+    fn entry(tag: u16, field_type: u16, count: u32, value: [u8; 4]) -> Vec<u8> {
+        let mut entry = tag.to_be_bytes().to_vec();
+        entry.extend(field_type.to_be_bytes());
+        entry.extend(count.to_be_bytes());
+        entry.extend(value);
+        entry
+    }
+
+    #[test]
+    fn test_tiff() {
+        let mut tiff = b"MM\0*".to_vec();
+        tiff.extend(8u32.to_be_bytes());
+        tiff.extend(4u16.to_be_bytes());
+        tiff.extend(entry(256, 4, 1, 3000u32.to_be_bytes()));
+        tiff.extend(entry(257, 3, 1, [0x07, 0xd0, 0, 0]));
+        // Three BitsPerSample values stored after the IFD.
+        tiff.extend(entry(258, 3, 3, 62u32.to_be_bytes()));
+        tiff.extend(entry(277, 3, 1, [0, 3, 0, 0]));
+        tiff.extend([0; 4]);
+        tiff.extend([0, 16, 0, 16, 0, 16]);
+        assert_eq!(read(tiff), info(3000, 2000, 48));
+    }
+
+    #[test]
+    fn test_tiff_bit_depth_overflow() {
+        let mut tiff = b"MM\0*".to_vec();
+        tiff.extend(8u32.to_be_bytes());
+        tiff.extend(4u16.to_be_bytes());
+        tiff.extend(entry(256, 4, 1, 640u32.to_be_bytes()));
+        tiff.extend(entry(257, 4, 1, 480u32.to_be_bytes()));
+        tiff.extend(entry(258, 4, 1, 0x10000u32.to_be_bytes()));
+        tiff.extend(entry(277, 4, 1, 0x10000u32.to_be_bytes()));
+        tiff.extend([0; 4]);
+        assert_eq!(
+            read(tiff),
+            Some(ImageInfo {
+                width: 640,
+                height: 480,
+                bit_depth: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_and_truncated() {
+        assert_eq!(read(b"not an image at all".to_vec()), None);
+        assert!(read_image_header(&mut Cursor::new(b"\x89PNG".to_vec())).is_err());
+    }
+}
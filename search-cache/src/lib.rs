@@ -1,24 +1,50 @@
 #![feature(str_from_raw_parts)]
+mod acronym;
+mod attribute_filter;
 mod cache;
+mod category;
+mod content_filter;
+mod date_filter;
 mod file_nodes;
+mod filter_provider;
+mod fold;
+mod fuzzy;
+mod highlight;
+mod image_filter;
+mod image_header;
+mod media_tags;
 mod metadata_cache;
 mod name_index;
+mod node_set;
+mod number_filter;
 mod persistent;
+mod planner;
 mod query;
+mod relevance;
 mod segment;
+mod size_filter;
 mod slab;
 mod slab_node;
+mod sort;
+mod tag_filter;
 mod type_and_size;
+mod wildcard;
 
 pub use cache::*;
+pub use cardinal_syntax::{ArgumentKind, Filter, FilterArgument, MacroRegistry};
+pub use category::FileCategories;
+pub use content_filter::ContentSearchConfig;
 pub use file_nodes::*;
+pub use filter_provider::{FilterProvider, NodeView};
 pub use fswalk::WalkData;
+pub use highlight::MatchRange;
 pub use metadata_cache::*;
 pub use name_index::*;
 pub use persistent::*;
 pub use segment::*;
 pub use slab::*;
 pub use slab_node::*;
+pub use sort::{SortDirection, SortKey, SortSpec};
 pub use type_and_size::*;
 
 #[cfg(test)]
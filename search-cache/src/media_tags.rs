@@ -0,0 +1,896 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// Frames and atoms larger than this are skipped; they're almost always artwork.
+const MAX_FIELD_LEN: u64 = 64 * 1024;
+/// Vorbis comment blocks may embed base64 cover art, so they get more room.
+/// Longer blocks are truncated, which still keeps the text fields in front.
+const MAX_COMMENT_LEN: u64 = 1024 * 1024;
+
+/// ID3v1 genre numbers, also used by ID3v2 `TCON` references and MP4 `gnre`.
+const ID3_GENRES: &[&str] = &[
+    "Blues",
+    "Classic Rock",
+    "Country",
+    "Dance",
+    "Disco",
+    "Funk",
+    "Grunge",
+    "Hip-Hop",
+    "Jazz",
+    "Metal",
+    "New Age",
+    "Oldies",
+    "Other",
+    "Pop",
+    "R&B",
+    "Rap",
+    "Reggae",
+    "Rock",
+    "Techno",
+    "Industrial",
+    "Alternative",
+    "Ska",
+    "Death Metal",
+    "Pranks",
+    "Soundtrack",
+    "Euro-Techno",
+    "Ambient",
+    "Trip-Hop",
+    "Vocal",
+    "Jazz+Funk",
+    "Fusion",
+    "Trance",
+    "Classical",
+    "Instrumental",
+    "Acid",
+    "House",
+    "Game",
+    "Sound Clip",
+    "Gospel",
+    "Noise",
+    "Alternative Rock",
+    "Bass",
+    "Soul",
+    "Punk",
+    "Space",
+    "Meditative",
+    "Instrumental Pop",
+    "Instrumental Rock",
+    "Ethnic",
+    "Gothic",
+    "Darkwave",
+    "Techno-Industrial",
+    "Electronic",
+    "Pop-Folk",
+    "Eurodance",
+    "Dream",
+    "Southern Rock",
+    "Comedy",
+    "Cult",
+    "Gangsta",
+    "Top 40",
+    "Christian Rap",
+    "Pop/Funk",
+    "Jungle",
+    "Native American",
+    "Cabaret",
+    "New Wave",
+    "Psychedelic",
+    "Rave",
+    "Showtunes",
+    "Trailer",
+    "Lo-Fi",
+    "Tribal",
+    "Acid Punk",
+    "Acid Jazz",
+    "Polka",
+    "Retro",
+    "Musical",
+    "Rock & Roll",
+    "Hard Rock",
+];
+
+/// Text and numbers pulled out of an audio file's tags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct MediaTags {
+    pub(crate) artist: Option<String>,
+    pub(crate) album: Option<String>,
+    pub(crate) title: Option<String>,
+    pub(crate) genre: Option<String>,
+    pub(crate) comment: Option<String>,
+    pub(crate) year: Option<u32>,
+    pub(crate) track: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Artist,
+    Album,
+    Title,
+    Genre,
+    Comment,
+    Year,
+    Track,
+}
+
+impl MediaTags {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Stores a textual value. Repeated text fields are joined with `"; "`,
+    /// numbers keep the first value that parses.
+    fn push(&mut self, field: Field, value: &str) {
+        let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+        if value.is_empty() {
+            return;
+        }
+        let slot = match field {
+            Field::Artist => &mut self.artist,
+            Field::Album => &mut self.album,
+            Field::Title => &mut self.title,
+            Field::Genre => &mut self.genre,
+            Field::Comment => &mut self.comment,
+            Field::Year => {
+                self.year = self.year.or_else(|| leading_number(value));
+                return;
+            }
+            Field::Track => {
+                self.track = self.track.or_else(|| leading_number(value));
+                return;
+            }
+        };
+        match slot {
+            Some(existing) => {
+                existing.push_str("; ");
+                existing.push_str(value);
+            }
+            None => *slot = Some(value.to_string()),
+        }
+    }
+
+    /// Fills the fields that are still missing from `other`.
+    fn merge_missing(&mut self, other: MediaTags) {
+        self.artist = self.artist.take().or(other.artist);
+        self.album = self.album.take().or(other.album);
+        self.title = self.title.take().or(other.title);
+        self.genre = self.genre.take().or(other.genre);
+        self.comment = self.comment.take().or(other.comment);
+        self.year = self.year.or(other.year);
+        self.track = self.track.or(other.track);
+    }
+}
+
+/// Reads ID3v1/ID3v2, FLAC and Ogg Vorbis comments or MP4 `ilst` atoms from
+/// `path`. Returns `None` when the file can't be read or carries no tags.
+pub(crate) fn read_media_tags(path: &Path) -> Option<MediaTags> {
+    let file = File::open(path).ok()?;
+    let tags = read_tags(&mut BufReader::new(file)).ok()?;
+    (!tags.is_empty()).then_some(tags)
+}
+
+fn read_tags<R: Read + Seek>(reader: &mut R) -> io::Result<MediaTags> {
+    let mut magic = [0u8; 8];
+    let len = read_up_to(reader, &mut magic)?;
+    let magic = &magic[..len];
+    reader.seek(SeekFrom::Start(0))?;
+    if magic.starts_with(b"fLaC") {
+        read_flac(reader)
+    } else if magic.starts_with(b"OggS") {
+        read_ogg(reader)
+    } else if magic.get(4..8) == Some(b"ftyp") {
+        read_mp4(reader)
+    } else {
+        let mut tags = if magic.starts_with(b"ID3") {
+            read_id3v2(reader)?
+        } else {
+            MediaTags::default()
+        };
+        // Older MP3s often only carry the fixed-size ID3v1 trailer.
+        if let Some(v1) = read_id3v1(reader)? {
+            tags.merge_missing(v1);
+        }
+        Ok(tags)
+    }
+}
+
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+fn read_body<R: Read>(reader: &mut R, len: u64) -> io::Result<Vec<u8>> {
+    let mut body = Vec::with_capacity(len as usize);
+    reader.take(len).read_to_end(&mut body)?;
+    if (body.len() as u64) < len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(body)
+}
+
+fn leading_number(value: &str) -> Option<u32> {
+    let value = value.trim_start();
+    let digits = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    value[..digits].parse().ok()
+}
+
+fn synchsafe(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0, |acc, &byte| (acc << 7) | u32::from(byte & 0x7f))
+}
+
+fn id3_field(id: &[u8]) -> Option<Field> {
+    Some(match id {
+        b"TPE1" | b"TP1" => Field::Artist,
+        b"TALB" | b"TAL" => Field::Album,
+        b"TIT2" | b"TT2" => Field::Title,
+        b"TCON" | b"TCO" => Field::Genre,
+        b"TYER" | b"TDRC" | b"TYE" => Field::Year,
+        b"TRCK" | b"TRK" => Field::Track,
+        b"COMM" | b"COM" => Field::Comment,
+        _ => return None,
+    })
+}
+
+fn read_id3v2<R: Read + Seek>(reader: &mut R) -> io::Result<MediaTags> {
+    let mut tags = MediaTags::default();
+    let mut header = [0u8; 10];
+    reader.read_exact(&mut header)?;
+    let version = header[3];
+    let flags = header[5];
+    if !(2..=4).contains(&version) {
+        return Ok(tags);
+    }
+    let unsynchronised = flags & 0x80 != 0;
+    let tag_end = 10 + u64::from(synchsafe(&header[6..10]));
+    let mut pos = 10;
+    if version >= 3 && flags & 0x40 != 0 {
+        let mut size = [0u8; 4];
+        reader.read_exact(&mut size)?;
+        // v2.4 counts the size field itself, v2.3 doesn't.
+        pos += match version {
+            3 => 4 + u64::from(u32::from_be_bytes(size)),
+            _ => u64::from(synchsafe(&size)),
+        };
+        reader.seek(SeekFrom::Start(pos))?;
+    }
+    let (id_len, header_len) = if version == 2 { (3, 6) } else { (4, 10) };
+    while pos + header_len as u64 <= tag_end {
+        let mut frame = [0u8; 10];
+        reader.read_exact(&mut frame[..header_len])?;
+        if frame[0] == 0 {
+            // Padding.
+            break;
+        }
+        let size = u64::from(match version {
+            2 => u32::from_be_bytes([0, frame[3], frame[4], frame[5]]),
+            3 => u32::from_be_bytes([frame[4], frame[5], frame[6], frame[7]]),
+            _ => synchsafe(&frame[4..8]),
+        });
+        pos += header_len as u64 + size;
+        if pos > tag_end {
+            break;
+        }
+        let format_flags = frame[9];
+        let (compressed_or_encrypted, data_length_indicator, frame_unsync) = match version {
+            2 => (false, false, false),
+            3 => (format_flags & 0xc0 != 0, false, false),
+            _ => (
+                format_flags & 0x0c != 0,
+                format_flags & 0x01 != 0,
+                format_flags & 0x02 != 0,
+            ),
+        };
+        let field = id3_field(&frame[..id_len]);
+        let Some(field) = field.filter(|_| size <= MAX_FIELD_LEN && !compressed_or_encrypted)
+        else {
+            reader.seek(SeekFrom::Start(pos))?;
+            continue;
+        };
+        let mut body = read_body(reader, size)?;
+        if unsynchronised || frame_unsync {
+            body = remove_unsynchronisation(&body);
+        }
+        let body = if data_length_indicator {
+            body.get(4..).unwrap_or_default()
+        } else {
+            &body
+        };
+        let Some((&encoding, text)) = body.split_first() else {
+            continue;
+        };
+        match field {
+            Field::Comment => {
+                // Language code, then a short description and the comment itself.
+                let Some(text) = text.get(3..) else {
+                    continue;
+                };
+                let (description, comment) = split_terminated(encoding, text);
+                // iTunes stores its loudness and gapless data in descriptive comments.
+                if !decode_text(encoding, description).starts_with("iTun") {
+                    tags.push(field, &decode_text(encoding, comment));
+                }
+            }
+            Field::Genre => {
+                for genre in decode_text(encoding, text).split('\0') {
+                    tags.push(field, &resolve_genre(genre));
+                }
+            }
+            _ => {
+                // ID3v2.4 separates multiple values with NUL.
+                for value in decode_text(encoding, text).split('\0') {
+                    tags.push(field, value);
+                }
+            }
+        }
+    }
+    Ok(tags)
+}
+
+fn read_id3v1<R: Read + Seek>(reader: &mut R) -> io::Result<Option<MediaTags>> {
+    let len = reader.seek(SeekFrom::End(0))?;
+    if len < 128 {
+        return Ok(None);
+    }
+    reader.seek(SeekFrom::End(-128))?;
+    let mut block = [0u8; 128];
+    reader.read_exact(&mut block)?;
+    if &block[..3] != b"TAG" {
+        return Ok(None);
+    }
+    let latin1 = |bytes: &[u8]| decode_text(0, bytes);
+    let mut tags = MediaTags::default();
+    tags.push(Field::Title, &latin1(&block[3..33]));
+    tags.push(Field::Artist, &latin1(&block[33..63]));
+    tags.push(Field::Album, &latin1(&block[63..93]));
+    tags.push(Field::Year, &latin1(&block[93..97]));
+    let comment = &block[97..127];
+    // ID3v1.1 steals the last two comment bytes for the track number.
+    if comment[28] == 0 && comment[29] != 0 {
+        tags.push(Field::Comment, &latin1(&comment[..28]));
+        tags.track = Some(u32::from(comment[29]));
+    } else {
+        tags.push(Field::Comment, &latin1(comment));
+    }
+    if let Some(genre) = ID3_GENRES.get(usize::from(block[127])) {
+        tags.push(Field::Genre, genre);
+    }
+    Ok(Some(tags))
+}
+
+/// Undoes ID3v2 unsynchronisation, which inserts a zero byte after every `0xff`.
+fn remove_unsynchronisation(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut previous = 0;
+    for &byte in bytes {
+        if !(previous == 0xff && byte == 0) {
+            out.push(byte);
+        }
+        previous = byte;
+    }
+    out
+}
+
+/// Splits ID3v2 text at its first terminator, which is two bytes wide for UTF-16.
+fn split_terminated(encoding: u8, bytes: &[u8]) -> (&[u8], &[u8]) {
+    if matches!(encoding, 1 | 2) {
+        let end = bytes
+            .chunks_exact(2)
+            .position(|unit| unit == [0, 0])
+            .map(|unit| unit * 2);
+        match end {
+            Some(end) => (&bytes[..end], &bytes[end + 2..]),
+            None => (bytes, &[]),
+        }
+    } else {
+        match bytes.iter().position(|&byte| byte == 0) {
+            Some(end) => (&bytes[..end], &bytes[end + 1..]),
+            None => (bytes, &[]),
+        }
+    }
+}
+
+/// Decodes ID3v2 text: 0 is Latin-1, 1 UTF-16 with a BOM, 2 UTF-16BE and 3 UTF-8.
+fn decode_text(encoding: u8, bytes: &[u8]) -> String {
+    match encoding {
+        0 => bytes.iter().map(|&byte| char::from(byte)).collect(),
+        1 | 2 => {
+            let little_endian = encoding == 1 && bytes.starts_with(&[0xff, 0xfe]);
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|unit| {
+                    if little_endian {
+                        u16::from_le_bytes([unit[0], unit[1]])
+                    } else {
+                        u16::from_be_bytes([unit[0], unit[1]])
+                    }
+                })
+                .filter(|&unit| unit != 0xfeff)
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Turns `"(17)"`, `"17"` or `"(17)Rock & Roll"` into a genre name.
+fn resolve_genre(raw: &str) -> String {
+    let raw = raw.trim();
+    let lookup = |number: &str| {
+        number
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| ID3_GENRES.get(number))
+            .map(|genre| genre.to_string())
+    };
+    if let Some(rest) = raw.strip_prefix('(') {
+        if let Some((number, refinement)) = rest.split_once(')') {
+            if !refinement.trim().is_empty() {
+                return refinement.trim().to_string();
+            }
+            if let Some(genre) = lookup(number) {
+                return genre;
+            }
+        }
+    }
+    if !raw.is_empty() && raw.bytes().all(|byte| byte.is_ascii_digit()) {
+        if let Some(genre) = lookup(raw) {
+            return genre;
+        }
+    }
+    raw.to_string()
+}
+
+fn read_flac<R: Read + Seek>(reader: &mut R) -> io::Result<MediaTags> {
+    reader.seek(SeekFrom::Start(4))?;
+    loop {
+        let mut header = [0u8; 4];
+        reader.read_exact(&mut header)?;
+        let last = header[0] & 0x80 != 0;
+        let block_type = header[0] & 0x7f;
+        let len = u64::from(u32::from_be_bytes([0, header[1], header[2], header[3]]));
+        if block_type == 4 {
+            let mut block = Vec::new();
+            reader
+                .take(len.min(MAX_COMMENT_LEN))
+                .read_to_end(&mut block)?;
+            return Ok(parse_vorbis_comment(&block));
+        }
+        if last {
+            return Ok(MediaTags::default());
+        }
+        reader.seek(SeekFrom::Current(len as i64))?;
+    }
+}
+
+fn read_ogg<R: Read + Seek>(reader: &mut R) -> io::Result<MediaTags> {
+    // The comment header is the second packet of the logical stream.
+    let mut packet_index = 0;
+    let mut packet = Vec::new();
+    loop {
+        let mut header = [0u8; 27];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != b"OggS" {
+            return Ok(MediaTags::default());
+        }
+        let mut lacing = vec![0u8; usize::from(header[26])];
+        reader.read_exact(&mut lacing)?;
+        for &segment in &lacing {
+            if packet_index == 1 && (packet.len() as u64) < MAX_COMMENT_LEN {
+                packet.extend(read_body(reader, u64::from(segment))?);
+            } else {
+                reader.seek(SeekFrom::Current(i64::from(segment)))?;
+            }
+            if segment < 255 {
+                packet_index += 1;
+                if packet_index == 2 {
+                    return Ok(parse_ogg_comment_packet(&packet));
+                }
+            }
+        }
+        if packet_index == 1 && packet.len() as u64 >= MAX_COMMENT_LEN {
+            return Ok(parse_ogg_comment_packet(&packet));
+        }
+    }
+}
+
+fn parse_ogg_comment_packet(packet: &[u8]) -> MediaTags {
+    if let Some(comment) = packet.strip_prefix(b"\x03vorbis") {
+        parse_vorbis_comment(comment)
+    } else if let Some(comment) = packet.strip_prefix(b"OpusTags") {
+        parse_vorbis_comment(comment)
+    } else {
+        MediaTags::default()
+    }
+}
+
+/// Parses a Vorbis comment block, stopping quietly at the first truncated entry.
+fn parse_vorbis_comment(data: &[u8]) -> MediaTags {
+    let mut tags = MediaTags::default();
+    let mut cursor = 0;
+    let next_u32 = |cursor: &mut usize| {
+        let bytes = data.get(*cursor..*cursor + 4)?;
+        *cursor += 4;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    };
+    let Some(vendor_len) = next_u32(&mut cursor) else {
+        return tags;
+    };
+    cursor += vendor_len;
+    let Some(count) = next_u32(&mut cursor) else {
+        return tags;
+    };
+    for _ in 0..count {
+        let Some(len) = next_u32(&mut cursor) else {
+            break;
+        };
+        let Some(entry) = data.get(cursor..cursor + len) else {
+            break;
+        };
+        cursor += len;
+        let entry = String::from_utf8_lossy(entry);
+        let Some((key, value)) = entry.split_once('=') else {
+            continue;
+        };
+        let field = match key.to_ascii_uppercase().as_str() {
+            "ARTIST" => Field::Artist,
+            "ALBUM" => Field::Album,
+            "TITLE" => Field::Title,
+            "GENRE" => Field::Genre,
+            "COMMENT" | "DESCRIPTION" => Field::Comment,
+            "DATE" | "YEAR" => Field::Year,
+            "TRACKNUMBER" => Field::Track,
+            _ => continue,
+        };
+        tags.push(field, value);
+    }
+    tags
+}
+
+/// `(type, body start, body end)` of the atoms between `start` and `end`.
+fn mp4_atoms<R: Read + Seek>(
+    reader: &mut R,
+    start: u64,
+    end: u64,
+) -> io::Result<Vec<([u8; 4], u64, u64)>> {
+    let mut atoms = Vec::new();
+    let mut pos = start;
+    while pos + 8 <= end {
+        reader.seek(SeekFrom::Start(pos))?;
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        let kind = [header[4], header[5], header[6], header[7]];
+        let (body_start, size) =
+            match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+                0 => (pos + 8, end - pos),
+                1 => {
+                    let mut large = [0u8; 8];
+                    reader.read_exact(&mut large)?;
+                    (pos + 16, u64::from_be_bytes(large))
+                }
+                size => (pos + 8, u64::from(size)),
+            };
+        let atom_end = pos.saturating_add(size);
+        if atom_end < body_start || atom_end > end {
+            break;
+        }
+        atoms.push((kind, body_start, atom_end));
+        pos = atom_end;
+    }
+    Ok(atoms)
+}
+
+fn find_mp4_atom<R: Read + Seek>(
+    reader: &mut R,
+    start: u64,
+    end: u64,
+    kind: &[u8; 4],
+) -> io::Result<Option<(u64, u64)>> {
+    Ok(mp4_atoms(reader, start, end)?
+        .into_iter()
+        .find(|(atom, ..)| atom == kind)
+        .map(|(_, start, end)| (start, end)))
+}
+
+fn read_mp4<R: Read + Seek>(reader: &mut R) -> io::Result<MediaTags> {
+    let mut tags = MediaTags::default();
+    let len = reader.seek(SeekFrom::End(0))?;
+    let mut range = (0, len);
+    for kind in [b"moov", b"udta", b"meta"] {
+        let Some(found) = find_mp4_atom(reader, range.0, range.1, kind)? else {
+            return Ok(tags);
+        };
+        range = found;
+    }
+    // `meta` is normally a full atom with version and flags, except in some
+    // QuickTime files where a child atom starts right away.
+    reader.seek(SeekFrom::Start(range.0))?;
+    let mut probe = [0u8; 8];
+    reader.read_exact(&mut probe)?;
+    if &probe[4..] != b"hdlr" {
+        range.0 += 4;
+    }
+    let Some((ilst_start, ilst_end)) = find_mp4_atom(reader, range.0, range.1, b"ilst")? else {
+        return Ok(tags);
+    };
+    for (kind, start, end) in mp4_atoms(reader, ilst_start, ilst_end)? {
+        let field = match &kind {
+            b"\xa9ART" => Field::Artist,
+            b"\xa9alb" => Field::Album,
+            b"\xa9nam" => Field::Title,
+            b"\xa9gen" | b"gnre" => Field::Genre,
+            b"\xa9cmt" => Field::Comment,
+            b"\xa9day" => Field::Year,
+            b"trkn" => Field::Track,
+            _ => continue,
+        };
+        let Some((data_start, data_end)) = find_mp4_atom(reader, start, end, b"data")? else {
+            continue;
+        };
+        if data_end - data_start > MAX_FIELD_LEN {
+            continue;
+        }
+        reader.seek(SeekFrom::Start(data_start))?;
+        let data = read_body(reader, data_end - data_start)?;
+        // Type indicator and locale come before the payload.
+        let Some(payload) = data.get(8..) else {
+            continue;
+        };
+        match (&kind, payload) {
+            (b"trkn", [_, _, high, low, ..]) => {
+                tags.track = tags
+                    .track
+                    .or(Some(u32::from(u16::from_be_bytes([*high, *low]))));
+            }
+            (b"gnre", [high, low, ..]) => {
+                // One-based ID3v1 genre number.
+                let number = usize::from(u16::from_be_bytes([*high, *low]));
+                if let Some(genre) = number.checked_sub(1).and_then(|n| ID3_GENRES.get(n)) {
+                    tags.push(field, genre);
+                }
+            }
+            (b"trkn" | b"gnre", _) => {}
+            _ => tags.push(field, &String::from_utf8_lossy(payload)),
+        }
+    }
+    Ok(tags)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn read(bytes: Vec<u8>) -> MediaTags {
+        read_tags(&mut Cursor::new(bytes)).unwrap()
+    }
+
+    fn id3v2_frame(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut frame = id.to_vec();
+        frame.extend((body.len() as u32).to_be_bytes());
+        frame.extend([0, 0]);
+        frame.extend(body);
+        frame
+    }
+
+    /// An ID3v2.3 tag with Latin-1 text frames, followed by some fake audio.
+    pub(crate) fn id3v2_file(frames: &[(&[u8; 4], &str)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (id, text) in frames {
+            let mut payload = vec![0];
+            if *id == b"COMM" {
+                payload.extend(b"eng\0");
+            }
+            payload.extend(text.as_bytes());
+            body.extend(id3v2_frame(id, &payload));
+        }
+        body.extend([0; 16]);
+        let size = body.len() as u32;
+        let mut file = b"ID3\x03\x00\x00".to_vec();
+        file.extend([
+            (size >> 21) as u8 & 0x7f,
+            (size >> 14) as u8 & 0x7f,
+            (size >> 7) as u8 & 0x7f,
+            size as u8 & 0x7f,
+        ]);
+        file.extend(body);
+        file.extend([0xff, 0xfb, 0x90, 0x00]);
+        file
+    }
+
+    fn vorbis_comment(entries: &[&str]) -> Vec<u8> {
+        let mut block = Vec::new();
+        block.extend(6u32.to_le_bytes());
+        block.extend(b"vendor");
+        block.extend((entries.len() as u32).to_le_bytes());
+        for entry in entries {
+            block.extend((entry.len() as u32).to_le_bytes());
+            block.extend(entry.as_bytes());
+        }
+        block
+    }
+
+    pub(crate) fn flac_file(entries: &[&str]) -> Vec<u8> {
+        let mut file = b"fLaC".to_vec();
+        // STREAMINFO
+        file.extend([0, 0, 0, 34]);
+        file.extend([0; 34]);
+        let comment = vorbis_comment(entries);
+        file.push(0x80 | 4);
+        file.extend(&(comment.len() as u32).to_be_bytes()[1..]);
+        file.extend(comment);
+        file
+    }
+
+    fn ogg_page(sequence: u32, packet: &[u8]) -> Vec<u8> {
+        let mut page = b"OggS\x00\x00".to_vec();
+        page.extend([0; 8]);
+        page.extend(1u32.to_le_bytes());
+        page.extend(sequence.to_le_bytes());
+        page.extend([0; 4]);
+        let mut lacing = vec![255; packet.len() / 255];
+        lacing.push((packet.len() % 255) as u8);
+        page.push(lacing.len() as u8);
+        page.extend(lacing);
+        page.extend(packet);
+        page
+    }
+
+    fn mp4_atom(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut atom = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        atom.extend(kind);
+        atom.extend(body);
+        atom
+    }
+
+    fn mp4_item(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut data = vec![0, 0, 0, 1, 0, 0, 0, 0];
+        data.extend(payload);
+        mp4_atom(kind, &mp4_atom(b"data", &data))
+    }
+
+    #[test]
+    fn test_id3v2() {
+        let tags = read(id3v2_file(&[
+            (b"TPE1", "Daft Punk"),
+            (b"TALB", "Discovery"),
+            (b"TIT2", "One More Time"),
+            (b"TCON", "(35)"),
+            (b"TYER", "2001"),
+            (b"TRCK", "1/14"),
+            (b"COMM", "Remastered"),
+        ]));
+        assert_eq!(tags.artist.as_deref(), Some("Daft Punk"));
+        assert_eq!(tags.album.as_deref(), Some("Discovery"));
+        assert_eq!(tags.title.as_deref(), Some("One More Time"));
+        assert_eq!(tags.genre.as_deref(), Some("House"));
+        assert_eq!(tags.comment.as_deref(), Some("Remastered"));
+        assert_eq!(tags.year, Some(2001));
+        assert_eq!(tags.track, Some(1));
+    }
+
+    #[test]
+    fn test_id3v2_utf16_and_skipped_frames() {
+        let mut title = vec![1, 0xff, 0xfe];
+        title.extend("Café".encode_utf16().flat_map(u16::to_le_bytes));
+        let mut body = id3v2_frame(b"APIC", &[0; 200]);
+        body.extend(id3v2_frame(b"TIT2", &title));
+        let size = body.len() as u32;
+        let mut file = b"ID3\x03\x00\x00\x00\x00".to_vec();
+        file.extend([(size >> 7) as u8 & 0x7f, size as u8 & 0x7f]);
+        file.extend(body);
+        let tags = read(file);
+        assert_eq!(tags.title.as_deref(), Some("Café"));
+        assert_eq!(tags.artist, None);
+    }
+
+    #[test]
+    fn test_id3v1() {
+        let mut file = vec![0xff, 0xfb, 0x90, 0x00];
+        let mut block = b"TAG".to_vec();
+        for (text, len) in [("Song", 30), ("Band", 30), ("Album", 30), ("1999", 4)] {
+            let mut field = text.as_bytes().to_vec();
+            field.resize(len, 0);
+            block.extend(field);
+        }
+        let mut comment = b"old".to_vec();
+        comment.resize(28, 0);
+        block.extend(comment);
+        block.extend([0, 7, 17]);
+        file.extend(block);
+        let tags = read(file);
+        assert_eq!(tags.title.as_deref(), Some("Song"));
+        assert_eq!(tags.artist.as_deref(), Some("Band"));
+        assert_eq!(tags.album.as_deref(), Some("Album"));
+        assert_eq!(tags.comment.as_deref(), Some("old"));
+        assert_eq!(tags.genre.as_deref(), Some("Rock"));
+        assert_eq!(tags.year, Some(1999));
+        assert_eq!(tags.track, Some(7));
+    }
+
+    #[test]
+    fn test_flac() {
+        let tags = read(flac_file(&[
+            "ARTIST=Radiohead",
+            "artist=Thom Yorke",
+            "TITLE=Reckoner",
+            "DATE=2007-10-10",
+            "TRACKNUMBER=07",
+            "GENRE=Alternative",
+        ]));
+        assert_eq!(tags.artist.as_deref(), Some("Radiohead; Thom Yorke"));
+        assert_eq!(tags.title.as_deref(), Some("Reckoner"));
+        assert_eq!(tags.genre.as_deref(), Some("Alternative"));
+        assert_eq!(tags.year, Some(2007));
+        assert_eq!(tags.track, Some(7));
+    }
+
+    #[test]
+    fn test_ogg_vorbis_and_opus() {
+        let mut vorbis = ogg_page(0, b"\x01vorbis-identification");
+        let mut packet = b"\x03vorbis".to_vec();
+        packet.extend(vorbis_comment(&["ALBUM=In Rainbows", &"x".repeat(600)]));
+        vorbis.extend(ogg_page(1, &packet));
+        assert_eq!(read(vorbis).album.as_deref(), Some("In Rainbows"));
+
+        let mut opus = ogg_page(0, b"OpusHead");
+        let mut packet = b"OpusTags".to_vec();
+        packet.extend(vorbis_comment(&["TITLE=Nude"]));
+        opus.extend(ogg_page(1, &packet));
+        assert_eq!(read(opus).title.as_deref(), Some("Nude"));
+    }
+
+    #[test]
+    fn test_mp4() {
+        let mut ilst = mp4_item(b"\xa9ART", b"Bjork");
+        ilst.extend(mp4_item(b"\xa9day", b"1997-09-22T07:00:00Z"));
+        ilst.extend(mp4_item(b"trkn", &[0, 0, 0, 3, 0, 10, 0, 0]));
+        ilst.extend(mp4_item(b"gnre", &[0, 15]));
+        let mut meta = vec![0, 0, 0, 0];
+        meta.extend(mp4_atom(b"hdlr", &[0; 25]));
+        meta.extend(mp4_atom(b"ilst", &ilst));
+        let moov = mp4_atom(
+            b"moov",
+            &[
+                mp4_atom(b"mvhd", &[0; 100]),
+                mp4_atom(b"udta", &mp4_atom(b"meta", &meta)),
+            ]
+            .concat(),
+        );
+        let mut file = mp4_atom(b"ftyp", b"M4A \0\0\0\0");
+        file.extend(mp4_atom(b"mdat", &[0; 64]));
+        file.extend(moov);
+        let tags = read(file);
+        assert_eq!(tags.artist.as_deref(), Some("Bjork"));
+        assert_eq!(tags.year, Some(1997));
+        assert_eq!(tags.track, Some(3));
+        assert_eq!(tags.genre.as_deref(), Some("R&B"));
+    }
+
+    #[test]
+    fn test_untagged() {
+        assert!(read(vec![0xff, 0xfb, 0x90, 0x00]).is_empty());
+        assert!(read(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_genre() {
+        assert_eq!(resolve_genre("(17)"), "Rock");
+        assert_eq!(resolve_genre("17"), "Rock");
+        assert_eq!(resolve_genre("(17)Rock & Roll"), "Rock & Roll");
+        assert_eq!(resolve_genre("Shoegaze"), "Shoegaze");
+    }
+}
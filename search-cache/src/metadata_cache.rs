@@ -9,10 +9,12 @@ use std::{
 pub struct MetadataCache {
     ctime_index: BTreeMap<NonZeroU32, Vec<usize>>,
     mtime_index: BTreeMap<NonZeroU32, Vec<usize>>,
+    atime_index: BTreeMap<NonZeroU32, Vec<usize>>,
     size_index: BTreeMap<u64, Vec<usize>>,
     /// For slab nodes without metadata
     no_ctime_index: BTreeSet<usize>,
     no_mtime_index: BTreeSet<usize>,
+    no_atime_index: BTreeSet<usize>,
     no_size_index: BTreeSet<usize>,
 }
 
@@ -44,6 +46,17 @@ impl MetadataCache {
         } else {
             self.no_mtime_index.insert(index);
         }
+        if let Some(atime) = metadata.as_ref().and_then(|x| x.atime()) {
+            if let Some(indexes) = self.atime_index.get_mut(&atime) {
+                if !indexes.contains(&index) {
+                    indexes.push(index);
+                }
+            } else {
+                self.atime_index.insert(atime, vec![index]);
+            }
+        } else {
+            self.no_atime_index.insert(index);
+        }
         if let Some(size) = metadata.as_ref().map(|x| x.size()) {
             if let Some(indexes) = self.size_index.get_mut(&size) {
                 if !indexes.contains(&index) {
@@ -78,6 +91,16 @@ impl MetadataCache {
         } else {
             self.no_mtime_index.remove(&index);
         }
+        if let Some(atime) = metadata.as_ref().and_then(|x| x.atime()) {
+            if let Some(indexes) = self.atime_index.get_mut(&atime) {
+                indexes.retain(|&x| x != index);
+                if indexes.is_empty() {
+                    self.atime_index.remove(&atime);
+                }
+            }
+        } else {
+            self.no_atime_index.remove(&index);
+        }
         if let Some(size) = metadata.as_ref().map(|x| x.size()) {
             if let Some(indexes) = self.size_index.get_mut(&size) {
                 indexes.retain(|&x| x != index);
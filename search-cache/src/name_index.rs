@@ -1,5 +1,4 @@
 use crate::{FileNodes, NAME_POOL, SlabIndex};
-use itertools::Itertools;
 use search_cancel::CancellationToken;
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, time::Instant};
@@ -77,19 +76,52 @@ impl NameIndex {
     }
 
     pub fn all_indices(&self, cancellation_token: CancellationToken) -> Option<Vec<SlabIndex>> {
-        self.map
+        self.all_indices_where(cancellation_token, None, |_| true)
+    }
+
+    /// Like [`Self::all_indices`], but only keeps the indices `keep` accepts,
+    /// without collecting the rest first, and stops after `limit` of them.
+    pub fn all_indices_where(
+        &self,
+        cancellation_token: CancellationToken,
+        limit: Option<usize>,
+        mut keep: impl FnMut(SlabIndex) -> bool,
+    ) -> Option<Vec<SlabIndex>> {
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut kept = Vec::new();
+        let all = self
+            .map
             .values()
-            .flat_map(|indices| indices.iter().copied())
-            .enumerate()
-            .map(|(i, index)| {
-                if i % 0x10000 == 0 && cancellation_token.is_cancelled() {
-                    Err(())
-                } else {
-                    Ok(index)
-                }
-            })
-            .try_collect()
-            .ok()
+            .flat_map(|indices| indices.iter().copied());
+        for (i, index) in all.enumerate() {
+            if kept.len() >= limit {
+                break;
+            }
+            if i % 0x10000 == 0 && cancellation_token.is_cancelled() {
+                return None;
+            }
+            if keep(index) {
+                kept.push(index);
+            }
+        }
+        Some(kept)
+    }
+
+    /// Indices of every name shared by more than one node, grouped by name.
+    pub fn duplicate_indices(
+        &self,
+        cancellation_token: CancellationToken,
+    ) -> Option<Vec<SlabIndex>> {
+        let mut result = Vec::new();
+        for (i, indices) in self.map.values().enumerate() {
+            if i % 0x10000 == 0 && cancellation_token.is_cancelled() {
+                return None;
+            }
+            if indices.len() > 1 {
+                result.extend(indices.iter().copied());
+            }
+        }
+        Some(result)
     }
 
     pub fn get(&self, name: &str) -> Option<&SortedSlabIndices> {
@@ -0,0 +1,151 @@
+use crate::SlabIndex;
+use roaring::RoaringBitmap;
+use std::ops::{BitAndAssign, BitOrAssign, SubAssign};
+
+/// A set of slab indices stored as a compressed bitmap.
+///
+/// Boolean queries produce these instead of node lists: AND, OR and NOT are
+/// `&=`, `|=` and `-=` running on whole words, and membership is a bit test.
+/// The final set is put back in name index order once, when the search
+/// returns.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct NodeSet {
+    bitmap: RoaringBitmap,
+}
+
+impl NodeSet {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn from_nodes(nodes: &[SlabIndex]) -> Self {
+        let mut bits: Vec<u32> = nodes.iter().copied().map(to_bit).collect();
+        bits.sort_unstable();
+        bits.dedup();
+        Self {
+            bitmap: RoaringBitmap::from_sorted_iter(bits).expect("indices are sorted"),
+        }
+    }
+
+    /// Builds a set from indices that are already in ascending order, like the
+    /// slab's own iteration order.
+    pub(crate) fn from_sorted(indices: impl IntoIterator<Item = SlabIndex>) -> Self {
+        Self {
+            bitmap: RoaringBitmap::from_sorted_iter(indices.into_iter().map(to_bit))
+                .expect("indices are sorted"),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.bitmap.len() as usize
+    }
+
+    /// Members in slab index order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = SlabIndex> + '_ {
+        self.bitmap.iter().map(|bit| SlabIndex::new(bit as usize))
+    }
+
+    /// Members in slab index order, for the filters that walk a node list.
+    pub(crate) fn to_vec(&self) -> Vec<SlabIndex> {
+        self.iter().collect()
+    }
+
+    /// Adds `index`, returning whether it wasn't in the set yet.
+    pub(crate) fn insert(&mut self, index: SlabIndex) -> bool {
+        self.bitmap.insert(to_bit(index))
+    }
+
+    pub(crate) fn contains(&self, index: SlabIndex) -> bool {
+        self.bitmap.contains(to_bit(index))
+    }
+}
+
+impl BitAndAssign<&NodeSet> for NodeSet {
+    fn bitand_assign(&mut self, other: &NodeSet) {
+        self.bitmap &= &other.bitmap;
+    }
+}
+
+impl BitOrAssign<&NodeSet> for NodeSet {
+    fn bitor_assign(&mut self, other: &NodeSet) {
+        self.bitmap |= &other.bitmap;
+    }
+}
+
+impl SubAssign<&NodeSet> for NodeSet {
+    fn sub_assign(&mut self, other: &NodeSet) {
+        self.bitmap -= &other.bitmap;
+    }
+}
+
+fn to_bit(index: SlabIndex) -> u32 {
+    // `SlabIndex` is a `u32` underneath, so this never truncates.
+    index.get() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(indices: &[usize]) -> NodeSet {
+        NodeSet::from_nodes(
+            &indices
+                .iter()
+                .copied()
+                .map(SlabIndex::new)
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn members(set: &NodeSet, upto: usize) -> Vec<usize> {
+        (0..upto)
+            .filter(|&index| set.contains(SlabIndex::new(index)))
+            .collect()
+    }
+
+    #[test]
+    fn test_from_unsorted_nodes() {
+        let nodes = set(&[9, 3, 70000, 3, 0]);
+        assert_eq!(members(&nodes, 100_000), [0, 3, 9, 70000]);
+        assert_eq!(members(&NodeSet::new(), 100), []);
+    }
+
+    #[test]
+    fn test_intersection() {
+        let mut evens = set(&(0..20).step_by(2).collect::<Vec<_>>());
+        evens &= &set(&(0..20).step_by(3).collect::<Vec<_>>());
+        assert_eq!(members(&evens, 20), [0, 6, 12, 18]);
+        evens &= &NodeSet::new();
+        assert_eq!(members(&evens, 20), []);
+    }
+
+    #[test]
+    fn test_union_and_difference() {
+        let mut nodes = set(&[1, 4, 9]);
+        nodes |= &set(&[2, 4, 70000]);
+        assert_eq!(members(&nodes, 100_000), [1, 2, 4, 9, 70000]);
+        nodes -= &set(&[4, 5, 70000]);
+        assert_eq!(members(&nodes, 100_000), [1, 2, 9]);
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(
+            nodes.to_vec(),
+            [1, 2, 9].map(SlabIndex::new),
+            "members come back in slab order"
+        );
+    }
+
+    #[test]
+    fn test_from_sorted() {
+        let nodes = NodeSet::from_sorted([0, 3, 9].map(SlabIndex::new));
+        assert_eq!(nodes, set(&[9, 0, 3]));
+    }
+
+    #[test]
+    fn test_insert_reports_new_members() {
+        let mut nodes = set(&[1]);
+        assert!(nodes.insert(SlabIndex::new(5)));
+        assert!(!nodes.insert(SlabIndex::new(5)));
+        assert!(!nodes.insert(SlabIndex::new(1)));
+        assert_eq!(members(&nodes, 10), [1, 5]);
+    }
+}
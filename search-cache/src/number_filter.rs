@@ -0,0 +1,73 @@
+use anyhow::{Result, anyhow};
+use cardinal_syntax::{ArgumentKind, ComparisonOp, FilterArgument, RangeValue};
+use std::ops::{Bound, RangeBounds};
+
+/// Plain integer filter such as `year:>=2000`, `track:1..3`, `width:1920` or
+/// `bitdepth:8;16`. Lists match any of their values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct NumberPredicate {
+    ranges: Vec<(Bound<u32>, Bound<u32>)>,
+    negated: bool,
+}
+
+impl NumberPredicate {
+    /// `name` is the filter name used in error messages.
+    pub(crate) fn parse(name: &str, argument: &FilterArgument) -> Result<Self> {
+        Self::parse_with(argument, |raw| {
+            raw.trim()
+                .parse()
+                .map_err(|_| anyhow!("{name}: expects a number, got {raw:?}"))
+        })
+    }
+
+    /// Like [`Self::parse`], with `value` turning each operand into a number.
+    pub(crate) fn parse_with(
+        argument: &FilterArgument,
+        value: impl Fn(&str) -> Result<u32>,
+    ) -> Result<Self> {
+        let exact = |value| (Bound::Included(value), Bound::Included(value));
+        let mut negated = false;
+        let ranges = match &argument.kind {
+            ArgumentKind::Comparison(comparison) => {
+                let operand = value(&comparison.value)?;
+                vec![match comparison.op {
+                    ComparisonOp::Lt => (Bound::Unbounded, Bound::Excluded(operand)),
+                    ComparisonOp::Lte => (Bound::Unbounded, Bound::Included(operand)),
+                    ComparisonOp::Gt => (Bound::Excluded(operand), Bound::Unbounded),
+                    ComparisonOp::Gte => (Bound::Included(operand), Bound::Unbounded),
+                    ComparisonOp::Eq => exact(operand),
+                    ComparisonOp::Ne => {
+                        negated = true;
+                        exact(operand)
+                    }
+                }]
+            }
+            ArgumentKind::Range(RangeValue { start, end, .. }) => {
+                let start = match start {
+                    Some(start) => Bound::Included(value(start)?),
+                    None => Bound::Unbounded,
+                };
+                let end = match end {
+                    Some(end) => Bound::Included(value(end)?),
+                    None => Bound::Unbounded,
+                };
+                vec![(start, end)]
+            }
+            ArgumentKind::List(items) => items
+                .iter()
+                .map(|item| value(item).map(exact))
+                .collect::<Result<_>>()?,
+            ArgumentKind::Bare | ArgumentKind::Phrase => vec![exact(value(&argument.raw)?)],
+        };
+        Ok(Self { ranges, negated })
+    }
+
+    /// Whether the predicate came from a `!=` comparison.
+    pub(crate) fn is_negated(&self) -> bool {
+        self.negated
+    }
+
+    pub(crate) fn matches(&self, value: u32) -> bool {
+        self.ranges.iter().any(|range| range.contains(&value)) != self.negated
+    }
+}
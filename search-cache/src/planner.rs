@@ -0,0 +1,366 @@
+use crate::{
+    SearchCache, SearchOptions, SegmentKind, SegmentMatcher, SlabIndex, TermOverrides,
+    build_segment_matchers,
+    category::FileKind,
+    query::{CANCEL_CHECK_INTERVAL, filter_nodes, normalize_extensions},
+    regex_matcher,
+};
+use anyhow::{Result, anyhow};
+use cardinal_syntax::{Expr, Filter, FilterKind, Term};
+use fswalk::NodeFileType;
+use hashbrown::HashSet;
+use query_segmentation::query_segmentation;
+use search_cancel::CancellationToken;
+
+/// A conjunct that can be checked one node at a time from the slab alone.
+///
+/// `evaluate_and` produces candidates from the most selective conjunct and runs
+/// the others as predicates over them, so `foo ext:rs` only looks at the nodes
+/// named like `foo` instead of scanning the index once per filter.
+#[derive(Debug)]
+pub(crate) enum NodePredicate {
+    /// A word, phrase or regex matched against the file name.
+    Name(SegmentMatcher),
+    FileType(NodeFileType),
+    /// Lowercase extensions; only files match.
+    Extensions(HashSet<String>),
+    /// Direct children of the folder, `None` when the folder isn't indexed.
+    Parent(Option<SlabIndex>),
+    /// Everything below the folder, at any depth.
+    InFolder(FolderScope),
+    Not(Box<NodePredicate>),
+    All(Vec<NodePredicate>),
+    Any(Vec<NodePredicate>),
+}
+
+/// Where an `infolder:` path points relative to the watch root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FolderScope {
+    /// The path is above the watch root, so every node is inside it.
+    Everything,
+    Folder(SlabIndex),
+    /// The path isn't indexed.
+    Nothing,
+}
+
+impl NodePredicate {
+    /// Relative cost of checking a single node; cheap checks run first so the
+    /// expensive ones see fewer nodes.
+    pub(crate) fn cost(&self) -> u8 {
+        match self {
+            Self::FileType(_) => 0,
+            Self::Extensions(_) | Self::Parent(_) => 1,
+            Self::Name(SegmentMatcher::Plain { .. }) => 2,
+            Self::Name(SegmentMatcher::Folded { .. }) => 3,
+            Self::Name(SegmentMatcher::Wildcard { .. }) => 3,
+            Self::Name(
+                SegmentMatcher::Regex { .. }
+                | SegmentMatcher::Fuzzy { .. }
+                | SegmentMatcher::Acronym { .. },
+            ) => 4,
+            Self::InFolder(_) => 5,
+            Self::Not(inner) => inner.cost(),
+            Self::All(predicates) | Self::Any(predicates) => {
+                predicates.iter().map(Self::cost).max().unwrap_or_default()
+            }
+        }
+    }
+
+    /// How selective the predicate is when used to produce the candidates
+    /// itself, lower being better. `None` when that means scanning everything.
+    pub(crate) fn source_rank(&self) -> Option<u8> {
+        match self {
+            Self::Parent(_) | Self::InFolder(FolderScope::Nothing) => Some(0),
+            Self::Name(
+                SegmentMatcher::Plain {
+                    kind: SegmentKind::Exact,
+                    ..
+                }
+                | SegmentMatcher::Folded {
+                    kind: SegmentKind::Exact,
+                    ..
+                },
+            ) => Some(1),
+            Self::Name(SegmentMatcher::Plain { .. } | SegmentMatcher::Folded { .. }) => Some(2),
+            Self::InFolder(FolderScope::Folder(_)) => Some(3),
+            Self::Name(_) => Some(4),
+            // `file:foo` is as selective as its name.
+            Self::All(predicates) => predicates.iter().filter_map(Self::source_rank).min(),
+            _ => None,
+        }
+    }
+}
+
+/// Position of the predicate with the best [`NodePredicate::source_rank`].
+pub(crate) fn best_source(predicates: &[NodePredicate]) -> Option<usize> {
+    predicates
+        .iter()
+        .enumerate()
+        .filter_map(|(i, predicate)| predicate.source_rank().map(|rank| (rank, i)))
+        .min()
+        .map(|(_, i)| i)
+}
+
+impl SearchCache {
+    /// Turns `expr` into a [`NodePredicate`] when it only depends on names,
+    /// types, extensions and folders. Anything else, including arguments that
+    /// would fail to evaluate, returns `None` and is left to `evaluate_expr`.
+    pub(crate) fn compile_predicate(
+        &self,
+        expr: &Expr,
+        options: SearchOptions,
+        no_subfolders: bool,
+    ) -> Result<Option<NodePredicate>> {
+        Ok(match expr {
+            Expr::Term(Term::Word(text) | Term::Phrase(text)) => {
+                self.name_predicate(text, options)?
+            }
+            Expr::Term(Term::Regex(pattern)) => {
+                let matcher = regex_matcher(pattern, options)
+                    .map_err(|err| anyhow!("Invalid regex pattern: {err}"))?;
+                Some(NodePredicate::Name(matcher))
+            }
+            Expr::Term(Term::Filter(filter)) => {
+                self.filter_predicate(filter, options, no_subfolders)?
+            }
+            Expr::Not(inner) => self
+                .compile_predicate(inner, options, false)?
+                .map(|inner| NodePredicate::Not(Box::new(inner))),
+            Expr::And(parts) | Expr::Or(parts) => {
+                let mut predicates = Vec::with_capacity(parts.len());
+                for part in parts {
+                    let Some(predicate) = self.compile_predicate(part, options, false)? else {
+                        return Ok(None);
+                    };
+                    predicates.push(predicate);
+                }
+                Some(match expr {
+                    Expr::And(_) => NodePredicate::All(predicates),
+                    _ => NodePredicate::Any(predicates),
+                })
+            }
+            Expr::Empty => None,
+        })
+    }
+
+    /// Plain words match names, unless they span several path segments.
+    fn name_predicate(&self, text: &str, options: SearchOptions) -> Result<Option<NodePredicate>> {
+        let segments = query_segmentation(text);
+        if segments.len() != 1 {
+            return Ok(None);
+        }
+        let mut matchers = build_segment_matchers(&segments, options, TermOverrides::default())
+            .map_err(|err| anyhow!("Invalid regex pattern: {err}"))?;
+        Ok(matchers.pop().map(NodePredicate::Name))
+    }
+
+    fn filter_predicate(
+        &self,
+        filter: &Filter,
+        options: SearchOptions,
+        no_subfolders: bool,
+    ) -> Result<Option<NodePredicate>> {
+        let argument = filter.argument.as_ref();
+        let predicate = match &filter.kind {
+            FilterKind::File | FilterKind::Folder => {
+                let file_type = match filter.kind {
+                    FilterKind::File => NodeFileType::File,
+                    _ => NodeFileType::Dir,
+                };
+                NodePredicate::FileType(file_type)
+            }
+            FilterKind::Ext => {
+                let Some(argument) = argument else {
+                    return Ok(None);
+                };
+                let extensions = normalize_extensions(argument);
+                if extensions.is_empty() {
+                    return Ok(None);
+                }
+                return Ok(Some(NodePredicate::Extensions(extensions)));
+            }
+            FilterKind::Type => {
+                let Some(kind) =
+                    argument.and_then(|arg| self.file_categories.resolve_kind(&arg.raw))
+                else {
+                    return Ok(None);
+                };
+                return Ok(Some(match kind {
+                    FileKind::Node(file_type) => NodePredicate::FileType(file_type),
+                    FileKind::Extensions(extensions) => {
+                        NodePredicate::Extensions(extensions.into_iter().collect())
+                    }
+                }));
+            }
+            FilterKind::Audio | FilterKind::Video | FilterKind::Doc | FilterKind::Exe => {
+                let category = match filter.kind {
+                    FilterKind::Audio => "audio",
+                    FilterKind::Video => "video",
+                    FilterKind::Doc => "document",
+                    _ => "executable",
+                };
+                let Some(extensions) = self.file_categories.extensions(category) else {
+                    return Ok(None);
+                };
+                NodePredicate::Extensions(extensions.iter().cloned().collect())
+            }
+            FilterKind::Parent | FilterKind::NoSubfolders => {
+                let Some(argument) = argument else {
+                    return Ok(None);
+                };
+                return Ok(Some(NodePredicate::Parent(
+                    self.query_folder(&argument.raw),
+                )));
+            }
+            FilterKind::InFolder => {
+                let Some(argument) = argument else {
+                    return Ok(None);
+                };
+                return Ok(Some(if no_subfolders {
+                    NodePredicate::Parent(self.query_folder(&argument.raw))
+                } else {
+                    NodePredicate::InFolder(self.folder_scope(&argument.raw))
+                }));
+            }
+            _ => return Ok(None),
+        };
+        // `file:report` and `audio:live` also match the name.
+        let Some(argument) = argument else {
+            return Ok(Some(predicate));
+        };
+        Ok(self
+            .name_predicate(&argument.raw, options)?
+            .map(|name| NodePredicate::All(vec![name, predicate])))
+    }
+
+    /// The indexed folder a `parent:` or `infolder:` path points to.
+    pub(crate) fn query_folder(&self, raw: &str) -> Option<SlabIndex> {
+        self.node_index_for_raw_path(&self.resolve_query_path(raw))
+    }
+
+    pub(crate) fn folder_scope(&self, raw: &str) -> FolderScope {
+        let target = self.resolve_query_path(raw);
+        match self.node_index_for_raw_path(&target) {
+            Some(folder) => FolderScope::Folder(folder),
+            None if self.file_nodes.path().starts_with(&target) => FolderScope::Everything,
+            None => FolderScope::Nothing,
+        }
+    }
+
+    pub(crate) fn node_matches(&self, index: SlabIndex, predicate: &NodePredicate) -> bool {
+        match predicate {
+            NodePredicate::Name(matcher) => {
+                matcher.matches(self.file_nodes[index].name_and_parent.as_str())
+            }
+            NodePredicate::FileType(file_type) => {
+                self.file_nodes[index].metadata.file_type_hint() == *file_type
+            }
+            NodePredicate::Extensions(extensions) => {
+                self.file_has_extension(index, |ext| extensions.contains(ext))
+            }
+            NodePredicate::Parent(folder) => {
+                folder.is_some() && self.file_nodes[index].name_and_parent.parent() == *folder
+            }
+            NodePredicate::InFolder(scope) => match scope {
+                FolderScope::Everything => true,
+                FolderScope::Folder(folder) => self.is_below(index, *folder),
+                FolderScope::Nothing => false,
+            },
+            NodePredicate::Not(inner) => !self.node_matches(index, inner),
+            NodePredicate::All(predicates) => predicates
+                .iter()
+                .all(|predicate| self.node_matches(index, predicate)),
+            NodePredicate::Any(predicates) => predicates
+                .iter()
+                .any(|predicate| self.node_matches(index, predicate)),
+        }
+    }
+
+    /// Produces the nodes matching a predicate with a [`NodePredicate::source_rank`].
+    pub(crate) fn predicate_source(
+        &self,
+        predicate: &NodePredicate,
+        token: CancellationToken,
+    ) -> Result<Option<Vec<SlabIndex>>> {
+        match predicate {
+            NodePredicate::Name(matcher) => {
+                self.execute_matchers(std::slice::from_ref(matcher), token)
+            }
+            NodePredicate::Parent(folder) => Ok(Some(
+                folder
+                    .map(|folder| self.in_name_order(self.file_nodes[folder].children.to_vec()))
+                    .unwrap_or_default(),
+            )),
+            NodePredicate::InFolder(FolderScope::Folder(folder)) => Ok(self
+                .descendants(*folder, token)
+                .map(|nodes| self.in_name_order(nodes))),
+            NodePredicate::InFolder(FolderScope::Nothing) => Ok(Some(Vec::new())),
+            NodePredicate::All(predicates) => {
+                let Some(source) = best_source(predicates) else {
+                    return Ok(self.search_empty(token));
+                };
+                let Some(nodes) = self.predicate_source(&predicates[source], token)? else {
+                    return Ok(None);
+                };
+                Ok(filter_nodes(nodes, token, |index| {
+                    predicates
+                        .iter()
+                        .enumerate()
+                        .all(|(i, predicate)| i == source || self.node_matches(index, predicate))
+                }))
+            }
+            _ => Ok(self.search_empty(token)),
+        }
+    }
+
+    /// Every node below `folder`, walking the slab instead of the whole index.
+    pub(crate) fn descendants(
+        &self,
+        folder: SlabIndex,
+        token: CancellationToken,
+    ) -> Option<Vec<SlabIndex>> {
+        let mut nodes = Vec::new();
+        let mut stack = self.file_nodes[folder].children.to_vec();
+        while let Some(index) = stack.pop() {
+            if nodes.len() % CANCEL_CHECK_INTERVAL == 0 && token.is_cancelled() {
+                return None;
+            }
+            nodes.push(index);
+            stack.extend_from_slice(&self.file_nodes[index].children);
+        }
+        Some(nodes)
+    }
+
+    /// Puts nodes collected from the slab in the order of the name index, by
+    /// name and then path, which is how every other search returns them.
+    fn in_name_order(&self, mut nodes: Vec<SlabIndex>) -> Vec<SlabIndex> {
+        let name = |index: SlabIndex| self.file_nodes[index].name_and_parent.as_str();
+        nodes.sort_unstable_by_key(|&index| name(index));
+        for group in nodes.chunk_by_mut(|&a, &b| name(a) == name(b)) {
+            if group.len() < 2 {
+                continue;
+            }
+            let Some(indices) = self.name_index.get(name(group[0])) else {
+                continue;
+            };
+            // The name index already keeps nodes sharing a name in path order.
+            let members: HashSet<SlabIndex> = group.iter().copied().collect();
+            let ordered = indices.iter().filter(|&index| members.contains(index));
+            for (slot, &index) in group.iter_mut().zip(ordered) {
+                *slot = index;
+            }
+        }
+        nodes
+    }
+
+    fn is_below(&self, index: SlabIndex, folder: SlabIndex) -> bool {
+        let mut current = self.file_nodes[index].name_and_parent.parent();
+        while let Some(parent) = current {
+            if parent == folder {
+                return true;
+            }
+            current = self.file_nodes[parent].name_and_parent.parent();
+        }
+        false
+    }
+}
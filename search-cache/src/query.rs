@@ -1,148 +1,464 @@
 use crate::{
-    SearchCache, SearchOptions, SegmentKind, SegmentMatcher, SlabIndex, build_segment_matchers,
+    FilterProvider, NodeView, SearchCache, SearchOptions, SegmentKind, SegmentMatcher, SlabIndex,
+    SlabNodeMetadataCompact, TermOverrides,
+    attribute_filter::AttributePredicate,
+    build_segment_matchers,
     cache::NAME_POOL,
+    category::FileKind,
+    content_filter::{ContentMatcher, filter_by_content},
+    date_filter::DatePredicate,
+    fold::Folding,
+    image_filter::ImagePredicate,
+    node_set::NodeSet,
+    planner::{NodePredicate, best_source},
+    regex_matcher,
+    size_filter::SizePredicate,
+    tag_filter::TagPredicate,
+    whole_word_pattern,
 };
 use anyhow::{Result, anyhow, bail};
-use cardinal_syntax::{ArgumentKind, Expr, Filter, FilterArgument, FilterKind, Term};
+use cardinal_syntax::{ArgumentKind, Expr, Filter, FilterArgument, FilterKind, Term, parse_query};
 use fswalk::NodeFileType;
 use hashbrown::HashSet;
+use namepool::SearchHint;
 use query_segmentation::query_segmentation;
-use regex::RegexBuilder;
 use search_cancel::CancellationToken;
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     path::{Component, Path, PathBuf},
+    sync::Arc,
 };
 
-const CANCEL_CHECK_INTERVAL: usize = 0x10000;
+pub(crate) const CANCEL_CHECK_INTERVAL: usize = 0x10000;
+/// A result set at least `1 / DENSE_SET_RATIO` of the index is put in order by
+/// walking the index rather than by sorting it.
+const DENSE_SET_RATIO: usize = 16;
+const METADATA_CANCEL_CHECK_INTERVAL: usize = 0x100;
+
+#[cfg(test)]
+thread_local! {
+    /// Names the name pool had a matcher check, to tell index lookups from scans.
+    pub(crate) static NAMES_CHECKED: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// What a conjunction matched. Duplicate filters return their groups next to
+/// each other, an order that name index order would break up.
+enum Conjunction {
+    Set(NodeSet),
+    Grouped(Vec<SlabIndex>),
+}
+
+impl Conjunction {
+    fn into_set(self) -> NodeSet {
+        match self {
+            Conjunction::Set(nodes) => nodes,
+            Conjunction::Grouped(nodes) => NodeSet::from_nodes(&nodes),
+        }
+    }
+}
 
 impl SearchCache {
     pub(crate) fn evaluate_expr(
-        &self,
+        &mut self,
         expr: &Expr,
         options: SearchOptions,
         token: CancellationToken,
-    ) -> Result<Option<Vec<SlabIndex>>> {
+    ) -> Result<Option<NodeSet>> {
         match expr {
-            Expr::Empty => Ok(self.search_empty(token)),
-            Expr::Term(term) => self.evaluate_term(term, options, token),
+            Expr::Empty => Ok(Some(self.all_nodes())),
+            Expr::Term(term) => Ok(self
+                .evaluate_term(term, options, TermOverrides::default(), token)?
+                .map(|nodes| NodeSet::from_nodes(&nodes))),
             Expr::Not(inner) => self.evaluate_not(inner, None, options, token),
-            Expr::And(parts) => self.evaluate_and(parts, options, token),
+            Expr::And(parts) => Ok(self
+                .evaluate_and(parts, None, options, token)?
+                .map(Conjunction::into_set)),
             Expr::Or(parts) => self.evaluate_or(parts, options, token),
         }
     }
 
-    fn evaluate_and(
+    /// Evaluates a whole query into name index order, or into its groups for a
+    /// duplicate filter. With `stop_after`, evaluation may stop once that many
+    /// nodes matched; those are the first nodes the full evaluation would have
+    /// returned, in the same order.
+    pub(crate) fn evaluate_root(
+        &mut self,
+        expr: &Expr,
+        options: SearchOptions,
+        stop_after: Option<usize>,
+        token: CancellationToken,
+    ) -> Result<Option<Vec<SlabIndex>>> {
+        let nodes = match expr {
+            Expr::Empty => {
+                return Ok(self
+                    .name_index
+                    .all_indices_where(token, stop_after, |_| true));
+            }
+            Expr::Not(inner) => self
+                .evaluate_not(inner, None, options, token)?
+                .map(Conjunction::Set),
+            Expr::And(parts) => self.evaluate_and(parts, stop_after, options, token)?,
+            // A lone term is planned like a conjunction of one.
+            Expr::Term(_) => {
+                self.evaluate_and(std::slice::from_ref(expr), stop_after, options, token)?
+            }
+            Expr::Or(parts) => self
+                .evaluate_or(parts, options, token)?
+                .map(Conjunction::Set),
+        };
+        match nodes {
+            None => Ok(None),
+            Some(Conjunction::Set(nodes)) => Ok(self.ordered_nodes(&nodes, stop_after, token)),
+            Some(Conjunction::Grouped(mut nodes)) => {
+                if let Some(limit) = stop_after {
+                    nodes.truncate(limit);
+                }
+                Ok(Some(nodes))
+            }
+        }
+    }
+
+    /// Every node in the slab, which is every node in the name index.
+    fn all_nodes(&self) -> NodeSet {
+        NodeSet::from_sorted(self.file_nodes.iter().map(|(index, _)| index))
+    }
+
+    /// Lists `nodes` in name index order, stopping after `limit` of them.
+    ///
+    /// A set covering a good part of the index is read off the index with a
+    /// bit test per entry. A sparse one is sorted by name instead, and only
+    /// names shared by several members are looked up to put them in path order.
+    fn ordered_nodes(
         &self,
+        nodes: &NodeSet,
+        limit: Option<usize>,
+        token: CancellationToken,
+    ) -> Option<Vec<SlabIndex>> {
+        if nodes.len().saturating_mul(DENSE_SET_RATIO) >= self.file_nodes.len() {
+            return self
+                .name_index
+                .all_indices_where(token, limit, |index| nodes.contains(index));
+        }
+        let mut named: Vec<_> = nodes
+            .iter()
+            .map(|index| (self.file_nodes[index].name_and_parent.as_str(), index))
+            .collect();
+        if token.is_cancelled() {
+            return None;
+        }
+        named.sort_unstable_by_key(|&(name, _)| name);
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut ordered = Vec::with_capacity(named.len().min(limit));
+        for (i, group) in named.chunk_by(|a, b| a.0 == b.0).enumerate() {
+            if ordered.len() >= limit {
+                break;
+            }
+            if i % CANCEL_CHECK_INTERVAL == 0 && token.is_cancelled() {
+                return None;
+            }
+            match group {
+                [(_, index)] => ordered.push(*index),
+                [(name, _), ..] => {
+                    let indices = self
+                        .name_index
+                        .get(name)
+                        .expect("node name must be in the name index");
+                    ordered.extend(
+                        indices
+                            .iter()
+                            .copied()
+                            .filter(|&index| nodes.contains(index)),
+                    );
+                }
+                [] => unreachable!("chunks are never empty"),
+            }
+        }
+        ordered.truncate(limit);
+        Some(ordered)
+    }
+
+    /// Plans a conjunction instead of evaluating each part against the whole
+    /// index: parts that need their own lookup run first, then the most
+    /// selective [`NodePredicate`] produces candidates (or, without one, the
+    /// whole index), and every other part only checks those candidates.
+    ///
+    /// With `stop_after`, checking the predicates stops once that many nodes
+    /// passed, provided nothing runs after them and the candidates came from
+    /// the index.
+    fn evaluate_and(
+        &mut self,
         parts: &[Expr],
+        stop_after: Option<usize>,
         options: SearchOptions,
         token: CancellationToken,
-    ) -> Result<Option<Vec<SlabIndex>>> {
+    ) -> Result<Option<Conjunction>> {
         if parts.is_empty() {
-            return Ok(Some(Vec::new()));
+            return Ok(Some(Conjunction::Set(NodeSet::new())));
         }
-        let mut current: Option<Vec<SlabIndex>> = None;
+        // A bare `nosubfolders:` next to `infolder:` keeps the folder search shallow.
+        let no_subfolders = parts.iter().any(is_no_subfolders_modifier)
+            && parts.iter().any(|part| {
+                matches!(
+                    part,
+                    Expr::Term(Term::Filter(Filter {
+                        kind: FilterKind::InFolder,
+                        ..
+                    }))
+                )
+            });
+        // Parts that can't be checked node by node, like `a/b` or `child:`.
+        let mut sources = Vec::new();
+        let mut predicates = Vec::new();
+        // Negations that aren't predicates, removed after the predicates ran.
+        let mut exclusions = Vec::new();
+        // Metadata, tags, images and providers can hit the disk, so they only
+        // check what's left after the slab-only predicates.
+        let mut node_filters = Vec::new();
+        // Reading file contents is the most expensive check, so it only sees
+        // the candidates every other part agreed on.
+        let mut content_filters = Vec::new();
+        // Duplicates are only meaningful among the final candidates, so they run last.
+        let mut duplicate_filters = Vec::new();
         for part in parts {
             match part {
                 Expr::Empty => {}
-                Expr::Not(inner) => {
-                    current = self.evaluate_not(inner, current, options, token)?;
+                Expr::Term(Term::Filter(filter)) if matches!(filter.kind, FilterKind::Content) => {
+                    content_filters.push(filter);
                 }
-                _ => {
-                    let Some(nodes) = self.evaluate_expr(part, options, token)? else {
-                        return Ok(None);
-                    };
-                    current = Some(match current {
-                        Some(mut existing) => {
-                            if intersect_in_place(&mut existing, &nodes, token).is_none() {
-                                return Ok(None);
-                            }
-                            existing
-                        }
-                        None => nodes,
-                    });
+                Expr::Term(Term::Filter(filter)) if is_duplicate_filter(filter) => {
+                    duplicate_filters.push(filter);
+                }
+                part if no_subfolders && is_no_subfolders_modifier(part) => {}
+                Expr::Term(Term::Filter(filter))
+                    if is_metadata_filter(filter)
+                        || is_tag_filter(filter)
+                        || is_image_filter(filter)
+                        || self.provided_filter(filter).is_some() =>
+                {
+                    node_filters.push(filter);
                 }
+                part => match self.compile_predicate(part, options, no_subfolders)? {
+                    Some(predicate) => predicates.push(predicate),
+                    None => match part {
+                        Expr::Not(inner) => exclusions.push(&**inner),
+                        part => sources.push(part),
+                    },
+                },
+            }
+        }
+
+        // Parts evaluated on their own are intersected as bitmaps.
+        let mut current: Option<NodeSet> = None;
+        for part in sources {
+            let Some(nodes) = self.evaluate_expr(part, options, token)? else {
+                return Ok(None);
+            };
+            match &mut current {
+                Some(current) => *current &= &nodes,
+                None => current = Some(nodes),
+            }
+        }
+
+        predicates.sort_by_key(NodePredicate::cost);
+        let predicates_last = exclusions.is_empty()
+            && node_filters.is_empty()
+            && content_filters.is_empty()
+            && duplicate_filters.is_empty();
+        // Only candidates coming from the index are in index order, so only
+        // they can stop at the first `stop_after` matches.
+        let predicate_limit = stop_after.filter(|_| predicates_last && current.is_none());
+        if !predicates.is_empty() {
+            let candidates = match current.take() {
+                Some(nodes) => Some(nodes.to_vec()),
+                None => match best_source(&predicates) {
+                    Some(i) => {
+                        let predicate = predicates.remove(i);
+                        self.predicate_source(&predicate, token)?
+                    }
+                    // Every node is a candidate, so the predicates are checked
+                    // while walking the index instead of after collecting it.
+                    None => {
+                        let nodes =
+                            self.name_index
+                                .all_indices_where(token, predicate_limit, |index| {
+                                    predicates
+                                        .iter()
+                                        .all(|predicate| self.node_matches(index, predicate))
+                                });
+                        predicates.clear();
+                        nodes
+                    }
+                },
+            };
+            let Some(mut nodes) = candidates else {
+                return Ok(None);
+            };
+            if !predicates.is_empty() {
+                let Some(filtered) = filter_nodes_until(nodes, predicate_limit, token, |index| {
+                    predicates
+                        .iter()
+                        .all(|predicate| self.node_matches(index, predicate))
+                }) else {
+                    return Ok(None);
+                };
+                nodes = filtered;
             }
+            current = Some(NodeSet::from_nodes(&nodes));
         }
-        Ok(Some(current.unwrap_or_default()))
+        for inner in exclusions {
+            let Some(nodes) = self.evaluate_not(inner, current.take(), options, token)? else {
+                return Ok(None);
+            };
+            current = Some(nodes);
+        }
+        // The remaining filters walk node lists, in slab order since the
+        // result is put in index order at the end anyway.
+        node_filters.sort_by_key(|filter| node_filter_cost(filter));
+        let mut current = current.map(|nodes| nodes.to_vec());
+        for filter in node_filters {
+            let base = current.take();
+            let nodes = if is_metadata_filter(filter) {
+                self.evaluate_metadata_filter(filter, base, token)?
+            } else if is_tag_filter(filter) {
+                self.evaluate_tag_filter(filter, base, token)?
+            } else if is_image_filter(filter) {
+                self.evaluate_image_filter(filter, base, token)?
+            } else {
+                self.evaluate_provided_filter(filter, base, token)?
+            };
+            let Some(nodes) = nodes else {
+                return Ok(None);
+            };
+            current = Some(nodes);
+        }
+        for filter in content_filters {
+            let Some(nodes) =
+                self.evaluate_content_filter(filter, current.take(), options, token)?
+            else {
+                return Ok(None);
+            };
+            current = Some(nodes);
+        }
+        if duplicate_filters.is_empty() {
+            return Ok(Some(Conjunction::Set(
+                current
+                    .map(|nodes| NodeSet::from_nodes(&nodes))
+                    .unwrap_or_default(),
+            )));
+        }
+        // Groups list their members in index order, so the base is put in that
+        // order first; the grouped result then skips the final reordering.
+        if let Some(nodes) = current.take() {
+            let Some(nodes) = self.ordered_nodes(&NodeSet::from_nodes(&nodes), None, token) else {
+                return Ok(None);
+            };
+            current = Some(nodes);
+        }
+        for filter in duplicate_filters {
+            let Some(nodes) =
+                self.evaluate_duplicate_filter(filter, current.take(), options, token)?
+            else {
+                return Ok(None);
+            };
+            current = Some(nodes);
+        }
+        Ok(Some(Conjunction::Grouped(current.unwrap_or_default())))
     }
 
     fn evaluate_or(
-        &self,
+        &mut self,
         parts: &[Expr],
         options: SearchOptions,
         token: CancellationToken,
-    ) -> Result<Option<Vec<SlabIndex>>> {
-        if parts.is_empty() {
-            return Ok(Some(Vec::new()));
-        }
-        let mut result: Vec<SlabIndex> = Vec::new();
+    ) -> Result<Option<NodeSet>> {
+        let mut result = NodeSet::new();
         for part in parts {
-            let candidate = self.evaluate_expr(part, options, token)?;
-            let Some(nodes) = candidate else {
+            let Some(nodes) = self.evaluate_expr(part, options, token)? else {
                 return Ok(None);
             };
-            if union_in_place(&mut result, &nodes, token).is_none() {
-                return Ok(None);
-            }
+            result |= &nodes;
         }
         Ok(Some(result))
     }
 
+    /// Removes the nodes matching `inner` from `base`, or from the whole index
+    /// when there is no `base`.
     fn evaluate_not(
-        &self,
+        &mut self,
         inner: &Expr,
-        base: Option<Vec<SlabIndex>>,
+        base: Option<NodeSet>,
         options: SearchOptions,
         token: CancellationToken,
-    ) -> Result<Option<Vec<SlabIndex>>> {
-        let mut universe = if let Some(current) = base {
-            current
-        } else {
-            match self.search_empty(token) {
-                Some(nodes) => nodes,
-                None => return Ok(None),
-            }
-        };
-        if let Some(negated) = self.evaluate_expr(inner, options, token)? {
-            if difference_in_place(&mut universe, &negated, token).is_none() {
-                return Ok(None);
-            }
-        } else {
+    ) -> Result<Option<NodeSet>> {
+        let Some(negated) = self.evaluate_expr(inner, options, token)? else {
             return Ok(None);
-        }
-        Ok(Some(universe))
+        };
+        let mut nodes = base.unwrap_or_else(|| self.all_nodes());
+        nodes -= &negated;
+        Ok(Some(nodes))
     }
 
+    /// `overrides` come from the modifiers wrapping the term, like `case:` in
+    /// `case:README`.
     fn evaluate_term(
-        &self,
+        &mut self,
         term: &Term,
         options: SearchOptions,
+        overrides: TermOverrides,
         token: CancellationToken,
     ) -> Result<Option<Vec<SlabIndex>>> {
         match term {
-            Term::Word(text) | Term::Phrase(text) => self.evaluate_word(text, options, token),
-            Term::Regex(pattern) => self.evaluate_regex(pattern, options, token),
-            Term::Filter(filter) => self.evaluate_filter(filter, options, token),
+            Term::Word(text) | Term::Phrase(text) => {
+                self.evaluate_word(text, options, overrides, token)
+            }
+            Term::Regex(pattern) if overrides.whole_word => self.evaluate_regex(
+                &whole_word_pattern(pattern),
+                overrides.apply(options),
+                token,
+            ),
+            Term::Regex(pattern) => self.evaluate_regex(pattern, overrides.apply(options), token),
+            Term::Filter(filter) if is_term_modifier(filter) => {
+                self.evaluate_modifier(filter, options, overrides, token)
+            }
+            Term::Filter(filter) => self.evaluate_filter(filter, overrides.apply(options), token),
         }
     }
 
+    /// Evaluates the term wrapped by `case:`, `nocase:`, `wholeword:`/`ww:`,
+    /// `acronym:`/`ac:` or `nowholefilename:` with the modifier applied. Modifiers nest, and the
+    /// innermost one wins: `case:nocase:readme` ignores case.
+    fn evaluate_modifier(
+        &mut self,
+        filter: &Filter,
+        options: SearchOptions,
+        overrides: TermOverrides,
+        token: CancellationToken,
+    ) -> Result<Option<Vec<SlabIndex>>> {
+        let argument = filter
+            .argument
+            .as_ref()
+            .ok_or_else(|| anyhow!("{:?} modifier requires a term", filter.kind))?;
+        let (term, overrides) = modified_term(&filter.kind, argument, overrides);
+        self.evaluate_term(&term, options, overrides, token)
+    }
+
     fn evaluate_word(
         &self,
         text: &str,
         options: SearchOptions,
+        overrides: TermOverrides,
         token: CancellationToken,
     ) -> Result<Option<Vec<SlabIndex>>> {
         let segments = query_segmentation(text);
         if segments.is_empty() {
             bail!("Unprocessable term: {text:?}");
         }
-        let matchers = build_segment_matchers(&segments, options)
+        let matchers = build_segment_matchers(&segments, options, overrides)
             .map_err(|err| anyhow!("Invalid regex pattern: {err}"))?;
         self.execute_matchers(&matchers, token)
     }
 
-    fn execute_matchers(
+    pub(crate) fn execute_matchers(
         &self,
         matchers: &[SegmentMatcher],
         token: CancellationToken,
@@ -182,7 +498,25 @@ impl SearchCache {
                         SegmentKind::Suffix => NAME_POOL.search_suffix(needle, token),
                         SegmentKind::Exact => NAME_POOL.search_exact(needle, token),
                     },
-                    SegmentMatcher::Regex { regex } => NAME_POOL.search_regex(regex, token),
+                    _ => {
+                        // Regexes see names in NFC, which raw names of other
+                        // forms only match once normalized, so they go through
+                        // a predicate like the folded matchers.
+                        let hint = match matcher {
+                            SegmentMatcher::Folded { needle, .. } => SearchHint::Literal(needle),
+                            SegmentMatcher::Regex { regex, .. } => SearchHint::Regex(regex),
+                            _ => SearchHint::None,
+                        };
+                        NAME_POOL.search_with(
+                            hint,
+                            |name| {
+                                #[cfg(test)]
+                                NAMES_CHECKED.set(NAMES_CHECKED.get() + 1);
+                                matcher.matches(name)
+                            },
+                            token,
+                        )
+                    }
                 };
                 let Some(names) = names else {
                     return Ok(None);
@@ -208,17 +542,13 @@ impl SearchCache {
         options: SearchOptions,
         token: CancellationToken,
     ) -> Result<Option<Vec<SlabIndex>>> {
-        let mut builder = RegexBuilder::new(pattern);
-        builder.case_insensitive(options.case_insensitive);
-        let regex = builder
-            .build()
+        let matcher = regex_matcher(pattern, options)
             .map_err(|err| anyhow!("Invalid regex pattern: {err}"))?;
-        let matcher = SegmentMatcher::Regex { regex };
         self.execute_matchers(std::slice::from_ref(&matcher), token)
     }
 
     fn evaluate_filter(
-        &self,
+        &mut self,
         filter: &Filter,
         options: SearchOptions,
         token: CancellationToken,
@@ -243,6 +573,22 @@ impl SearchCache {
                     .ok_or_else(|| anyhow!("ext: requires at least one extension"))?;
                 self.evaluate_extension_filter(argument, token)
             }
+            FilterKind::Type => {
+                let argument = filter
+                    .argument
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("type: requires a file type"))?;
+                self.evaluate_kind_filter(argument, token)
+            }
+            FilterKind::Audio | FilterKind::Video | FilterKind::Doc | FilterKind::Exe => {
+                let category = match filter.kind {
+                    FilterKind::Audio => "audio",
+                    FilterKind::Video => "video",
+                    FilterKind::Doc => "document",
+                    _ => "executable",
+                };
+                self.evaluate_category_filter(category, filter.argument.as_ref(), options, token)
+            }
             FilterKind::Parent => {
                 let argument = filter
                     .argument
@@ -255,12 +601,253 @@ impl SearchCache {
                     .argument
                     .as_ref()
                     .ok_or_else(|| anyhow!("infolder: requires a folder path"))?;
-                self.evaluate_infolder_filter(argument, token)
+                self.evaluate_infolder_filter(argument, true, token)
+            }
+            FilterKind::NoSubfolders => {
+                let argument = filter.argument.as_ref().ok_or_else(|| {
+                    anyhow!("nosubfolders: requires a folder path or an infolder: filter")
+                })?;
+                self.evaluate_infolder_filter(argument, false, token)
+            }
+            FilterKind::Child => {
+                let argument = filter
+                    .argument
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("child: requires a file name"))?;
+                self.evaluate_child_filter(argument, options, token)
+            }
+            FilterKind::Size
+            | FilterKind::DateModified
+            | FilterKind::DateCreated
+            | FilterKind::DateAccessed
+            | FilterKind::Attribute => self.evaluate_metadata_filter(filter, None, token),
+            FilterKind::Duplicate
+            | FilterKind::NamePartDuplicate
+            | FilterKind::SizeDuplicate
+            | FilterKind::DateModifiedDuplicate
+            | FilterKind::AttributeDuplicate => {
+                self.evaluate_duplicate_filter(filter, None, options, token)
+            }
+            FilterKind::Artist
+            | FilterKind::Album
+            | FilterKind::Title
+            | FilterKind::Genre
+            | FilterKind::Year
+            | FilterKind::Track
+            | FilterKind::Comment => self.evaluate_tag_filter(filter, None, token),
+            FilterKind::Width
+            | FilterKind::Height
+            | FilterKind::Dimensions
+            | FilterKind::Orientation
+            | FilterKind::BitDepth => self.evaluate_image_filter(filter, None, token),
+            FilterKind::Content => self.evaluate_content_filter(filter, None, options, token),
+            FilterKind::Custom(_) if self.provided_filter(filter).is_some() => {
+                self.evaluate_provided_filter(filter, None, token)
             }
             _ => bail!("Filter {:?} is not supported yet", filter.kind),
         }
     }
 
+    /// Evaluates filters that need node metadata against `base`, or against every
+    /// node when there is no base. Metadata that hasn't been fetched yet is loaded lazily.
+    fn evaluate_metadata_filter(
+        &mut self,
+        filter: &Filter,
+        base: Option<Vec<SlabIndex>>,
+        token: CancellationToken,
+    ) -> Result<Option<Vec<SlabIndex>>> {
+        let predicate = MetadataPredicate::parse(filter)?;
+        let Some(nodes) = base.or_else(|| self.search_empty(token)) else {
+            return Ok(None);
+        };
+        Ok(filter_nodes_with_metadata(nodes, token, |index| {
+            let metadata = if predicate.needs_metadata() {
+                self.ensure_metadata(index)
+            } else {
+                self.file_nodes[index].metadata
+            };
+            predicate.matches(self.file_nodes[index].name_and_parent.as_str(), metadata)
+        }))
+    }
+
+    /// Evaluates music tag filters against the audio files of `base`, or of the
+    /// whole index. Tags are read from disk once per node and cached.
+    fn evaluate_tag_filter(
+        &mut self,
+        filter: &Filter,
+        base: Option<Vec<SlabIndex>>,
+        token: CancellationToken,
+    ) -> Result<Option<Vec<SlabIndex>>> {
+        let predicate = TagPredicate::parse(filter)?;
+        let audio = self
+            .file_categories
+            .extensions("audio")
+            .cloned()
+            .unwrap_or_default();
+        let Some(nodes) = base.or_else(|| self.search_empty(token)) else {
+            return Ok(None);
+        };
+        Ok(filter_nodes_with_metadata(nodes, token, |index| {
+            self.file_has_extension(index, |ext| audio.contains(ext))
+                && self
+                    .media_tags(index)
+                    .is_some_and(|tags| predicate.matches(tags))
+        }))
+    }
+
+    /// Evaluates image filters against the pictures of `base`, or of the whole
+    /// index. Only headers are read, once per node.
+    fn evaluate_image_filter(
+        &mut self,
+        filter: &Filter,
+        base: Option<Vec<SlabIndex>>,
+        token: CancellationToken,
+    ) -> Result<Option<Vec<SlabIndex>>> {
+        let predicate = ImagePredicate::parse(filter)?;
+        let pictures = self
+            .file_categories
+            .extensions("picture")
+            .cloned()
+            .unwrap_or_default();
+        let Some(nodes) = base.or_else(|| self.search_empty(token)) else {
+            return Ok(None);
+        };
+        Ok(filter_nodes_with_metadata(nodes, token, |index| {
+            self.file_has_extension(index, |ext| pictures.contains(ext))
+                && self
+                    .image_info(index)
+                    .is_some_and(|info| predicate.matches(&info))
+        }))
+    }
+
+    fn provided_filter(&self, filter: &Filter) -> Option<Arc<dyn FilterProvider>> {
+        match &filter.kind {
+            FilterKind::Custom(name) => self.filter_provider(name),
+            _ => None,
+        }
+    }
+
+    /// Hands `base`, or the whole index, to the provider registered for the filter.
+    fn evaluate_provided_filter(
+        &self,
+        filter: &Filter,
+        base: Option<Vec<SlabIndex>>,
+        token: CancellationToken,
+    ) -> Result<Option<Vec<SlabIndex>>> {
+        let provider = self
+            .provided_filter(filter)
+            .ok_or_else(|| anyhow!("No provider for filter {:?}", filter.kind))?;
+        let Some(nodes) = base.or_else(|| self.search_empty(token)) else {
+            return Ok(None);
+        };
+        // Providers are outside code: whatever they return beyond the
+        // candidates, stale or out of range indices included, is dropped.
+        let candidates = NodeSet::from_nodes(&nodes);
+        let Some(matched) =
+            provider.filter(filter, nodes, NodeView::new(&self.file_nodes), token)?
+        else {
+            return Ok(None);
+        };
+        Ok(filter_nodes(matched, token, |index| {
+            candidates.contains(index)
+        }))
+    }
+
+    /// Keeps the files of `base`, or of the whole index, whose contents match.
+    fn evaluate_content_filter(
+        &self,
+        filter: &Filter,
+        base: Option<Vec<SlabIndex>>,
+        options: SearchOptions,
+        token: CancellationToken,
+    ) -> Result<Option<Vec<SlabIndex>>> {
+        let argument = filter
+            .argument
+            .as_ref()
+            .ok_or_else(|| anyhow!("content: requires text to look for"))?;
+        let matcher = ContentMatcher::new(argument, options)?;
+        let Some(nodes) = base.or_else(|| self.search_empty(token)) else {
+            return Ok(None);
+        };
+        let mut candidates = Vec::with_capacity(nodes.len());
+        for (i, index) in nodes.into_iter().enumerate() {
+            if i % CANCEL_CHECK_INTERVAL == 0 && token.is_cancelled() {
+                return Ok(None);
+            }
+            if self.file_nodes[index].metadata.file_type_hint() != NodeFileType::File {
+                continue;
+            }
+            if let Some(path) = self.node_path(index) {
+                candidates.push((index, path));
+            }
+        }
+        filter_by_content(candidates, &matcher, self.content_search, token)
+    }
+
+    /// Keeps the nodes of `base` (or of the whole index) sharing a name, stem,
+    /// size, modification date or attributes with another one. Members of a
+    /// group are returned next to each other, in the order of `base`.
+    fn evaluate_duplicate_filter(
+        &mut self,
+        filter: &Filter,
+        base: Option<Vec<SlabIndex>>,
+        options: SearchOptions,
+        token: CancellationToken,
+    ) -> Result<Option<Vec<SlabIndex>>> {
+        // `dupe:report` only looks for duplicates among names matching `report`.
+        let base = match &filter.argument {
+            Some(argument) => {
+                let Some(named) =
+                    self.evaluate_word(&argument.raw, options, TermOverrides::default(), token)?
+                else {
+                    return Ok(None);
+                };
+                match base {
+                    Some(existing) => {
+                        let named = NodeSet::from_nodes(&named);
+                        let Some(existing) =
+                            filter_nodes(existing, token, |index| named.contains(index))
+                        else {
+                            return Ok(None);
+                        };
+                        Some(existing)
+                    }
+                    None => Some(named),
+                }
+            }
+            None => base,
+        };
+        if base.is_none() && matches!(filter.kind, FilterKind::Duplicate) {
+            return Ok(self.name_index.duplicate_indices(token));
+        }
+        let Some(nodes) = base.or_else(|| self.search_empty(token)) else {
+            return Ok(None);
+        };
+        Ok(match filter.kind {
+            FilterKind::Duplicate => group_duplicates(nodes, token, |index| {
+                Some(self.file_nodes[index].name_and_parent.as_str())
+            }),
+            FilterKind::NamePartDuplicate => group_duplicates(nodes, token, |index| {
+                Some(name_stem(self.file_nodes[index].name_and_parent.as_str()))
+            }),
+            FilterKind::SizeDuplicate => group_duplicates(nodes, token, |index| {
+                // Folder sizes aren't tracked, so folders never count as duplicates.
+                let metadata = self.ensure_metadata(index);
+                let metadata = metadata.as_ref()?;
+                (metadata.r#type() != NodeFileType::Dir).then(|| metadata.size())
+            }),
+            FilterKind::DateModifiedDuplicate => group_duplicates(nodes, token, |index| {
+                let metadata = self.ensure_metadata(index);
+                metadata.as_ref()?.mtime().map(|mtime| mtime.get())
+            }),
+            FilterKind::AttributeDuplicate => group_duplicates(nodes, token, |index| {
+                let metadata = self.ensure_metadata(index);
+                metadata.as_ref().map(|metadata| metadata.mode())
+            }),
+            _ => bail!("Filter {:?} doesn't look for duplicates", filter.kind),
+        })
+    }
+
     fn evaluate_type_filter(
         &self,
         file_type: NodeFileType,
@@ -269,7 +856,7 @@ impl SearchCache {
         token: CancellationToken,
     ) -> Result<Option<Vec<SlabIndex>>> {
         let base = if let Some(arg) = argument {
-            self.evaluate_word(&arg.raw, options, token)?
+            self.evaluate_word(&arg.raw, options, TermOverrides::default(), token)?
         } else {
             self.search_empty(token)
         };
@@ -281,6 +868,65 @@ impl SearchCache {
         }))
     }
 
+    fn evaluate_kind_filter(
+        &self,
+        argument: &FilterArgument,
+        token: CancellationToken,
+    ) -> Result<Option<Vec<SlabIndex>>> {
+        let kind = self
+            .file_categories
+            .resolve_kind(&argument.raw)
+            .ok_or_else(|| anyhow!("Unknown file type: {:?}", argument.raw))?;
+        let Some(nodes) = self.search_empty(token) else {
+            return Ok(None);
+        };
+        Ok(match kind {
+            FileKind::Node(file_type) => filter_nodes(nodes, token, |index| {
+                self.file_nodes[index].metadata.file_type_hint() == file_type
+            }),
+            FileKind::Extensions(extensions) => filter_nodes(nodes, token, |index| {
+                self.file_has_extension(index, |ext| extensions.contains(ext))
+            }),
+        })
+    }
+
+    fn evaluate_category_filter(
+        &self,
+        category: &str,
+        argument: Option<&FilterArgument>,
+        options: SearchOptions,
+        token: CancellationToken,
+    ) -> Result<Option<Vec<SlabIndex>>> {
+        let Some(extensions) = self.file_categories.extensions(category) else {
+            bail!("No file category named {category:?}");
+        };
+        let base = if let Some(arg) = argument {
+            self.evaluate_word(&arg.raw, options, TermOverrides::default(), token)?
+        } else {
+            self.search_empty(token)
+        };
+        let Some(nodes) = base else {
+            return Ok(None);
+        };
+        Ok(filter_nodes(nodes, token, |index| {
+            self.file_has_extension(index, |ext| extensions.contains(ext))
+        }))
+    }
+
+    pub(crate) fn file_has_extension(
+        &self,
+        index: SlabIndex,
+        accept: impl Fn(&str) -> bool,
+    ) -> bool {
+        let node = &self.file_nodes[index];
+        if node.metadata.file_type_hint() != NodeFileType::File {
+            return false;
+        }
+        extension_of(node.name_and_parent.as_str())
+            .map(|ext| accept(&ext))
+            .unwrap_or(false)
+    }
+
     fn evaluate_extension_filter(
         &self,
         argument: &FilterArgument,
@@ -294,13 +940,7 @@ impl SearchCache {
             return Ok(None);
         };
         Ok(filter_nodes(nodes, token, |index| {
-            let node = &self.file_nodes[index];
-            if node.metadata.file_type_hint() != NodeFileType::File {
-                return false;
-            }
-            extension_of(node.name_and_parent.as_str())
-                .map(|ext| extensions.contains(ext.as_str()))
-                .unwrap_or(false)
+            self.file_has_extension(index, |ext| extensions.contains(ext))
         }))
     }
 
@@ -309,41 +949,60 @@ impl SearchCache {
         argument: &FilterArgument,
         token: CancellationToken,
     ) -> Result<Option<Vec<SlabIndex>>> {
-        let target = self.resolve_query_path(&argument.raw);
-        let Some(nodes) = self.search_empty(token) else {
-            return Ok(None);
-        };
-        Ok(filter_nodes(nodes, token, |index| {
-            self.node_parent_path(index)
-                .map(|parent| parent == target)
-                .unwrap_or(false)
-        }))
+        let predicate = NodePredicate::Parent(self.query_folder(&argument.raw));
+        self.predicate_source(&predicate, token)
     }
 
+    /// Nodes below the folder named by `argument`, collected by walking the
+    /// slab down from it. Without `subfolders` only its direct children are
+    /// returned.
     fn evaluate_infolder_filter(
         &self,
         argument: &FilterArgument,
+        subfolders: bool,
         token: CancellationToken,
     ) -> Result<Option<Vec<SlabIndex>>> {
-        let target = self.resolve_query_path(&argument.raw);
-        let Some(nodes) = self.search_empty(token) else {
-            return Ok(None);
+        let predicate = if subfolders {
+            NodePredicate::InFolder(self.folder_scope(&argument.raw))
+        } else {
+            NodePredicate::Parent(self.query_folder(&argument.raw))
         };
-        Ok(filter_nodes(nodes, token, |index| {
-            self.file_nodes
-                .node_path(index)
-                .is_some_and(|path| path.starts_with(&target) && path != target)
-        }))
+        self.predicate_source(&predicate, token)
     }
 
-    fn node_parent_path(&self, index: SlabIndex) -> Option<PathBuf> {
-        let parent = self.file_nodes[index].name_and_parent.parent()?;
-        self.file_nodes.node_path(parent)
+    /// Folders with a direct child matching `argument`, found by walking up from
+    /// the matching children. `child:regex:<pattern>` matches children by regex.
+    fn evaluate_child_filter(
+        &self,
+        argument: &FilterArgument,
+        options: SearchOptions,
+        token: CancellationToken,
+    ) -> Result<Option<Vec<SlabIndex>>> {
+        let children = match argument.raw.strip_prefix("regex:") {
+            Some(pattern) => self.evaluate_regex(pattern, options, token)?,
+            None => self.evaluate_word(&argument.raw, options, TermOverrides::default(), token)?,
+        };
+        let Some(children) = children else {
+            return Ok(None);
+        };
+        let mut seen = NodeSet::new();
+        let mut parents = Vec::new();
+        for (i, child) in children.into_iter().enumerate() {
+            if i % CANCEL_CHECK_INTERVAL == 0 && token.is_cancelled() {
+                return Ok(None);
+            }
+            if let Some(parent) = self.file_nodes[child].name_and_parent.parent() {
+                if seen.insert(parent) {
+                    parents.push(parent);
+                }
+            }
+        }
+        Ok(Some(parents))
     }
 
-    fn resolve_query_path(&self, raw: &str) -> PathBuf {
+    pub(crate) fn resolve_query_path(&self, raw: &str) -> PathBuf {
         let trimmed = raw.trim();
-        let candidate = PathBuf::from(trimmed);
+        let candidate = expand_home(trimmed);
         let joined = if candidate.is_absolute() {
             candidate
         } else {
@@ -353,7 +1012,20 @@ impl SearchCache {
     }
 }
 
-fn normalize_extensions(argument: &FilterArgument) -> HashSet<String> {
+/// Expands a leading `~` to the home directory so macros like
+/// `infolder:~/work` stay portable.
+fn expand_home(path: &str) -> PathBuf {
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest.trim_start_matches('/'),
+        _ => return PathBuf::from(path),
+    };
+    match std::env::var_os("HOME") {
+        Some(home) => PathBuf::from(home).join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+pub(crate) fn normalize_extensions(argument: &FilterArgument) -> HashSet<String> {
     let mut values = HashSet::new();
     match &argument.kind {
         ArgumentKind::List(list) => {
@@ -377,7 +1049,7 @@ fn normalize_extension(raw: &str) -> Option<String> {
     if trimmed.is_empty() {
         None
     } else {
-        Some(trimmed.to_ascii_lowercase())
+        Some(Folding::CASE.apply(trimmed).into_owned())
     }
 }
 
@@ -386,16 +1058,30 @@ fn extension_of(name: &str) -> Option<String> {
     if pos + 1 >= name.len() {
         return None;
     }
-    Some(name[pos + 1..].to_ascii_lowercase())
+    Some(Folding::CASE.apply(&name[pos + 1..]).into_owned())
 }
 
-fn filter_nodes(
+pub(crate) fn filter_nodes(
     nodes: Vec<SlabIndex>,
     token: CancellationToken,
+    predicate: impl FnMut(SlabIndex) -> bool,
+) -> Option<Vec<SlabIndex>> {
+    filter_nodes_until(nodes, None, token, predicate)
+}
+
+/// Like [`filter_nodes`], but stops once `limit` nodes passed.
+fn filter_nodes_until(
+    nodes: Vec<SlabIndex>,
+    limit: Option<usize>,
+    token: CancellationToken,
     mut predicate: impl FnMut(SlabIndex) -> bool,
 ) -> Option<Vec<SlabIndex>> {
-    let mut filtered = Vec::with_capacity(nodes.len());
+    let limit = limit.unwrap_or(usize::MAX);
+    let mut filtered = Vec::with_capacity(nodes.len().min(limit));
     for (i, index) in nodes.into_iter().enumerate() {
+        if filtered.len() >= limit {
+            break;
+        }
         if i % CANCEL_CHECK_INTERVAL == 0 && token.is_cancelled() {
             return None;
         }
@@ -406,68 +1092,232 @@ fn filter_nodes(
     Some(filtered)
 }
 
-fn intersect_in_place(
-    values: &mut Vec<SlabIndex>,
-    rhs: &[SlabIndex],
+/// Like [`filter_nodes`], but checks for cancellation more often since the
+/// predicate may hit the disk.
+fn filter_nodes_with_metadata(
+    nodes: Vec<SlabIndex>,
     token: CancellationToken,
-) -> Option<()> {
-    if values.is_empty() {
-        return Some(());
-    }
-    let rhs_set: HashSet<SlabIndex> = rhs.iter().copied().collect();
-    let mut filtered = Vec::with_capacity(values.len().min(rhs.len()));
-    for (i, index) in values.iter().copied().enumerate() {
-        if i % CANCEL_CHECK_INTERVAL == 0 && token.is_cancelled() {
+    mut predicate: impl FnMut(SlabIndex) -> bool,
+) -> Option<Vec<SlabIndex>> {
+    let mut filtered = Vec::with_capacity(nodes.len());
+    for (i, index) in nodes.into_iter().enumerate() {
+        if i % METADATA_CANCEL_CHECK_INTERVAL == 0 && token.is_cancelled() {
             return None;
         }
-        if rhs_set.contains(&index) {
+        if predicate(index) {
             filtered.push(index);
         }
     }
-    *values = filtered;
-    Some(())
+    Some(filtered)
+}
+
+fn is_no_subfolders_modifier(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Term(Term::Filter(Filter {
+            kind: FilterKind::NoSubfolders,
+            argument: None,
+        }))
+    )
 }
 
-fn difference_in_place(
-    values: &mut Vec<SlabIndex>,
-    rhs: &[SlabIndex],
+/// Groups `nodes` by `key` and keeps the groups with more than one member,
+/// ordered by key. Nodes without a key are dropped.
+fn group_duplicates<K: Ord>(
+    nodes: Vec<SlabIndex>,
     token: CancellationToken,
-) -> Option<()> {
-    if values.is_empty() || rhs.is_empty() {
-        return Some(());
-    }
-    let rhs_set: HashSet<SlabIndex> = rhs.iter().copied().collect();
-    let mut filtered = Vec::with_capacity(values.len());
-    for (i, index) in values.iter().copied().enumerate() {
-        if i % CANCEL_CHECK_INTERVAL == 0 && token.is_cancelled() {
+    mut key: impl FnMut(SlabIndex) -> Option<K>,
+) -> Option<Vec<SlabIndex>> {
+    let mut groups: BTreeMap<K, Vec<SlabIndex>> = BTreeMap::new();
+    for (i, index) in nodes.into_iter().enumerate() {
+        if i % METADATA_CANCEL_CHECK_INTERVAL == 0 && token.is_cancelled() {
             return None;
         }
-        if !rhs_set.contains(&index) {
-            filtered.push(index);
+        if let Some(key) = key(index) {
+            groups.entry(key).or_default().push(index);
         }
     }
-    *values = filtered;
-    Some(())
+    Some(
+        groups
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .flatten()
+            .collect(),
+    )
 }
 
-fn union_in_place(
-    values: &mut Vec<SlabIndex>,
-    rhs: &[SlabIndex],
-    token: CancellationToken,
-) -> Option<()> {
-    if rhs.is_empty() {
-        return Some(());
+/// File name without its last extension; dot files keep their full name.
+fn name_stem(name: &str) -> &str {
+    match name.rfind('.') {
+        Some(pos) if pos > 0 => &name[..pos],
+        _ => name,
     }
-    let mut seen: HashSet<SlabIndex> = values.iter().copied().collect();
-    for (i, index) in rhs.iter().copied().enumerate() {
-        if i % CANCEL_CHECK_INTERVAL == 0 && token.is_cancelled() {
-            return None;
+}
+
+fn is_duplicate_filter(filter: &Filter) -> bool {
+    matches!(
+        filter.kind,
+        FilterKind::Duplicate
+            | FilterKind::NamePartDuplicate
+            | FilterKind::SizeDuplicate
+            | FilterKind::DateModifiedDuplicate
+            | FilterKind::AttributeDuplicate
+    )
+}
+
+/// The term a modifier wraps, with the modifier added to `overrides`.
+pub(crate) fn modified_term(
+    kind: &FilterKind,
+    argument: &FilterArgument,
+    mut overrides: TermOverrides,
+) -> (Term, TermOverrides) {
+    match kind {
+        FilterKind::CaseSensitive => overrides.case_insensitive = Some(false),
+        FilterKind::NoCase => overrides.case_insensitive = Some(true),
+        FilterKind::WholeWord => overrides.whole_word = true,
+        FilterKind::Acronym => overrides.acronym = true,
+        // Only wildcards are required to match the whole name.
+        FilterKind::NoWholeFilename => overrides.partial_wildcards = true,
+        _ => {}
+    }
+    let term = match argument.kind {
+        ArgumentKind::Phrase => Term::Phrase(argument.raw.clone()),
+        _ => match parse_query(&argument.raw).map(|query| query.expr) {
+            Ok(Expr::Term(term)) => term,
+            _ => Term::Word(argument.raw.clone()),
+        },
+    };
+    (term, overrides)
+}
+
+pub(crate) fn is_term_modifier(filter: &Filter) -> bool {
+    matches!(
+        filter.kind,
+        FilterKind::CaseSensitive
+            | FilterKind::NoCase
+            | FilterKind::WholeWord
+            | FilterKind::Acronym
+            | FilterKind::NoWholeFilename
+    )
+}
+
+fn is_tag_filter(filter: &Filter) -> bool {
+    matches!(
+        filter.kind,
+        FilterKind::Artist
+            | FilterKind::Album
+            | FilterKind::Title
+            | FilterKind::Genre
+            | FilterKind::Year
+            | FilterKind::Track
+            | FilterKind::Comment
+    )
+}
+
+fn is_image_filter(filter: &Filter) -> bool {
+    matches!(
+        filter.kind,
+        FilterKind::Width
+            | FilterKind::Height
+            | FilterKind::Dimensions
+            | FilterKind::Orientation
+            | FilterKind::BitDepth
+    )
+}
+
+/// Order in which `evaluate_and` runs filters that may read from disk.
+fn node_filter_cost(filter: &Filter) -> u8 {
+    if is_metadata_filter(filter) {
+        0
+    } else if is_image_filter(filter) {
+        1
+    } else if is_tag_filter(filter) {
+        2
+    } else {
+        3
+    }
+}
+
+fn is_metadata_filter(filter: &Filter) -> bool {
+    matches!(
+        filter.kind,
+        FilterKind::Size
+            | FilterKind::DateModified
+            | FilterKind::DateCreated
+            | FilterKind::DateAccessed
+            | FilterKind::Attribute
+    )
+}
+
+enum MetadataPredicate {
+    Size(SizePredicate),
+    DateModified(DatePredicate),
+    DateCreated(DatePredicate),
+    DateAccessed(DatePredicate),
+    Attribute(AttributePredicate),
+}
+
+impl MetadataPredicate {
+    fn parse(filter: &Filter) -> Result<Self> {
+        let argument = filter.argument.as_ref();
+        match filter.kind {
+            FilterKind::Size => {
+                let argument = argument.ok_or_else(|| anyhow!("size: requires a size"))?;
+                Ok(Self::Size(SizePredicate::parse(argument)?))
+            }
+            FilterKind::DateModified => {
+                let argument = argument.ok_or_else(|| anyhow!("dm: requires a date"))?;
+                Ok(Self::DateModified(DatePredicate::parse(argument)?))
+            }
+            FilterKind::DateCreated => {
+                let argument = argument.ok_or_else(|| anyhow!("dc: requires a date"))?;
+                Ok(Self::DateCreated(DatePredicate::parse(argument)?))
+            }
+            FilterKind::DateAccessed => {
+                let argument = argument.ok_or_else(|| anyhow!("da: requires a date"))?;
+                Ok(Self::DateAccessed(DatePredicate::parse(argument)?))
+            }
+            FilterKind::Attribute => {
+                let argument = argument.ok_or_else(|| anyhow!("attrib: requires attributes"))?;
+                Ok(Self::Attribute(AttributePredicate::parse(argument)?))
+            }
+            _ => bail!("Filter {:?} doesn't rely on metadata", filter.kind),
+        }
+    }
+
+    /// `attrib:H` only looks at the name, so there's no need to hit the disk.
+    fn needs_metadata(&self) -> bool {
+        match self {
+            Self::Attribute(predicate) => predicate.needs_mode(),
+            _ => true,
         }
-        if seen.insert(index) {
-            values.push(index);
+    }
+
+    fn matches(&self, name: &str, metadata: SlabNodeMetadataCompact) -> bool {
+        let Some(metadata) = metadata.as_ref() else {
+            // Hidden files can still be recognised by their name alone.
+            return matches!(
+                self,
+                Self::Attribute(predicate) if !predicate.needs_mode() && predicate.matches(name, 0)
+            );
+        };
+        match self {
+            // Folder sizes aren't tracked, so they never satisfy a size filter.
+            Self::Size(predicate) => {
+                metadata.r#type() != NodeFileType::Dir && predicate.matches(metadata.size())
+            }
+            Self::DateModified(predicate) => metadata
+                .mtime()
+                .is_some_and(|mtime| predicate.matches(mtime.get().into())),
+            Self::DateCreated(predicate) => metadata
+                .ctime()
+                .is_some_and(|ctime| predicate.matches(ctime.get().into())),
+            Self::DateAccessed(predicate) => metadata
+                .atime()
+                .is_some_and(|atime| predicate.matches(atime.get().into())),
+            Self::Attribute(predicate) => predicate.matches(name, metadata.mode()),
         }
     }
-    Some(())
 }
 
 fn normalize_path(path: &Path) -> PathBuf {
@@ -0,0 +1,264 @@
+use crate::{
+    ResultDetails, SearchCache, SegmentKind, SlabIndex, fold::Folding, segment::segment_value,
+    wildcard::has_wildcards,
+};
+use cardinal_syntax::{ArgumentKind, Expr, FilterKind, Term};
+use fswalk::NodeFileType;
+use query_segmentation::query_segmentation;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Every score starts here so the penalties can't underflow.
+const BASE_SCORE: u32 = 10_000;
+const COVERAGE_POINTS: u32 = 200;
+const DEPTH_PENALTY: u32 = 15;
+const DIRECTORY_POINTS: u32 = 50;
+const RECENCY_POINTS: u32 = 100;
+/// A file modified this many days ago gets half of [`RECENCY_POINTS`].
+const RECENCY_HALF_LIFE_DAYS: u64 = 7;
+
+/// Scores nodes by how well their name matches the words of a query, for
+/// [`SortKey::Relevance`](crate::SortKey::Relevance).
+///
+/// The score adds up, from most to least important:
+/// - the best kind of match of each word: the whole name (or the name without
+///   its extension), its start, the start of a word inside it, anywhere;
+/// - how much of the name the words cover;
+/// - a bonus for folders and for recent modification times, if already known;
+/// - minus a penalty for every folder between the node and the root,
+///
+/// so `cargo` ranks `~/src/app/Cargo.toml` above the `cargo-foo-1.2.3`
+/// crates deep inside `~/.cargo/registry`.
+#[derive(Debug, Clone)]
+pub(crate) struct RelevanceScorer {
+    /// Case folded words the name is expected to contain.
+    words: Vec<String>,
+    now: u64,
+}
+
+impl RelevanceScorer {
+    /// Collects the words of `expr` that describe names, skipping negated
+    /// parts, regexes and wildcards.
+    pub(crate) fn new(expr: &Expr) -> Self {
+        let words = name_terms(expr).into_iter().filter_map(name_word).collect();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self { words, now }
+    }
+
+    fn score(&self, name: &str, depth: usize, is_dir: bool, mtime: Option<u64>) -> u32 {
+        let name = Folding::CASE.apply(name);
+        let mut score = BASE_SCORE;
+        let mut covered = 0;
+        for word in &self.words {
+            if let Some(kind) = match_kind(&name, word) {
+                score += match kind {
+                    MatchKind::Segment(SegmentKind::Exact) => 400,
+                    MatchKind::Segment(SegmentKind::Prefix) => 300,
+                    MatchKind::WordStart => 200,
+                    MatchKind::Segment(SegmentKind::Suffix | SegmentKind::Substr) => 100,
+                };
+                covered += word.len();
+            }
+        }
+        if !name.is_empty() {
+            score += COVERAGE_POINTS * covered.min(name.len()) as u32 / name.len() as u32;
+        }
+        if is_dir {
+            score += DIRECTORY_POINTS;
+        }
+        if let Some(mtime) = mtime {
+            let age_days = self.now.saturating_sub(mtime) / (24 * 60 * 60);
+            score += (u64::from(RECENCY_POINTS) * RECENCY_HALF_LIFE_DAYS
+                / (RECENCY_HALF_LIFE_DAYS + age_days)) as u32;
+        }
+        score.saturating_sub(DEPTH_PENALTY.saturating_mul(depth.min(BASE_SCORE as usize) as u32))
+    }
+}
+
+/// How a word matches a name. Plain [`SegmentKind`]s are about where the
+/// match sits; a match starting a word inside the name ranks between a
+/// prefix and any other substring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MatchKind {
+    Segment(SegmentKind),
+    WordStart,
+}
+
+fn match_kind(name: &str, word: &str) -> Option<MatchKind> {
+    let stem = name
+        .rfind('.')
+        .filter(|&pos| pos > 0)
+        .map(|pos| &name[..pos]);
+    if name == word || stem == Some(word) {
+        return Some(MatchKind::Segment(SegmentKind::Exact));
+    }
+    if name.starts_with(word) {
+        return Some(MatchKind::Segment(SegmentKind::Prefix));
+    }
+    let mut kind = None;
+    for (pos, _) in name.match_indices(word) {
+        let word_start = name[..pos]
+            .chars()
+            .next_back()
+            .is_some_and(|previous| !previous.is_alphanumeric());
+        if word_start {
+            return Some(MatchKind::WordStart);
+        }
+        kind = Some(MatchKind::Segment(if pos + word.len() == name.len() {
+            SegmentKind::Suffix
+        } else {
+            SegmentKind::Substr
+        }));
+    }
+    kind
+}
+
+/// The words and name filter arguments of `expr` that describe names, skipping
+/// negated parts, regexes and wildcards.
+pub(crate) fn name_terms(expr: &Expr) -> Vec<&str> {
+    let mut terms = Vec::new();
+    collect_terms(expr, &mut terms);
+    terms
+}
+
+fn collect_terms<'e>(expr: &'e Expr, terms: &mut Vec<&'e str>) {
+    match expr {
+        Expr::Term(Term::Word(text) | Term::Phrase(text)) => {
+            if !has_wildcards(text) {
+                terms.push(text);
+            }
+        }
+        Expr::Term(Term::Filter(filter)) => {
+            let is_name_filter = matches!(
+                filter.kind,
+                FilterKind::File
+                    | FilterKind::Folder
+                    | FilterKind::CaseSensitive
+                    | FilterKind::NoCase
+                    | FilterKind::WholeWord
+                    | FilterKind::Acronym
+                    | FilterKind::NoWholeFilename
+            );
+            match &filter.argument {
+                Some(argument)
+                    if is_name_filter
+                        && !matches!(argument.kind, ArgumentKind::List(_))
+                        && !has_wildcards(&argument.raw) =>
+                {
+                    terms.push(&argument.raw)
+                }
+                _ => {}
+            }
+        }
+        Expr::And(parts) | Expr::Or(parts) => {
+            for part in parts {
+                collect_terms(part, terms);
+            }
+        }
+        Expr::Term(Term::Regex(_)) | Expr::Not(_) | Expr::Empty => {}
+    }
+}
+
+/// Only the last segment of `a/b` is about the name itself.
+fn name_word(text: &str) -> Option<String> {
+    let word = Folding::CASE
+        .apply(segment_value(query_segmentation(text).last()?))
+        .into_owned();
+    (!word.is_empty()).then_some(word)
+}
+
+impl SearchCache {
+    /// The node's score when the search it was found by is ranked by relevance.
+    pub(crate) fn relevance_score(&self, details: &ResultDetails, index: SlabIndex) -> Option<u32> {
+        let scorer = details.relevance.as_ref()?;
+        let node = self.file_nodes.get(index)?;
+        let mut depth = 0;
+        let mut parent = node.name_and_parent.parent();
+        while let Some(index) = parent {
+            depth += 1;
+            parent = self.file_nodes[index].name_and_parent.parent();
+        }
+        let mtime = node
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.mtime())
+            .map(|mtime| u64::from(mtime.get()));
+        Some(scorer.score(
+            node.name_and_parent.as_str(),
+            depth,
+            node.metadata.file_type_hint() == NodeFileType::Dir,
+            mtime,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cardinal_syntax::parse_query;
+
+    fn scorer(query: &str) -> RelevanceScorer {
+        RelevanceScorer {
+            now: 1_000 * 24 * 60 * 60,
+            ..RelevanceScorer::new(&parse_query(query).unwrap().expr)
+        }
+    }
+
+    #[test]
+    fn test_collects_name_words() {
+        let words = |query: &str| scorer(query).words;
+        assert_eq!(words("Cargo"), ["cargo"]);
+        assert_eq!(words("src/Main ext:rs !test"), ["main"]);
+        assert_eq!(
+            words("<foo | \"Bar baz\"> *.rs regex:^x"),
+            ["foo", "bar baz"]
+        );
+        assert_eq!(words("file:report nocase:README"), ["report", "readme"]);
+    }
+
+    #[test]
+    fn test_match_kinds() {
+        let kind = |name, word| match_kind(name, word);
+        use MatchKind::*;
+        assert_eq!(
+            kind("cargo.toml", "cargo"),
+            Some(Segment(SegmentKind::Exact))
+        );
+        assert_eq!(kind("cargo", "cargo"), Some(Segment(SegmentKind::Exact)));
+        assert_eq!(
+            kind("cargo-foo", "cargo"),
+            Some(Segment(SegmentKind::Prefix))
+        );
+        assert_eq!(kind(".cargo", "cargo"), Some(WordStart));
+        assert_eq!(kind("my_cargo_list", "cargo"), Some(WordStart));
+        assert_eq!(
+            kind("subcargo", "cargo"),
+            Some(Segment(SegmentKind::Suffix))
+        );
+        assert_eq!(
+            kind("subcargos", "cargo"),
+            Some(Segment(SegmentKind::Substr))
+        );
+        assert_eq!(kind("crate", "cargo"), None);
+    }
+
+    #[test]
+    fn test_score_ordering() {
+        let scorer = scorer("cargo");
+        let day = 24 * 60 * 60;
+        let manifest = scorer.score("Cargo.toml", 3, false, None);
+        let registry_manifest = scorer.score("Cargo.toml", 9, false, None);
+        let registry_crate = scorer.score("cargo-foo-1.2.3", 7, true, None);
+        let substring = scorer.score("subcargos", 3, false, None);
+        assert!(manifest > registry_manifest);
+        assert!(manifest > registry_crate);
+        assert!(registry_crate > substring);
+        // Recent files win over old ones, all else being equal.
+        let recent = scorer.score("Cargo.toml", 3, false, Some(999 * day));
+        let old = scorer.score("Cargo.toml", 3, false, Some(day));
+        assert!(recent > old && old >= manifest);
+        // Folders get a bonus.
+        assert!(scorer.score("cargo", 3, true, None) > scorer.score("cargo", 3, false, None));
+    }
+}
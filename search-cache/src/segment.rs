@@ -1,13 +1,72 @@
+use crate::{
+    SortSpec,
+    acronym::AcronymPattern,
+    fold::Folding,
+    fuzzy::FuzzyPattern,
+    wildcard::{WildcardPattern, has_wildcards},
+};
 use query_segmentation::Segment;
 use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+/// Anything but a letter or a digit separates words for `wholeword:`, so
+/// `ww:report` finds `q3_report.pdf` but not `reports.pdf`.
+const NON_WORD: &str = r"[^\p{L}\p{N}]";
+/// Names the word inside a `wholeword:` pattern, so highlights can leave out
+/// the separators matched around it.
+const WORD_GROUP: &str = "__word";
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct SearchOptions {
     pub use_regex: bool,
+    /// Compare names with full Unicode case folding.
     pub case_insensitive: bool,
+    /// Match letters whatever their accents, so `resume` finds `Résumé.pdf`.
+    /// Doesn't apply to regexes.
+    pub ignore_diacritics: bool,
+    /// Let plain words match names a few typos away, see
+    /// [`SearchResultNode::distance`](crate::SearchResultNode::distance).
+    /// Regexes, wildcards and `ww:` still match exactly.
+    pub fuzzy: bool,
+    /// Read plain words as initials or camel-case humps, like `acronym:` does
+    /// for a single term.
+    pub acronym: bool,
+    /// Report what matched in each result, see
+    /// [`SearchResultNode::highlights`](crate::SearchResultNode::highlights).
+    /// Off by default, as it runs the query again on every returned node.
+    pub highlight: bool,
+    /// Order of the results; `None` keeps the order they're found in, which
+    /// is cheapest.
+    pub sort: Option<SortSpec>,
+    /// Only return this many results, after skipping `offset` of them.
+    pub limit: Option<usize>,
+    pub offset: usize,
+    /// Only count the matches: the page comes back empty, with an exact total.
+    pub count_only: bool,
+}
+
+/// Options a modifier such as `case:`, `nocase:` or `ww:` sets for the term it
+/// wraps, on top of the global [`SearchOptions`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct TermOverrides {
+    pub(crate) case_insensitive: Option<bool>,
+    pub(crate) whole_word: bool,
+    pub(crate) acronym: bool,
+    /// Set by `nowholefilename:`: wildcards may match part of the name.
+    pub(crate) partial_wildcards: bool,
+}
+
+impl TermOverrides {
+    pub(crate) fn apply(self, options: SearchOptions) -> SearchOptions {
+        SearchOptions {
+            case_insensitive: self.case_insensitive.unwrap_or(options.case_insensitive),
+            acronym: options.acronym || self.acronym,
+            ..options
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum SegmentKind {
     Substr,
     Prefix,
@@ -15,22 +74,144 @@ pub(crate) enum SegmentKind {
     Exact,
 }
 
+impl SegmentKind {
+    fn matches(self, candidate: &str, needle: &str) -> bool {
+        match self {
+            SegmentKind::Substr => candidate.contains(needle),
+            SegmentKind::Prefix => candidate.starts_with(needle),
+            SegmentKind::Suffix => candidate.ends_with(needle),
+            SegmentKind::Exact => candidate == needle,
+        }
+    }
+
+    /// [`Self::matches`] for an ASCII `candidate` and a case folded `needle`,
+    /// without folding a copy of the candidate.
+    fn matches_ignore_ascii_case(self, candidate: &str, needle: &str) -> bool {
+        let (candidate, needle) = (candidate.as_bytes(), needle.as_bytes());
+        if needle.len() > candidate.len() {
+            return false;
+        }
+        match self {
+            SegmentKind::Substr => {
+                needle.is_empty()
+                    || candidate
+                        .windows(needle.len())
+                        .any(|window| window.eq_ignore_ascii_case(needle))
+            }
+            SegmentKind::Prefix => candidate[..needle.len()].eq_ignore_ascii_case(needle),
+            SegmentKind::Suffix => {
+                candidate[candidate.len() - needle.len()..].eq_ignore_ascii_case(needle)
+            }
+            SegmentKind::Exact => candidate.eq_ignore_ascii_case(needle),
+        }
+    }
+
+    /// Where `needle` sits in `candidate` when it [`matches`](Self::matches):
+    /// every occurrence for a substring.
+    fn ranges(self, candidate: &str, needle: &str) -> Option<Vec<Range<usize>>> {
+        if !self.matches(candidate, needle) {
+            return None;
+        }
+        let range = match self {
+            SegmentKind::Substr => {
+                return Some(
+                    candidate
+                        .match_indices(needle)
+                        .map(|(start, _)| start..start + needle.len())
+                        .collect(),
+                );
+            }
+            SegmentKind::Prefix => 0..needle.len(),
+            SegmentKind::Suffix => candidate.len() - needle.len()..candidate.len(),
+            SegmentKind::Exact => 0..candidate.len(),
+        };
+        Some(vec![range])
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) enum SegmentMatcher {
-    Plain { kind: SegmentKind, needle: String },
-    Regex { regex: Regex },
+    /// Compares the raw name, for case-sensitive ASCII needles.
+    Plain {
+        kind: SegmentKind,
+        needle: String,
+    },
+    /// Compares the name as brought to the same [`Folding`] as the needle.
+    Folded {
+        kind: SegmentKind,
+        needle: String,
+        folding: Folding,
+    },
+    Wildcard {
+        pattern: WildcardPattern,
+    },
+    /// Runs on the name after `folding`, which only normalizes to NFC for
+    /// regexes typed by the user.
+    Regex {
+        regex: Regex,
+        folding: Folding,
+    },
+    Fuzzy {
+        pattern: FuzzyPattern,
+    },
+    Acronym {
+        pattern: AcronymPattern,
+    },
 }
 
 impl SegmentMatcher {
     pub(crate) fn matches(&self, candidate: &str) -> bool {
         match self {
-            SegmentMatcher::Plain { kind, needle } => match kind {
-                SegmentKind::Substr => candidate.contains(needle),
-                SegmentKind::Prefix => candidate.starts_with(needle),
-                SegmentKind::Suffix => candidate.ends_with(needle),
-                SegmentKind::Exact => candidate == needle,
-            },
-            SegmentMatcher::Regex { regex } => regex.is_match(candidate),
+            SegmentMatcher::Plain { kind, needle } => kind.matches(candidate, needle),
+            SegmentMatcher::Folded {
+                kind,
+                needle,
+                folding,
+            } => {
+                if folding.case && candidate.is_ascii() {
+                    kind.matches_ignore_ascii_case(candidate, needle)
+                } else {
+                    kind.matches(&folding.apply(candidate), needle)
+                }
+            }
+            SegmentMatcher::Wildcard { pattern } => pattern.matches(candidate),
+            SegmentMatcher::Regex { regex, folding } => regex.is_match(&folding.apply(candidate)),
+            SegmentMatcher::Fuzzy { pattern } => pattern.matches(candidate),
+            SegmentMatcher::Acronym { pattern } => pattern.matches(candidate),
+        }
+    }
+
+    /// The byte ranges of `candidate` that made it match, `None` if it
+    /// doesn't. Ranges may be empty or overlap.
+    pub(crate) fn ranges(&self, candidate: &str) -> Option<Vec<Range<usize>>> {
+        match self {
+            SegmentMatcher::Plain { kind, needle } => kind.ranges(candidate, needle),
+            SegmentMatcher::Folded {
+                kind,
+                needle,
+                folding,
+            } => {
+                let folded = folding.apply_mapped(candidate);
+                let ranges = kind.ranges(&folded.text, needle)?;
+                Some(
+                    ranges
+                        .into_iter()
+                        .map(|range| folded.original(range))
+                        .collect(),
+                )
+            }
+            SegmentMatcher::Wildcard { pattern } => pattern.ranges(candidate),
+            SegmentMatcher::Regex { regex, folding } => {
+                let folded = folding.apply_mapped(candidate);
+                let ranges: Vec<_> = regex
+                    .captures_iter(&folded.text)
+                    .filter_map(|captures| captures.name(WORD_GROUP).or_else(|| captures.get(0)))
+                    .map(|found| folded.original(found.range()))
+                    .collect();
+                (!ranges.is_empty()).then_some(ranges)
+            }
+            SegmentMatcher::Fuzzy { pattern } => pattern.span(candidate).map(|span| vec![span]),
+            SegmentMatcher::Acronym { pattern } => pattern.ranges(candidate),
         }
     }
 }
@@ -38,38 +219,113 @@ impl SegmentMatcher {
 pub(crate) fn build_segment_matchers(
     segments: &[Segment<'_>],
     options: SearchOptions,
+    overrides: TermOverrides,
 ) -> Result<Vec<SegmentMatcher>, regex::Error> {
+    let options = overrides.apply(options);
+    let whole_word = overrides.whole_word;
+    let folding = Folding::new(options);
     segments
         .iter()
         .map(|segment| {
             let kind = segment_kind(segment);
             let value = segment_value(segment);
-            if options.use_regex || options.case_insensitive {
-                let base = if options.use_regex {
-                    value.to_owned()
-                } else {
-                    regex::escape(value)
-                };
-                let pattern = match kind {
-                    SegmentKind::Substr => base,
-                    SegmentKind::Prefix => format!("^(?:{base})"),
-                    SegmentKind::Suffix => format!("(?:{base})$"),
-                    SegmentKind::Exact => format!("^(?:{base})$"),
-                };
-                let mut builder = RegexBuilder::new(&pattern);
-                builder.case_insensitive(options.case_insensitive);
-                builder.build().map(|regex| SegmentMatcher::Regex { regex })
-            } else {
-                Ok(SegmentMatcher::Plain {
-                    kind,
-                    needle: value.to_string(),
-                })
+            if !options.use_regex && has_wildcards(value) {
+                return Ok(wildcard_matcher(
+                    value,
+                    folding,
+                    overrides.partial_wildcards,
+                ));
             }
+            if options.acronym && !options.use_regex {
+                return Ok(SegmentMatcher::Acronym {
+                    pattern: AcronymPattern::new(kind, value),
+                });
+            }
+            if options.fuzzy && !options.use_regex && !whole_word {
+                return Ok(SegmentMatcher::Fuzzy {
+                    pattern: FuzzyPattern::new(kind, value, folding),
+                });
+            }
+            if !options.use_regex && !whole_word {
+                return Ok(text_matcher(kind, value, folding));
+            }
+            // Regexes typed by the user see the name as is, apart from NFC,
+            // while `ww:` words are folded like any other word.
+            let (base, folding) = if options.use_regex {
+                (value.to_owned(), Folding::default())
+            } else {
+                (regex::escape(&folding.apply(value)), folding)
+            };
+            let pattern = match (kind, whole_word) {
+                (SegmentKind::Substr, false) => base,
+                (SegmentKind::Prefix, false) => format!("^(?:{base})"),
+                (SegmentKind::Suffix, false) => format!("(?:{base})$"),
+                (SegmentKind::Substr, true) => whole_word_pattern(&base),
+                (SegmentKind::Prefix, true) => format!("^(?P<{WORD_GROUP}>{base})(?:{NON_WORD}|$)"),
+                (SegmentKind::Suffix, true) => format!("(?:^|{NON_WORD})(?P<{WORD_GROUP}>{base})$"),
+                (SegmentKind::Exact, _) => format!("^(?:{base})$"),
+            };
+            let mut builder = RegexBuilder::new(&pattern);
+            builder.case_insensitive(options.case_insensitive && folding.is_exact());
+            builder
+                .build()
+                .map(|regex| SegmentMatcher::Regex { regex, folding })
         })
         .collect()
 }
 
-fn segment_kind(segment: &Segment<'_>) -> SegmentKind {
+/// A regex typed by the user, which sees names as they are apart from NFC.
+pub(crate) fn regex_matcher(
+    pattern: &str,
+    options: SearchOptions,
+) -> Result<SegmentMatcher, regex::Error> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(options.case_insensitive)
+        .build()
+        .map(|regex| SegmentMatcher::Regex {
+            regex,
+            folding: Folding::default(),
+        })
+}
+
+/// Compares raw names when that gives the same answer as folding them, which
+/// lets the name pool search without copying any name.
+fn text_matcher(kind: SegmentKind, value: &str, folding: Folding) -> SegmentMatcher {
+    if folding.is_exact() && value.is_ascii() {
+        SegmentMatcher::Plain {
+            kind,
+            needle: value.to_string(),
+        }
+    } else {
+        SegmentMatcher::Folded {
+            kind,
+            needle: folding.apply(value).into_owned(),
+            folding,
+        }
+    }
+}
+
+/// Wraps `pattern` so it only matches between word boundaries.
+pub(crate) fn whole_word_pattern(pattern: &str) -> String {
+    format!("(?:^|{NON_WORD})(?P<{WORD_GROUP}>{pattern})(?:{NON_WORD}|$)")
+}
+
+/// Wildcard segments match the whole name, whatever slashes surround them, so
+/// `src/*.rs` means "a `.rs` file directly inside a folder ending with `src`".
+/// With `partial`, they may match any part of it instead.
+fn wildcard_matcher(value: &str, folding: Folding, partial: bool) -> SegmentMatcher {
+    let pattern = if partial {
+        WildcardPattern::unanchored(value, folding)
+    } else {
+        WildcardPattern::new(value, folding)
+    };
+    match pattern.as_plain() {
+        Some((kind, needle)) => text_matcher(kind, needle, folding),
+        None => SegmentMatcher::Wildcard { pattern },
+    }
+}
+
+pub(crate) fn segment_kind(segment: &Segment<'_>) -> SegmentKind {
     match segment {
         Segment::Substr(_) => SegmentKind::Substr,
         Segment::Prefix(_) => SegmentKind::Prefix,
@@ -78,7 +334,7 @@ fn segment_kind(segment: &Segment<'_>) -> SegmentKind {
     }
 }
 
-fn segment_value<'s>(segment: &Segment<'s>) -> &'s str {
+pub(crate) fn segment_value<'s>(segment: &Segment<'s>) -> &'s str {
     match segment {
         Segment::Substr(value)
         | Segment::Prefix(value)
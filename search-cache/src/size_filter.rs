@@ -0,0 +1,253 @@
+use anyhow::{Result, anyhow, bail};
+use cardinal_syntax::{ArgumentKind, ComparisonOp, FilterArgument, RangeValue};
+use std::ops::{Bound, RangeBounds};
+
+const KB: u64 = 1000;
+const MB: u64 = 1000 * KB;
+const GB: u64 = 1000 * MB;
+const TB: u64 = 1000 * GB;
+const KIB: u64 = 1024;
+const MIB: u64 = 1024 * KIB;
+const GIB: u64 = 1024 * MIB;
+const TIB: u64 = 1024 * GIB;
+
+/// Byte range a `size:` filter accepts.
+///
+/// `kb`/`mb`/`gb`/`tb` are decimal (`1kb` == 1000 bytes) while
+/// `kib`/`mib`/`gib`/`tib` are binary (`1kib` == 1024 bytes), and the numeric
+/// part may be fractional (`1.5mb`). A bare value such as `size:10mb` matches
+/// the whole unit it names, so it accepts `10mb..<11mb` instead of exactly
+/// 10000000 bytes. Range ends are widened the same way, so `size:1mb..2mb`
+/// accepts everything `size:2mb` does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SizePredicate {
+    start: Bound<u64>,
+    end: Bound<u64>,
+    negated: bool,
+}
+
+impl SizePredicate {
+    pub(crate) fn parse(argument: &FilterArgument) -> Result<Self> {
+        match &argument.kind {
+            ArgumentKind::Comparison(comparison) => {
+                let value = parse_size(&comparison.value)?;
+                Ok(match comparison.op {
+                    ComparisonOp::Lt => Self::new(Bound::Unbounded, Bound::Excluded(value.bytes)),
+                    ComparisonOp::Lte => Self::new(Bound::Unbounded, Bound::Included(value.bytes)),
+                    ComparisonOp::Gt => Self::new(Bound::Excluded(value.bytes), Bound::Unbounded),
+                    ComparisonOp::Gte => Self::new(Bound::Included(value.bytes), Bound::Unbounded),
+                    ComparisonOp::Eq => Self::equal(value),
+                    ComparisonOp::Ne => Self {
+                        negated: true,
+                        ..Self::equal(value)
+                    },
+                })
+            }
+            ArgumentKind::Range(RangeValue { start, end, .. }) => {
+                let start = match start {
+                    Some(start) => Bound::Included(parse_size(start)?.bytes),
+                    None => Bound::Unbounded,
+                };
+                let end = match end {
+                    Some(end) => Self::unit_end(parse_size(end)?),
+                    None => Bound::Unbounded,
+                };
+                Ok(Self::new(start, end))
+            }
+            ArgumentKind::Bare | ArgumentKind::Phrase => {
+                let raw = argument.raw.trim();
+                if let Some(keyword) = Self::keyword(raw) {
+                    return Ok(keyword);
+                }
+                Ok(Self::equal(parse_size(raw)?))
+            }
+            ArgumentKind::List(_) => bail!("size: doesn't accept a list: {:?}", argument.raw),
+        }
+    }
+
+    pub(crate) fn matches(&self, size: u64) -> bool {
+        (self.start, self.end).contains(&size) != self.negated
+    }
+
+    fn new(start: Bound<u64>, end: Bound<u64>) -> Self {
+        Self {
+            start,
+            end,
+            negated: false,
+        }
+    }
+
+    fn equal(value: SizeValue) -> Self {
+        Self::new(Bound::Included(value.bytes), Self::unit_end(value))
+    }
+
+    /// End of the unit `value` names, e.g. up to but excluding `3mb` for `2mb`.
+    fn unit_end(value: SizeValue) -> Bound<u64> {
+        Bound::Excluded(value.bytes.saturating_add(value.granularity))
+    }
+
+    /// Everything's size keywords, which use binary units.
+    fn keyword(raw: &str) -> Option<Self> {
+        let (start, end) = match raw.to_ascii_lowercase().as_str() {
+            "empty" => (Bound::Included(0), Bound::Included(0)),
+            "tiny" => (Bound::Excluded(0), Bound::Included(10 * KIB)),
+            "small" => (Bound::Excluded(10 * KIB), Bound::Included(100 * KIB)),
+            "medium" => (Bound::Excluded(100 * KIB), Bound::Included(MIB)),
+            "large" => (Bound::Excluded(MIB), Bound::Included(16 * MIB)),
+            "huge" => (Bound::Excluded(16 * MIB), Bound::Included(128 * MIB)),
+            "gigantic" => (Bound::Excluded(128 * MIB), Bound::Unbounded),
+            _ => return None,
+        };
+        Some(Self::new(start, end))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SizeValue {
+    bytes: u64,
+    /// Width of the unit the value was written in, e.g. 1024 for `3kib`.
+    granularity: u64,
+}
+
+fn parse_size(raw: &str) -> Result<SizeValue> {
+    let raw = raw.trim();
+    let split = raw
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(raw.len());
+    let (number, unit) = raw.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| anyhow!("Invalid size: {raw:?}"))?;
+    let multiplier = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => KB,
+        "m" | "mb" => MB,
+        "g" | "gb" => GB,
+        "t" | "tb" => TB,
+        "kib" => KIB,
+        "mib" => MIB,
+        "gib" => GIB,
+        "tib" => TIB,
+        _ => bail!("Unknown size unit in {raw:?}"),
+    };
+    let bytes = (number * multiplier as f64).round();
+    // Fractional values are already more precise than the unit they use.
+    let granularity = if number.fract() == 0.0 { multiplier } else { 1 };
+    Ok(SizeValue {
+        bytes: bytes as u64,
+        granularity,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cardinal_syntax::{Expr, Term, parse_query};
+
+    fn predicate(query: &str) -> SizePredicate {
+        let Expr::Term(Term::Filter(filter)) = parse_query(query).unwrap().expr else {
+            panic!("expected a filter");
+        };
+        SizePredicate::parse(filter.argument.as_ref().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_size_units() {
+        assert_eq!(parse_size("42").unwrap().bytes, 42);
+        assert_eq!(parse_size("42b").unwrap().bytes, 42);
+        assert_eq!(parse_size("1kb").unwrap().bytes, 1000);
+        assert_eq!(parse_size("1k").unwrap().bytes, 1000);
+        assert_eq!(parse_size("1KiB").unwrap().bytes, 1024);
+        assert_eq!(parse_size("1.5MB").unwrap().bytes, 1_500_000);
+        assert_eq!(parse_size("1.5mib").unwrap().bytes, 1536 * 1024);
+        assert_eq!(parse_size("2gb").unwrap().bytes, 2_000_000_000);
+        assert_eq!(parse_size("2gib").unwrap().bytes, 2 * GIB);
+        assert_eq!(parse_size("1tb").unwrap().bytes, TB);
+        assert_eq!(parse_size("1tib").unwrap().bytes, TIB);
+        assert!(parse_size("1parsec").is_err());
+        assert!(parse_size("mb").is_err());
+    }
+
+    #[test]
+    fn test_comparisons() {
+        let gt = predicate("size:>1kib");
+        assert!(!gt.matches(1024));
+        assert!(gt.matches(1025));
+
+        let gte = predicate("size:>=1kib");
+        assert!(gte.matches(1024));
+        assert!(!gte.matches(1023));
+
+        let lt = predicate("size:<1kb");
+        assert!(lt.matches(999));
+        assert!(!lt.matches(1000));
+
+        let ne = predicate("size:!=10");
+        assert!(!ne.matches(10));
+        assert!(ne.matches(11));
+    }
+
+    #[test]
+    fn test_equality_covers_whole_unit() {
+        let eq = predicate("size:1mb");
+        assert!(!eq.matches(MB - 1));
+        assert!(eq.matches(MB));
+        assert!(eq.matches(2 * MB - 1));
+        assert!(!eq.matches(2 * MB));
+
+        let binary = predicate("size:1mib");
+        assert!(!binary.matches(MIB - 1));
+        assert!(binary.matches(2 * MIB - 1));
+        assert!(!binary.matches(2 * MIB));
+
+        let exact = predicate("size:1.5kib");
+        assert!(exact.matches(1536));
+        assert!(!exact.matches(1537));
+    }
+
+    #[test]
+    fn test_ranges() {
+        let range = predicate("size:1kib..2kib");
+        assert!(!range.matches(1023));
+        assert!(range.matches(1024));
+        assert!(range.matches(2048));
+        assert!(range.matches(3071));
+        assert!(!range.matches(3072));
+
+        let open_start = predicate("size:..1kb");
+        assert!(open_start.matches(0));
+        assert!(open_start.matches(1999));
+        assert!(!open_start.matches(2000));
+
+        let open_end = predicate("size:1kb..");
+        assert!(open_end.matches(u64::MAX));
+        assert!(!open_end.matches(999));
+
+        let exact_end = predicate("size:1kib..1.5kib");
+        assert!(exact_end.matches(1536));
+        assert!(!exact_end.matches(1537));
+    }
+
+    #[test]
+    fn test_range_end_matches_bare_value() {
+        let range = predicate("size:1mb..2mb");
+        let bare = predicate("size:2mb");
+        for size in [2 * MB, 3 * MB - 1, 3 * MB] {
+            assert_eq!(range.matches(size), bare.matches(size), "{size}");
+        }
+    }
+
+    #[test]
+    fn test_keywords() {
+        assert!(predicate("size:empty").matches(0));
+        assert!(!predicate("size:empty").matches(1));
+        assert!(predicate("size:tiny").matches(10 * KIB));
+        assert!(!predicate("size:tiny").matches(0));
+        assert!(predicate("size:Small").matches(50 * KIB));
+        assert!(predicate("size:medium").matches(MIB));
+        assert!(predicate("size:large").matches(16 * MIB));
+        assert!(predicate("size:huge").matches(100 * MIB));
+        assert!(predicate("size:gigantic").matches(GIB));
+        assert!(!predicate("size:gigantic").matches(128 * MIB));
+    }
+}
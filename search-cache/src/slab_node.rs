@@ -140,6 +140,15 @@ impl<'a> SlabNodeMetadata<'a> {
     pub fn mtime(&self) -> Option<NonZeroU32> {
         NonZeroU32::new(self.0.mtime)
     }
+
+    pub fn atime(&self) -> Option<NonZeroU32> {
+        NonZeroU32::new(self.0.atime)
+    }
+
+    /// Raw `st_mode`: file type, permission and setuid/setgid/sticky bits.
+    pub fn mode(&self) -> u32 {
+        self.0.mode.into()
+    }
 }
 
 /// Use a compact form so that
@@ -149,6 +158,9 @@ pub struct SlabNodeMetadataCompact {
     // Actually a Option<NonZeroU32>, but using u32 here due to https://github.com/serde-rs/serde/issues/1834
     ctime: u32,
     mtime: u32,
+    atime: u32,
+    // `st_mode` only uses the low 16 bits on both macOS and Linux.
+    mode: u16,
 }
 
 impl SlabNodeMetadataCompact {
@@ -157,6 +169,8 @@ impl SlabNodeMetadataCompact {
             state_type_and_size: crate::StateTypeSize::unaccessible(),
             ctime: 0,
             mtime: 0,
+            atime: 0,
+            mode: 0,
         }
     }
 
@@ -166,6 +180,8 @@ impl SlabNodeMetadataCompact {
             size,
             ctime,
             mtime,
+            atime,
+            mode,
         }: fswalk::NodeMetadata,
     ) -> Self {
         Self {
@@ -178,6 +194,11 @@ impl SlabNodeMetadataCompact {
                 .and_then(|x| std::num::NonZeroU32::try_from(x).ok())
                 .map(|x| x.get())
                 .unwrap_or_default(),
+            atime: atime
+                .and_then(|x| std::num::NonZeroU32::try_from(x).ok())
+                .map(|x| x.get())
+                .unwrap_or_default(),
+            mode: mode as u16,
         }
     }
 
@@ -186,6 +207,8 @@ impl SlabNodeMetadataCompact {
             state_type_and_size: crate::StateTypeSize::none(),
             ctime: 0,
             mtime: 0,
+            atime: 0,
+            mode: 0,
         }
     }
 
@@ -225,4 +248,17 @@ impl SlabNodeMetadataCompact {
 pub struct SearchResultNode {
     pub path: std::path::PathBuf,
     pub metadata: SlabNodeMetadataCompact,
+    /// Relevance of the node when its search was sorted by
+    /// [`SortKey::Relevance`](crate::SortKey::Relevance), higher being better.
+    pub score: Option<u32>,
+    /// Typos between the name and the query when its search was
+    /// [`fuzzy`](crate::SearchOptions::fuzzy), lower being closer.
+    pub distance: Option<u32>,
+    /// Byte offsets in the name of the characters an acronym search matched,
+    /// for highlighting.
+    pub acronym_positions: Option<Vec<usize>>,
+    /// What matched the query, in the name and in the names of the folders
+    /// above it, when its search asked for
+    /// [`highlight`](crate::SearchOptions::highlight).
+    pub highlights: Option<Vec<crate::MatchRange>>,
 }
@@ -0,0 +1,302 @@
+use crate::{ResultDetails, SearchCache, SlabIndex, query::CANCEL_CHECK_INTERVAL};
+use anyhow::{Result, bail};
+use fswalk::NodeFileType;
+use search_cancel::CancellationToken;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, collections::BinaryHeap, num::NonZeroU32, path::PathBuf, str::FromStr};
+
+/// What search results are ordered by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortKey {
+    /// File name, ignoring case.
+    #[default]
+    Name,
+    /// Full path.
+    Path,
+    Size,
+    DateModified,
+    DateCreated,
+    DateAccessed,
+    /// Lowercase extension. Folders and names without one come first.
+    Extension,
+    /// Folders before files, then by extension.
+    Type,
+    /// Best matches of the query's words first: exact names, then prefixes,
+    /// word starts and substrings, favouring shallow, recent files and
+    /// folders. Ties keep the name index order.
+    Relevance,
+    /// Closest matches of a fuzzy search first.
+    Distance,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// How [`SearchCache::search_with_options`] orders its results.
+///
+/// Ties on the key are broken by name and then by the name index order (by
+/// name, then path), always ascending, so the order is the same from one
+/// search to the next. Nodes whose size or date can't be read come last in
+/// both directions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SortSpec {
+    pub key: SortKey,
+    #[serde(default)]
+    pub direction: SortDirection,
+}
+
+impl SortKey {
+    fn needs_metadata(self) -> bool {
+        matches!(
+            self,
+            Self::Size | Self::DateModified | Self::DateCreated | Self::DateAccessed
+        )
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = anyhow::Error;
+
+    /// Accepts the key names and the date filter shorthands, like `dm`.
+    fn from_str(value: &str) -> Result<Self> {
+        Ok(match value.to_ascii_lowercase().as_str() {
+            "name" => Self::Name,
+            "path" => Self::Path,
+            "size" => Self::Size,
+            "dm" | "modified" | "datemodified" => Self::DateModified,
+            "dc" | "created" | "datecreated" => Self::DateCreated,
+            "da" | "accessed" | "dateaccessed" => Self::DateAccessed,
+            "ext" | "extension" => Self::Extension,
+            "type" => Self::Type,
+            "relevance" | "rank" => Self::Relevance,
+            "distance" => Self::Distance,
+            _ => bail!("Unknown sort key {value:?}"),
+        })
+    }
+}
+
+impl SortDirection {
+    fn apply(self, ordering: Ordering) -> Ordering {
+        match self {
+            Self::Ascending => ordering,
+            Self::Descending => ordering.reverse(),
+        }
+    }
+}
+
+/// The values a node is compared by, collected up front so the comparisons
+/// don't look them up again.
+struct SortEntry {
+    index: SlabIndex,
+    name: &'static str,
+    extension: &'static str,
+    /// Size, date or folder rank, depending on the key.
+    number: Option<u64>,
+    /// Only built for the path key, since it walks up the parent chain.
+    path: Option<PathBuf>,
+    /// Where the node came in the name index order, the last tie-break.
+    position: u32,
+}
+
+/// A [`SortEntry`] ordered by [`compare_sorted`], so a [`BinaryHeap`] can
+/// hold on to the first entries.
+struct Ranked {
+    entry: SortEntry,
+    sort: SortSpec,
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_sorted(&self.entry, &other.entry, self.sort)
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+impl SearchCache {
+    /// Orders `nodes`, which must be in name index order, by `sort`, fetching
+    /// the metadata of size and date sorts first. With `keep`, only that many
+    /// of the first nodes are returned, ranked with a bounded heap instead of
+    /// sorting everything. Relevance and distance are taken from `details`.
+    /// Returns `None` when cancelled.
+    pub(crate) fn sort_nodes(
+        &mut self,
+        nodes: Vec<SlabIndex>,
+        sort: SortSpec,
+        details: &ResultDetails,
+        keep: Option<usize>,
+        token: CancellationToken,
+    ) -> Option<Vec<SlabIndex>> {
+        if sort.key.needs_metadata() {
+            self.fetch_missing_metadata(&nodes, token)?;
+        }
+        let cache = &*self;
+        let sorted = match keep {
+            Some(keep) => {
+                let mut heap = BinaryHeap::with_capacity(keep.min(nodes.len()));
+                for (i, index) in nodes.into_iter().enumerate() {
+                    if i % CANCEL_CHECK_INTERVAL == 0 && token.is_cancelled() {
+                        return None;
+                    }
+                    let ranked = Ranked {
+                        entry: cache.sort_entry(index, position(i), sort.key, details),
+                        sort,
+                    };
+                    if heap.len() < keep {
+                        heap.push(ranked);
+                    } else if let Some(mut last) = heap.peek_mut() {
+                        if ranked < *last {
+                            *last = ranked;
+                        }
+                    }
+                }
+                heap.into_sorted_vec()
+                    .into_iter()
+                    .map(|ranked| ranked.entry.index)
+                    .collect()
+            }
+            None => {
+                let mut entries = Vec::with_capacity(nodes.len());
+                for (i, index) in nodes.into_iter().enumerate() {
+                    if i % CANCEL_CHECK_INTERVAL == 0 && token.is_cancelled() {
+                        return None;
+                    }
+                    entries.push(cache.sort_entry(index, position(i), sort.key, details));
+                }
+                entries.sort_by(|a, b| compare_sorted(a, b, sort));
+                entries.into_iter().map(|entry| entry.index).collect()
+            }
+        };
+        (!token.is_cancelled()).then_some(sorted)
+    }
+
+    fn sort_entry(
+        &self,
+        index: SlabIndex,
+        position: u32,
+        key: SortKey,
+        details: &ResultDetails,
+    ) -> SortEntry {
+        let node = &self.file_nodes[index];
+        let name = node.name_and_parent.as_str();
+        let is_dir = node.metadata.file_type_hint() == NodeFileType::Dir;
+        let metadata = node.metadata.as_ref();
+        let number = match key {
+            SortKey::Size => metadata.map(|metadata| metadata.size()),
+            SortKey::DateModified => metadata.and_then(|metadata| metadata.mtime()).map(to_u64),
+            SortKey::DateCreated => metadata.and_then(|metadata| metadata.ctime()).map(to_u64),
+            SortKey::DateAccessed => metadata.and_then(|metadata| metadata.atime()).map(to_u64),
+            SortKey::Type => Some(u64::from(!is_dir)),
+            SortKey::Relevance => self.relevance_score(details, index).map(u64::from),
+            SortKey::Distance => self.fuzzy_distance(details, index).map(u64::from),
+            _ => None,
+        };
+        let extension = match name.rfind('.') {
+            Some(pos) if !is_dir => &name[pos + 1..],
+            _ => "",
+        };
+        SortEntry {
+            index,
+            name,
+            extension,
+            number,
+            path: match key {
+                SortKey::Path => self.node_path(index),
+                _ => None,
+            },
+            position,
+        }
+    }
+}
+
+fn position(i: usize) -> u32 {
+    // Results are slab indices, so there are never more than `u32::MAX` of them.
+    i as u32
+}
+
+/// The full order: the key, then name ignoring case and the name index order.
+/// Relevance ties go straight to the name index order.
+fn compare_sorted(a: &SortEntry, b: &SortEntry, sort: SortSpec) -> Ordering {
+    compare_entries(a, b, sort)
+        .then_with(|| match sort.key {
+            SortKey::Relevance => Ordering::Equal,
+            _ => cmp_ignore_case(a.name, b.name),
+        })
+        .then_with(|| a.position.cmp(&b.position))
+}
+
+/// Compares the sort key alone; ties are broken by the caller.
+fn compare_entries(a: &SortEntry, b: &SortEntry, sort: SortSpec) -> Ordering {
+    let direction = sort.direction;
+    match sort.key {
+        SortKey::Name => direction.apply(cmp_ignore_case(a.name, b.name)),
+        SortKey::Path => direction.apply(a.path.cmp(&b.path)),
+        SortKey::Extension => direction.apply(cmp_ignore_case(a.extension, b.extension)),
+        // Higher scores come first when ascending.
+        SortKey::Relevance => direction.apply(b.number.cmp(&a.number)),
+        SortKey::Type => direction.apply(
+            a.number
+                .cmp(&b.number)
+                .then_with(|| cmp_ignore_case(a.extension, b.extension)),
+        ),
+        SortKey::Size
+        | SortKey::DateModified
+        | SortKey::DateCreated
+        | SortKey::DateAccessed
+        | SortKey::Distance => match (a.number, b.number) {
+            (Some(a), Some(b)) => direction.apply(a.cmp(&b)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        },
+    }
+}
+
+fn to_u64(time: NonZeroU32) -> u64 {
+    time.get().into()
+}
+
+fn cmp_ignore_case(a: &str, b: &str) -> Ordering {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .cmp(b.chars().flat_map(char::to_lowercase))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sort_key() {
+        assert_eq!("Size".parse::<SortKey>().unwrap(), SortKey::Size);
+        assert_eq!("dm".parse::<SortKey>().unwrap(), SortKey::DateModified);
+        assert_eq!("ext".parse::<SortKey>().unwrap(), SortKey::Extension);
+        assert!("colour".parse::<SortKey>().is_err());
+    }
+
+    #[test]
+    fn test_cmp_ignore_case() {
+        assert_eq!(cmp_ignore_case("README", "license"), Ordering::Greater);
+        assert_eq!(cmp_ignore_case("Äpfel", "äpfel"), Ordering::Equal);
+        assert_eq!(cmp_ignore_case("a", "ab"), Ordering::Less);
+    }
+}
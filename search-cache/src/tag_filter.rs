@@ -0,0 +1,185 @@
+use crate::{
+    fold::Folding,
+    media_tags::MediaTags,
+    number_filter::NumberPredicate,
+    wildcard::{WildcardPattern, has_wildcards},
+};
+use anyhow::{Result, anyhow, bail};
+use cardinal_syntax::{ArgumentKind, Filter, FilterKind};
+
+/// An `artist:`, `album:`, `title:`, `genre:`, `comment:`, `year:` or `track:`
+/// filter.
+///
+/// Text fields match case-insensitively, either as a substring or, when the
+/// value has wildcards, against the whole tag. `year:` and `track:` accept
+/// comparisons (`year:>=2000`), ranges (`track:1..3`) and lists (`year:1999;2001`).
+#[derive(Debug, Clone)]
+pub(crate) struct TagPredicate {
+    field: TagField,
+    matcher: TagMatcher,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagField {
+    Artist,
+    Album,
+    Title,
+    Genre,
+    Comment,
+    Year,
+    Track,
+}
+
+#[derive(Debug, Clone)]
+enum TagMatcher {
+    /// Matches when any of the values does.
+    Text(Vec<TextMatcher>),
+    Number(NumberPredicate),
+}
+
+#[derive(Debug, Clone)]
+enum TextMatcher {
+    /// Lowercased needle.
+    Substring(String),
+    Wildcard(WildcardPattern),
+}
+
+impl TagPredicate {
+    pub(crate) fn parse(filter: &Filter) -> Result<Self> {
+        let (field, name) = match filter.kind {
+            FilterKind::Artist => (TagField::Artist, "artist"),
+            FilterKind::Album => (TagField::Album, "album"),
+            FilterKind::Title => (TagField::Title, "title"),
+            FilterKind::Genre => (TagField::Genre, "genre"),
+            FilterKind::Comment => (TagField::Comment, "comment"),
+            FilterKind::Year => (TagField::Year, "year"),
+            FilterKind::Track => (TagField::Track, "track"),
+            _ => bail!("Filter {:?} isn't a music tag filter", filter.kind),
+        };
+        let argument = filter
+            .argument
+            .as_ref()
+            .ok_or_else(|| anyhow!("{name}: requires a value"))?;
+        let matcher = match field {
+            TagField::Year | TagField::Track => {
+                TagMatcher::Number(NumberPredicate::parse(name, argument)?)
+            }
+            _ => {
+                let values: Vec<&str> = match &argument.kind {
+                    ArgumentKind::List(items) => items.iter().map(String::as_str).collect(),
+                    _ => vec![argument.raw.as_str()],
+                };
+                let matchers: Vec<TextMatcher> = values
+                    .into_iter()
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(TextMatcher::new)
+                    .collect();
+                if matchers.is_empty() {
+                    bail!("{name}: requires a value");
+                }
+                TagMatcher::Text(matchers)
+            }
+        };
+        Ok(Self { field, matcher })
+    }
+
+    pub(crate) fn matches(&self, tags: &MediaTags) -> bool {
+        match &self.matcher {
+            TagMatcher::Text(matchers) => {
+                let value = match self.field {
+                    TagField::Artist => &tags.artist,
+                    TagField::Album => &tags.album,
+                    TagField::Title => &tags.title,
+                    TagField::Genre => &tags.genre,
+                    _ => &tags.comment,
+                };
+                value
+                    .as_deref()
+                    .is_some_and(|value| matchers.iter().any(|matcher| matcher.matches(value)))
+            }
+            TagMatcher::Number(predicate) => {
+                let value = match self.field {
+                    TagField::Year => tags.year,
+                    _ => tags.track,
+                };
+                value.is_some_and(|value| predicate.matches(value))
+            }
+        }
+    }
+}
+
+impl TextMatcher {
+    fn new(value: &str) -> Self {
+        if has_wildcards(value) {
+            Self::Wildcard(WildcardPattern::new(value, Folding::CASE))
+        } else {
+            Self::Substring(Folding::CASE.apply(value).into_owned())
+        }
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            Self::Substring(needle) => Folding::CASE.apply(value).contains(needle.as_str()),
+            Self::Wildcard(pattern) => pattern.matches(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cardinal_syntax::{Expr, Term, parse_query};
+
+    fn predicate(query: &str) -> Result<TagPredicate> {
+        let Expr::Term(Term::Filter(filter)) = parse_query(query).unwrap().expr else {
+            panic!("expected a filter");
+        };
+        TagPredicate::parse(&filter)
+    }
+
+    fn tags() -> MediaTags {
+        MediaTags {
+            artist: Some("Daft Punk".to_string()),
+            album: Some("Discovery".to_string()),
+            title: Some("One More Time".to_string()),
+            genre: Some("House".to_string()),
+            comment: None,
+            year: Some(2001),
+            track: Some(1),
+        }
+    }
+
+    #[test]
+    fn test_text_fields() {
+        let tags = tags();
+        assert!(predicate("artist:daft").unwrap().matches(&tags));
+        assert!(predicate("title:\"more time\"").unwrap().matches(&tags));
+        assert!(predicate("album:disc*").unwrap().matches(&tags));
+        assert!(!predicate("album:covery*").unwrap().matches(&tags));
+        assert!(predicate("genre:techno;house").unwrap().matches(&tags));
+        assert!(!predicate("genre:techno").unwrap().matches(&tags));
+        assert!(!predicate("comment:live").unwrap().matches(&tags));
+    }
+
+    #[test]
+    fn test_number_fields() {
+        let tags = tags();
+        assert!(predicate("year:2001").unwrap().matches(&tags));
+        assert!(predicate("year:>=2000").unwrap().matches(&tags));
+        assert!(!predicate("year:<2001").unwrap().matches(&tags));
+        assert!(predicate("year:1990..2005").unwrap().matches(&tags));
+        assert!(predicate("year:1999;2001").unwrap().matches(&tags));
+        assert!(predicate("track:01").unwrap().matches(&tags));
+        assert!(!predicate("track:!=1").unwrap().matches(&tags));
+        assert!(predicate("track:..3").unwrap().matches(&tags));
+        assert!(!predicate("track:2").unwrap().matches(&MediaTags::default()));
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        assert!(predicate("year:nineties").is_err());
+        assert!(predicate("track:>x").is_err());
+        assert!(predicate("artist:").is_err());
+    }
+}
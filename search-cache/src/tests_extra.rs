@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod extra {
-    use crate::SearchCache;
+    use crate::{ResultDetails, SearchCache};
     use search_cancel::CancellationToken;
     use std::{fs, path::PathBuf};
     use tempdir::TempDir;
@@ -22,7 +22,7 @@ mod extra {
         let tmp = TempDir::new("node_path").unwrap();
         fs::create_dir(tmp.path().join("dir1")).unwrap();
         fs::File::create(tmp.path().join("dir1/file_x")).unwrap();
-        let cache = SearchCache::walk_fs(tmp.path().to_path_buf());
+        let mut cache = SearchCache::walk_fs(tmp.path().to_path_buf());
         let idxs = cache.search("file_x").unwrap();
         assert_eq!(idxs.len(), 1);
         let full = cache.node_path(idxs.into_iter().next().unwrap()).unwrap();
@@ -73,14 +73,14 @@ mod extra {
         assert_eq!(q1.len(), 1);
         assert!(q1[0].metadata.is_none());
         // expand_file_nodes should fetch metadata
-        let nodes = cache.expand_file_nodes(&idxs);
+        let nodes = cache.expand_file_nodes(&idxs, &ResultDetails::default());
         assert_eq!(nodes.len(), 1);
         assert!(
             nodes[0].metadata.is_some(),
             "metadata should be fetched on demand"
         );
         // A second expand should still have metadata (cached)
-        let nodes2 = cache.expand_file_nodes(&idxs);
+        let nodes2 = cache.expand_file_nodes(&idxs, &ResultDetails::default());
         assert!(nodes2[0].metadata.is_some());
     }
 
@@ -0,0 +1,256 @@
+use crate::{SegmentKind, fold::Folding};
+use std::ops::Range;
+
+/// Everything-style wildcard pattern: `*` matches any run of characters and `?`
+/// matches exactly one. Patterns always cover the whole name, compared after
+/// `folding`.
+#[derive(Clone, Debug)]
+pub(crate) struct WildcardPattern {
+    /// Folded pieces between the `*`s. A pattern without `*` has a single piece.
+    pieces: Vec<Box<str>>,
+    folding: Folding,
+}
+
+pub(crate) fn has_wildcards(text: &str) -> bool {
+    text.contains(['*', '?'])
+}
+
+impl WildcardPattern {
+    pub(crate) fn new(pattern: &str, folding: Folding) -> Self {
+        Self {
+            pieces: folding.apply(pattern).split('*').map(Box::from).collect(),
+            folding,
+        }
+    }
+
+    /// Like [`WildcardPattern::new`], but the pattern may match anywhere in the
+    /// name, as if it were wrapped in `*`s.
+    pub(crate) fn unanchored(pattern: &str, folding: Folding) -> Self {
+        let mut pattern = Self::new(pattern, folding);
+        if pattern
+            .pieces
+            .first()
+            .is_some_and(|piece| !piece.is_empty())
+        {
+            pattern.pieces.insert(0, Box::from(""));
+        }
+        if pattern.pieces.len() == 1 || pattern.pieces.last().is_some_and(|piece| !piece.is_empty())
+        {
+            pattern.pieces.push(Box::from(""));
+        }
+        pattern
+    }
+
+    /// Shape of the pattern when it can be answered by a plain string search,
+    /// i.e. it has no `?` and only leading and/or trailing `*`s.
+    pub(crate) fn as_plain(&self) -> Option<(SegmentKind, &str)> {
+        if !self.folding.is_exact() || self.pieces.iter().any(|piece| piece.contains('?')) {
+            return None;
+        }
+        let literals: Vec<&str> = self
+            .pieces
+            .iter()
+            .map(|piece| &**piece)
+            .filter(|piece| !piece.is_empty())
+            .collect();
+        let first = self.pieces.first().map(|piece| &**piece);
+        let last = self.pieces.last().map(|piece| &**piece);
+        match (literals.as_slice(), first, last) {
+            ([], ..) => Some((SegmentKind::Substr, "")),
+            ([literal], Some(""), Some("")) => Some((SegmentKind::Substr, literal)),
+            ([literal], Some(""), _) => Some((SegmentKind::Suffix, literal)),
+            ([literal], _, Some("")) => Some((SegmentKind::Prefix, literal)),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn matches(&self, candidate: &str) -> bool {
+        self.match_pieces(&self.folding.apply(candidate), |_| {})
+    }
+
+    /// Byte ranges of `candidate` the pieces between the `*`s matched.
+    pub(crate) fn ranges(&self, candidate: &str) -> Option<Vec<Range<usize>>> {
+        let folded = self.folding.apply_mapped(candidate);
+        let mut ranges = Vec::new();
+        self.match_pieces(&folded.text, |range| {
+            if !range.is_empty() {
+                ranges.push(folded.original(range));
+            }
+        })
+        .then_some(ranges)
+    }
+
+    /// Matches an already folded `candidate`, passing where each piece landed
+    /// to `on_piece` along the way.
+    fn match_pieces(&self, candidate: &str, mut on_piece: impl FnMut(Range<usize>)) -> bool {
+        let Some((first, rest)) = self.pieces.split_first() else {
+            return candidate.is_empty();
+        };
+        let Some(consumed) = self.piece_at(first, candidate) else {
+            return false;
+        };
+        on_piece(0..consumed);
+        let Some((last, middle)) = rest.split_last() else {
+            return consumed == candidate.len();
+        };
+        let mut offset = consumed;
+        // Pieces have a fixed length, so taking the leftmost match of each middle
+        // piece never rules out a match the later pieces could have used.
+        for piece in middle {
+            let Some((start, len)) = self.find_piece(piece, &candidate[offset..]) else {
+                return false;
+            };
+            on_piece(offset + start..offset + start + len);
+            offset += start + len;
+        }
+        if last.is_empty() {
+            return true;
+        }
+        let remaining = &candidate[offset..];
+        let piece_len = last.chars().count();
+        let Some((tail_start, _)) = remaining.char_indices().rev().nth(piece_len - 1) else {
+            return false;
+        };
+        if self.piece_at(last, &remaining[tail_start..]) != Some(remaining.len() - tail_start) {
+            return false;
+        }
+        on_piece(offset + tail_start..candidate.len());
+        true
+    }
+
+    /// Matches `piece` at the start of `candidate`, returning the matched byte length.
+    fn piece_at(&self, piece: &str, candidate: &str) -> Option<usize> {
+        let mut chars = candidate.chars();
+        let mut consumed = 0;
+        for expected in piece.chars() {
+            let actual = chars.next()?;
+            if expected != '?' && expected != actual {
+                return None;
+            }
+            consumed += actual.len_utf8();
+        }
+        Some(consumed)
+    }
+
+    /// Leftmost occurrence of `piece` in `candidate` as `(byte offset, byte length)`.
+    fn find_piece(&self, piece: &str, candidate: &str) -> Option<(usize, usize)> {
+        if !piece.contains('?') {
+            return candidate.find(piece).map(|start| (start, piece.len()));
+        }
+        candidate
+            .char_indices()
+            .map(|(start, _)| start)
+            .chain(std::iter::once(candidate.len()))
+            .find_map(|start| {
+                self.piece_at(piece, &candidate[start..])
+                    .map(|len| (start, len))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, candidate: &str) -> bool {
+        WildcardPattern::new(pattern, Folding::default()).matches(candidate)
+    }
+
+    #[test]
+    fn test_star_matches_whole_name() {
+        assert!(matches("*.mp3", "song.mp3"));
+        assert!(matches("*.mp3", ".mp3"));
+        assert!(!matches("*.mp3", "song.mp3.bak"));
+        assert!(matches("song*", "song.mp3"));
+        assert!(!matches("song*", "my song"));
+        assert!(matches("*", ""));
+        assert!(matches("*", "anything"));
+        assert!(matches("a*b*c", "abc"));
+        assert!(matches("a*b*c", "a-b-b-c"));
+        assert!(!matches("a*b*c", "a-c-b"));
+        assert!(matches("**x", "x"));
+    }
+
+    #[test]
+    fn test_question_mark_matches_one_char() {
+        assert!(matches("file?.txt", "file1.txt"));
+        assert!(!matches("file?.txt", "file.txt"));
+        assert!(!matches("file?.txt", "file12.txt"));
+        assert!(matches("?", "é"));
+        assert!(matches("*?.rs", "a.rs"));
+        assert!(!matches("*?.rs", ".rs"));
+        assert!(matches("*a?c*", "xxabcxx"));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let pattern = WildcardPattern::new("*.MP3", Folding::CASE);
+        assert!(pattern.matches("song.mp3"));
+        assert!(pattern.matches("SONG.Mp3"));
+        assert!(!pattern.matches("song.mp4"));
+        assert!(WildcardPattern::new("R?ADME*", Folding::CASE).matches("readme.md"));
+        assert!(!WildcardPattern::new("*.MP3", Folding::default()).matches("song.mp3"));
+        // `?` is one character of the composed name.
+        assert!(WildcardPattern::new("caf?", Folding::default()).matches("cafe\u{301}"));
+        assert!(WildcardPattern::new("STRA*", Folding::CASE).matches("Straße"));
+    }
+
+    #[test]
+    fn test_unanchored() {
+        let pattern = |pattern: &str| WildcardPattern::unanchored(pattern, Folding::default());
+        assert!(pattern("*.mp3").matches("song.mp3.bak"));
+        assert!(pattern("s?ng").matches("my song.mp3"));
+        assert!(pattern("a*c").matches("xabcx"));
+        assert!(!pattern("a*c").matches("cba"));
+        assert_eq!(
+            pattern("*.mp3").as_plain(),
+            Some((SegmentKind::Substr, ".mp3"))
+        );
+        assert_eq!(pattern("").as_plain(), Some((SegmentKind::Substr, "")));
+    }
+
+    #[test]
+    fn test_plain_shapes() {
+        let pattern = |pattern: &str| WildcardPattern::new(pattern, Folding::default());
+        assert_eq!(
+            pattern("*.mp3").as_plain(),
+            Some((SegmentKind::Suffix, ".mp3"))
+        );
+        assert_eq!(
+            pattern("foo*").as_plain(),
+            Some((SegmentKind::Prefix, "foo"))
+        );
+        assert_eq!(
+            pattern("*foo*").as_plain(),
+            Some((SegmentKind::Substr, "foo"))
+        );
+        assert_eq!(pattern("**").as_plain(), Some((SegmentKind::Substr, "")));
+        assert_eq!(pattern("a*b").as_plain(), None);
+        assert_eq!(pattern("a?b").as_plain(), None);
+        assert_eq!(
+            WildcardPattern::new("*.mp3", Folding::CASE).as_plain(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_piece_ranges() {
+        let ranges = |pattern: &str, candidate: &str| {
+            WildcardPattern::new(pattern, Folding::CASE)
+                .ranges(candidate)
+                .map(|ranges| {
+                    ranges
+                        .into_iter()
+                        .map(|range| (range.start, range.end))
+                        .collect::<Vec<_>>()
+                })
+        };
+        assert_eq!(
+            ranges("*report*.PDF", "q3_Report_final.pdf"),
+            Some(vec![(3, 9), (15, 19)])
+        );
+        assert_eq!(ranges("r?port*", "Report.txt"), Some(vec![(0, 6)]));
+        assert_eq!(ranges("*.mp3", "song.mp4"), None);
+        assert_eq!(ranges("caf?*", "Cafe\u{301} notes"), Some(vec![(0, 6)]));
+    }
+}